@@ -1,8 +1,8 @@
 mod parser;
 mod compiler;
 mod symex;
+mod testgen;
 
-use std::collections::HashSet;
 use std::env;
 use std::fs;
 use crate::parser::*;
@@ -10,40 +10,209 @@ use crate::parser::parser::parse_file;
 use crate::parser::parser::Execute;
 use ParseResult::*;
 
-//static PATH_TO_SOLVER:&str = "z3\\bin\\z3";
+/// The command line options.
+struct Options {
+    filename: String,
+    /// Write a test module for the file instead of printing the results.
+    gen_tests: bool,
+    /// How many calls deep to inline before treating calls as unknown.
+    max_call_depth: usize,
+    /// Instantiate a summary of each called function instead of inlining it.
+    summaries: bool,
+    /// Print the inputs that make each function return this value.
+    returns: Option<i128>,
+    /// The solver to run.
+    solver: symex::SolverConfig,
+    /// The order pending paths are explored in.
+    strategy: symex::Strategy,
+    /// The seed of the random-path strategy.
+    seed: Option<u64>,
+    /// The limits on the whole run.
+    budget: symex::Budget,
+    /// Merge the paths an if forks back into one where it ends.
+    merge: bool,
+    /// Run the rest of a loop at once when it reaches the iteration limit.
+    loop_summaries: bool,
+}
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Options, String> {
+        let mut filename = None;
+        let mut gen_tests = false;
+        let mut max_call_depth = 8;
+        let mut summaries = false;
+        let mut returns = None;
+        let mut solver = symex::SolverConfig::new();
+        let mut strategy = symex::Strategy::default();
+        let mut seed = None;
+        let mut budget = symex::Budget::new();
+        let mut merge = false;
+        let mut loop_summaries = false;
+
+        let mut args = args.into_iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--gen-tests" => gen_tests = true,
+                "--summaries" => summaries = true,
+                "--merge" => merge = true,
+                "--loop-summaries" => loop_summaries = true,
+                "--max-call-depth" => {
+                    max_call_depth = args.next()
+                        .and_then(|v| str::parse(&v).ok())
+                        .ok_or("--max-call-depth expects a number")?;
+                },
+                "--returns" => {
+                    returns = Some(args.next()
+                        .and_then(|v| str::parse(&v).ok())
+                        .ok_or("--returns expects a number")?);
+                },
+                "--solver" => {
+                    let kind = args.next()
+                        .and_then(|v| symex::SolverKind::from_name(&v))
+                        .ok_or("--solver expects z3, cvc5, yices or builtin")?;
+                    solver = solver.with_kind(Some(kind));
+                },
+                "--solver-path" => {
+                    solver = solver.with_path(Some(args.next().ok_or("--solver-path expects a path")?));
+                },
+                "--solver-timeout" => {
+                    let ms = args.next()
+                        .and_then(|v| str::parse(&v).ok())
+                        .ok_or("--solver-timeout expects a number of milliseconds")?;
+                    solver = solver.with_timeout_ms(Some(ms));
+                },
+                "--strategy" => {
+                    strategy = args.next()
+                        .and_then(|v| symex::Strategy::from_name(&v))
+                        .ok_or("--strategy expects dfs, bfs, random or coverage")?;
+                },
+                "--seed" => {
+                    seed = Some(args.next()
+                        .and_then(|v| str::parse(&v).ok())
+                        .ok_or("--seed expects a number")?);
+                },
+                "--max-paths" => {
+                    let max = args.next()
+                        .and_then(|v| str::parse(&v).ok())
+                        .ok_or("--max-paths expects a number")?;
+                    budget = budget.with_max_paths(Some(max));
+                },
+                "--max-solver-time" => {
+                    let ms = args.next()
+                        .and_then(|v| str::parse(&v).ok())
+                        .ok_or("--max-solver-time expects a number of milliseconds")?;
+                    budget = budget.with_max_solver_time(Some(std::time::Duration::from_millis(ms)));
+                },
+                "--max-time" => {
+                    let ms = args.next()
+                        .and_then(|v| str::parse(&v).ok())
+                        .ok_or("--max-time expects a number of milliseconds")?;
+                    budget = budget.with_max_time(Some(std::time::Duration::from_millis(ms)));
+                },
+                _ if filename.is_none() => filename = Some(arg),
+                _ => return Err(format!("Unexpected argument {}", arg)),
+            }
+        }
+
+        Ok(Options { filename: filename.ok_or("File name expected")?, gen_tests, max_call_depth, summaries, returns, solver, strategy, seed, budget, merge, loop_summaries })
+    }
 
-    if args.len() != 2 {
-        println!("File name expected");
-        return;
+    fn execute_args<'a>(&self, text: &'a str) -> parser::parser::ExecuteArgs<'a, str> {
+        parser::parser::ExecuteArgs::new(text)
+            .with_max_call_depth(self.max_call_depth)
+            .with_summaries(self.summaries.then(symex::SummaryCache::default))
+            .with_returns(self.returns)
+            .with_solver(std::rc::Rc::new(self.solver.clone()))
+            .with_scheduler(std::rc::Rc::new(std::cell::RefCell::new(self.scheduler())))
+            .with_merge(self.merge)
+            .with_loop_summaries(self.loop_summaries)
     }
 
-    let filename = args[1].clone();
+    fn scheduler(&self) -> symex::Scheduler {
+        let scheduler = symex::Scheduler::new(self.strategy, self.budget.clone());
+        match self.seed {
+            Some(seed) => scheduler.with_seed(seed),
+            None => scheduler,
+        }
+    }
+}
 
-    let valid = compiler::compile_input(&filename);
-        if valid {
-           run_parser(filename);
+fn main() {
+    let options = match Options::parse(env::args().collect()) {
+        Ok(options) => options,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        },
+    };
+
+    let valid = compiler::compile_input(&options.filename);
+        if valid && options.gen_tests {
+           run_gen_tests(&options);
+        } else if valid {
+           run_parser(&options);
         } else {
             println!("Could not compile");
         }
 }
 
-fn run_parser(filename: String) {
-
+fn run_gen_tests(options: &Options) {
+    let filename = &options.filename;
     let t = fs::read_to_string(filename).expect("Could not read");
     let text = t.as_str();
+    match parse_file(text) {
+        Okay(value, _) => {
+            let out = filename.strip_suffix(".rs").unwrap_or(filename).to_string() + "_symex_tests.rs";
+            fs::write(&out, testgen::gen_tests(&value, options.execute_args(text))).expect("Could not write tests");
+            println!("Wrote tests to {}", out);
+            // include! paths are relative to the including file
+            let name = std::path::Path::new(&out).file_name().and_then(|n| n.to_str()).unwrap_or(&out);
+            println!("Add include!(\"{}\"); to the end of {} to run them", name, filename);
+        },
+        Error(error) => panic!("Error: {}", error),
+        Panic(error) => panic!("Panic: {}", error),
+    }
+}
+
+fn run_parser(options: &Options) {
+
+    let t = fs::read_to_string(&options.filename).expect("Could not read");
+    let text = t.as_str();
     match parse_file(text) {
         Okay(value, _) => {
             let mut engine = Vec::new();
-            let _result = value.execute(&mut engine, parser::parser::ExecuteArgs { store: text, ids: HashSet::from([0]), max_loop_iter: 100 });
-            let mut i = 0;
-            while i < engine.len() {
-                if engine[i].pi.satisfiable && engine[i].reached_symex {
-                  println!("{}", engine[i].to_string());
+            let args = options.execute_args(text);
+            let scheduler = args.scheduler.clone();
+            let result = match value.execute(&mut engine, args) {
+                Ok(result) => result,
+                Err(err) => {
+                    println!("{}", err);
+                    return;
+                },
+            };
+            for res in result.res.iter() {
+                match res {
+                    parser::parser::SymexRes::Symex { model: Some(_), .. } => println!("{}\n", res),
+                    parser::parser::SymexRes::Panic { .. } => println!("{}\n", res),
+                    parser::parser::SymexRes::UnknownPanic { .. } => println!("{}\n", res),
+                    parser::parser::SymexRes::Returns { .. } => println!("{}\n", res),
+                    parser::parser::SymexRes::Query { .. } => println!("{}\n", res),
+                    parser::parser::SymexRes::Frontier { .. } => println!("{}\n", res),
+                    parser::parser::SymexRes::MatchNotExhaustive { .. } => println!("{}\n", res),
+                    parser::parser::SymexRes::UnreachableArm { .. } => println!("{}\n", res),
+                    _ => {},
                 }
-                i = i + 1;
+            }
+
+            let pruned = scheduler.borrow().pruned();
+            if pruned > 0 {
+                println!("Pruned {} infeasible paths", pruned);
+            }
+
+            let exhausted = scheduler.borrow().exhausted;
+            if let Some(reason) = exhausted {
+                let frontier = result.res.iter().filter(|res| matches!(res, parser::parser::SymexRes::Frontier { .. })).count();
+                println!("Stopped because {}: {} paths left unexplored", reason, frontier);
             }
         },
         Error(error) => panic!("Error: {}", error),
@@ -54,20 +223,21 @@ fn run_parser(filename: String) {
 #[cfg(test)] 
 mod test {
 
-    use rsmt2::*;
+    use crate::symex::SolverConfig;
 
     #[test]
-    fn rsmt2_test() -> Result<(), Box<dyn std::error::Error>> {
-        let parser = ();
-        let conf = SmtConf::z3("z3\\bin\\z3.exe");
-        let mut solver = conf.spawn(parser).unwrap();
+    fn solver_test() -> Result<(), Box<dyn std::error::Error>> {
+        let mut solver = SolverConfig::new().spawn()?;
 
-        solver.declare_const("n", "Int")?;
-        solver.declare_const("m", "Int")?;
-        solver.assert("(= 4 5)")?;
+        solver.declare_const("n", "(_ BitVec 32)")?;
+        solver.declare_const("m", "(_ BitVec 32)")?;
+        solver.assert("(= (bvadd n m) (_ bv4 32))")?;
+        assert_eq!(solver.check_sat_or_unk()?, Some(true));
 
-        let is_sat = solver.check_sat()?;
-        assert!(is_sat);
+        solver.push(1)?;
+        solver.assert("(= (_ bv4 32) (_ bv5 32))")?;
+        assert_eq!(solver.check_sat_or_unk()?, Some(false));
+        solver.pop(1)?;
         Ok(())
     }
 }
\ No newline at end of file
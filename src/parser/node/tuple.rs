@@ -26,16 +26,16 @@ macro_rules! impl_tuple {
                 ), cxt.pos)
             }
 
-            fn parse_span<'a>(&self, cxt: ParseContext<'a, Store, Pos, V>) -> ParseResult<Span<Pos>, Err, Pos> {
-                let mut _curr_pos = cxt.pos.clone();
+            fn parse_span<'a>(&self, mut cxt: ParseContext<'a, Store, Pos, V>) -> ParseResult<Span<Pos>, Err, Pos> {
+                let start_pos = cxt.pos.clone();
 
                 $(match self.$num.parse_span(cxt.clone()) {
-                    ParseResult::Okay(_, new_pos) => { _curr_pos = new_pos; },
+                    ParseResult::Okay(_, new_pos) => { cxt.pos = new_pos; },
                     ParseResult::Error(err) => { return Error(err) },
                     ParseResult::Panic(err) => { return Panic(err) },
                 });*
 
-                Okay(Span::new(cxt.pos, _curr_pos.clone()), _curr_pos)
+                Okay(Span::new(start_pos, cxt.pos.clone()), cxt.pos)
             }
         }
     };
@@ -1,12 +1,13 @@
 #![allow(unused)]
-use std::{fmt::Display, collections::HashSet};
+use std::{fmt::Display, collections::{HashSet, HashMap}, rc::Rc};
 
-use crate::{parser::{ParseContext, Not, AnyV, Funnel4, AnyMemTable, Funnel6, Join, Funnel2, OneOf8, MapPValue, Funnel9, LRJoin, Funnel3, Funnel8, OneOf11, RLJoin, Funnel12, Funnel, AnyOf4, AnyOf11, AnyOf8, OJoin}, srule, symex::{SymExEngine, self, new_assert}};
+use crate::{parser::{ParseContext, Not, AnyV, Funnel4, AnyMemTable, Funnel6, Join, Funnel2, OneOf8, MapPValue, Funnel9, LRJoin, Funnel3, Funnel8, OneOf11, RLJoin, Funnel12, Funnel13, Funnel15, Funnel16, Funnel, AnyOf4, AnyOf11, AnyOf8, OJoin}, srule, symex::{SymExEngine, self, new_assert, IntTy, SymVar, SymModel, FnSummary, SummaryCache, SymTerm, SolverConfig, SolverError, CheckResult, Scheduler, SchedulerRef, Pending, LoopSummary, LoopExit, array_cell, array_cells, tuple_cell, field_cell, tag_cell, tag_of}};
 
 use super::{ParseResult, Span, ZeroOrMore, SpanOf, Map, OneOf3, Spanned, OneOrMore, AnyOf3, Maybe, AnyOf2, MapV, OneOf6, Leader, Surround, End, Req, OneOf5, OneOf4, OneOf2, ParsePos, ParseStore};
 
 use ParseResult::*;
 use unicode_xid::UnicodeXID;
+use thiserror::Error;
 
 /// 
 /// A parse position the parser.
@@ -91,7 +92,14 @@ impl ParseStore<PPos, char> for &str {
     }
 }
 
-type ExErr = ();
+/// 
+/// Why executing a function failed.
+/// 
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ExErr {
+    #[error(transparent)]
+    Solver(#[from] SolverError),
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ExOk {
@@ -105,6 +113,63 @@ pub enum SymexRes {
     Symex {
         symex_pos: Span<PPos>,
         res: String,
+        /// Inputs that reach the marker along this path (`None` if it is
+        /// infeasible).
+        model: Option<SymModel>,
+    },
+    /// A feasible path through a function that ended (returned or fell off
+    /// the end) without panicking, along with inputs that take it and the
+    /// value it returns for them (if the function returns an int).
+    /// `truncated` paths hit the loop iteration limit, so the program does
+    /// not actually end this way for those inputs.
+    Path {
+        path: u32,
+        model: SymModel,
+        ret: Option<String>,
+        truncated: bool,
+    },
+    /// The path `path` through `fn_name` returns `value` for the inputs in
+    /// `model`. Only reported when asked for with `ExecuteArgs::returns`.
+    Returns {
+        fn_name: String,
+        path: u32,
+        value: i128,
+        model: SymModel,
+    },
+    /// The answer to the query of the `//symex` comment at `symex_pos` (whose
+    /// text is `query`) on one path that reaches it.
+    Query {
+        symex_pos: Span<PPos>,
+        query: String,
+        outcome: QueryOutcome,
+    },
+    /// Evaluating the expression at `span` panics for the inputs in `model`.
+    Panic {
+        span: Span<PPos>,
+        kind: PanicKind,
+        model: SymModel,
+    },
+    /// The solver could not tell whether evaluating the expression at `span`
+    /// can panic with `kind`.
+    UnknownPanic {
+        span: Span<PPos>,
+        kind: PanicKind,
+    },
+    /// The path `path` through `fn_name` was suspended when the budget of
+    /// the run ran out, after assuming `pi`.
+    Frontier {
+        fn_name: String,
+        path: u32,
+        pi: String,
+    },
+    /// No arm of the match at `span` matches the inputs in `model`.
+    MatchNotExhaustive {
+        span: Span<PPos>,
+        model: SymModel,
+    },
+    /// The match arm at `span` is not taken on any path reaching it.
+    UnreachableArm {
+        span: Span<PPos>,
     },
     InfiniteLoopMaxIterHit,
     WhileLoopMaxIterHit,
@@ -115,10 +180,61 @@ impl Display for SymexRes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use SymexRes::*;
         match self {
-            Symex { symex_pos, res } => {
+            Symex { symex_pos, res, model } => {
                 Display::fmt(symex_pos, f)?;
                 f.write_str("\n")?;
                 f.write_str(res)?;
+                match model {
+                    Some(model) => write!(f, "inputs: {}", model)?,
+                    None => f.write_str("inputs: unreachable")?,
+                }
+            },
+            Path { path, model, ret, truncated } => {
+                write!(f, "path {}\ninputs: {}", path, model)?;
+                if let Some(ret) = ret {
+                    write!(f, "\nreturns: {}", ret)?;
+                }
+                if *truncated {
+                    f.write_str("\n(cut off by the loop iteration limit)")?;
+                }
+            },
+            Returns { fn_name, path, value, model } => {
+                write!(f, "{} returns {} on path {}\ninputs: {}", fn_name, value, path, model)?;
+            },
+            Query { symex_pos, query, outcome } => {
+                Display::fmt(symex_pos, f)?;
+                write!(f, "\n{}\n", query)?;
+                match outcome {
+                    QueryOutcome::Proved => f.write_str("holds on this path")?,
+                    QueryOutcome::Counterexample(model) => write!(f, "fails for inputs: {}", model)?,
+                    QueryOutcome::Reached(model) => write!(f, "reached with inputs: {}", model)?,
+                    QueryOutcome::Unreachable => f.write_str("unreachable")?,
+                    QueryOutcome::Unknown => f.write_str("unknown: the solver could not tell")?,
+                    QueryOutcome::NonBoolAssert => f.write_str("error: does not assert a boolean expression")?,
+                }
+            },
+            Panic { span, kind, model } => {
+                Display::fmt(span, f)?;
+                f.write_str("\n")?;
+                Display::fmt(kind, f)?;
+                write!(f, "\ninputs: {}", model)?;
+            },
+            UnknownPanic { span, kind } => {
+                Display::fmt(span, f)?;
+                f.write_str("\n")?;
+                Display::fmt(kind, f)?;
+                f.write_str("\nthe solver could not tell whether this can happen")?;
+            },
+            Frontier { fn_name, path, pi } => {
+                write!(f, "{} path {} left unexplored\npi: {}", fn_name, path, pi)?;
+            },
+            MatchNotExhaustive { span, model } => {
+                Display::fmt(span, f)?;
+                write!(f, "\nno match arm matches\ninputs: {}", model)?;
+            },
+            UnreachableArm { span } => {
+                Display::fmt(span, f)?;
+                f.write_str("\nmatch arm is unreachable")?;
             },
             InfiniteLoopMaxIterHit => f.write_str("Infinite loop hit iteration limit")?,
             WhileLoopMaxIterHit    => f.write_str("While loop hit iteration limit")?,
@@ -129,13 +245,155 @@ impl Display for SymexRes {
     }
 }
 
+/// 
+/// The answer to a `//symex` query on a path that reaches it.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum QueryOutcome {
+    /// The assertion holds for every input that takes the path.
+    Proved,
+    /// The assertion fails for these inputs.
+    Counterexample(SymModel),
+    /// These inputs reach the comment.
+    Reached(SymModel),
+    /// No feasible path reaches the comment.
+    Unreachable,
+    /// The solver could not tell whether the path reaches the comment or
+    /// whether the assertion holds on it.
+    Unknown,
+    /// The expression asserted is not a boolean, so the query cannot be
+    /// answered.
+    NonBoolAssert,
+}
+
+/// 
+/// Why evaluating an expression panics. Displays as the message rustc's
+/// debug-mode checks panic with.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PanicKind {
+    /// The arithmetic or shift operator overflowed its type.
+    Overflow(BinOp),
+    /// The minimum value of a signed type was negated.
+    NegOverflow,
+    /// `/` with a divisor of zero.
+    DivByZero,
+    /// `%` with a divisor of zero.
+    RemByZero,
+    /// The condition of an `assert!` or `debug_assert!` was false. Holds
+    /// (the start of) the panic message.
+    AssertFailed(String),
+    /// A `panic!` was reached. Holds (the start of) the panic message.
+    Explicit(String),
+    /// An `unreachable!` was reached. Holds (the start of) the panic message.
+    Unreachable(String),
+    /// An array of `len` items was indexed past its end, by `index` when
+    /// known.
+    IndexOutOfBounds { len: usize, index: Option<String> },
+    /// `unwrap` or `expect` was called on a `None` or `Err`. Holds (the
+    /// start of) the panic message.
+    Unwrap(String),
+}
+
+impl PanicKind {
+    /// A short name for the condition, used when it is assumed not to happen
+    /// in `pi`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PanicKind::Overflow(_) | PanicKind::NegOverflow => "overflow",
+            PanicKind::DivByZero | PanicKind::RemByZero => "div_by_zero",
+            PanicKind::AssertFailed(_) => "assert",
+            PanicKind::Explicit(_) => "panic",
+            PanicKind::Unreachable(_) => "unreachable",
+            PanicKind::IndexOutOfBounds { .. } => "index",
+            PanicKind::Unwrap(_) => "unwrap",
+        }
+    }
+}
+
+impl Display for PanicKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use PanicKind::*;
+        let op = match self {
+            Overflow(BinOp::Add) => "add",
+            Overflow(BinOp::Sub) => "subtract",
+            Overflow(BinOp::Mul) => "multiply",
+            Overflow(BinOp::Div) => "divide",
+            Overflow(BinOp::Mod) => "calculate the remainder",
+            Overflow(BinOp::LSh) => "shift left",
+            Overflow(BinOp::RSh) => "shift right",
+            Overflow(op) => op.as_ref(),
+            NegOverflow => "negate",
+            DivByZero => return f.write_str("attempt to divide by zero"),
+            RemByZero => return f.write_str("attempt to calculate the remainder with a divisor of zero"),
+            AssertFailed(message) | Explicit(message) | Unreachable(message) | Unwrap(message) => return f.write_str(message),
+            IndexOutOfBounds { len, index: Some(index) } => return write!(f, "index out of bounds: the len is {} but the index is {}", len, index),
+            IndexOutOfBounds { len, index: None } => return write!(f, "index out of bounds: the len is {}", len),
+        };
+        write!(f, "attempt to {} with overflow", op)
+    }
+}
+
 pub struct ExecuteArgs<'a, Store: ParseStore<PPos, char> + ?Sized> {
     pub store: &'a Store,
     pub ids: HashSet<usize>,
     pub max_loop_iter: usize,
+    /// The integer type the surrounding context expects the executed
+    /// expression to have.
+    pub int_ty: IntTy,
+    /// The functions of the crate by name, which calls are inlined from.
+    pub fns: Rc<HashMap<String, RFn>>,
+    /// The structs of the crate by name, which give the fields of the
+    /// values of their type.
+    pub structs: Rc<HashMap<String, RStruct>>,
+    /// The enums of the crate by name, which give the variants of the
+    /// values of their type.
+    pub enums: Rc<HashMap<String, REnum>>,
+    /// How many calls deep the executed code is.
+    pub call_depth: usize,
+    /// The call depth after which calls return an unconstrained value instead
+    /// of being inlined.
+    pub max_call_depth: usize,
+    /// The summaries of the functions called so far, if calls are
+    /// instantiated from summaries instead of being inlined.
+    pub summaries: Option<SummaryCache>,
+    /// A value to find the inputs that make each path return, reported as
+    /// `SymexRes::Returns`.
+    pub returns: Option<i128>,
+    /// The solver each new engine starts.
+    pub solver: Rc<SolverConfig>,
+    /// Orders the pending paths and enforces the budget of the run.
+    pub scheduler: SchedulerRef,
+    /// Whether the paths an if forks are merged back into one where it
+    /// ends.
+    pub merge: bool,
+    /// Whether a while loop still running after `max_loop_iter` iterations
+    /// is summarised to run the rest of its iterations at once, instead of
+    /// being cut off.
+    pub loop_summaries: bool,
 }
 
 impl <'a, Store: ParseStore<PPos, char> + ?Sized> ExecuteArgs<'a, Store> {
+    pub fn new(store: &'a Store) -> Self {
+        ExecuteArgs {
+            store,
+            ids: HashSet::new(),
+            max_loop_iter: 100,
+            int_ty: IntTy::I32,
+            fns: Rc::new(HashMap::new()),
+            structs: Rc::new(HashMap::new()),
+            enums: Rc::new(HashMap::new()),
+            call_depth: 0,
+            max_call_depth: 8,
+            summaries: None,
+            returns: None,
+            solver: Rc::new(SolverConfig::new()),
+            scheduler: SchedulerRef::default(),
+            merge: false,
+            loop_summaries: false,
+        }
+    }
+
     pub fn with_ids(mut self, ids: HashSet<usize>) -> Self {
         self.ids = ids;
         self
@@ -145,6 +403,66 @@ impl <'a, Store: ParseStore<PPos, char> + ?Sized> ExecuteArgs<'a, Store> {
         self.max_loop_iter = max_loop_iter;
         self
     }
+
+    pub fn with_int_ty(mut self, int_ty: IntTy) -> Self {
+        self.int_ty = int_ty;
+        self
+    }
+
+    pub fn with_fns(mut self, fns: Rc<HashMap<String, RFn>>) -> Self {
+        self.fns = fns;
+        self
+    }
+
+    pub fn with_structs(mut self, structs: Rc<HashMap<String, RStruct>>) -> Self {
+        self.structs = structs;
+        self
+    }
+
+    pub fn with_enums(mut self, enums: Rc<HashMap<String, REnum>>) -> Self {
+        self.enums = enums;
+        self
+    }
+
+    pub fn with_call_depth(mut self, call_depth: usize) -> Self {
+        self.call_depth = call_depth;
+        self
+    }
+
+    pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+
+    pub fn with_summaries(mut self, summaries: Option<SummaryCache>) -> Self {
+        self.summaries = summaries;
+        self
+    }
+
+    pub fn with_returns(mut self, returns: Option<i128>) -> Self {
+        self.returns = returns;
+        self
+    }
+
+    pub fn with_solver(mut self, solver: Rc<SolverConfig>) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    pub fn with_scheduler(mut self, scheduler: SchedulerRef) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    pub fn with_loop_summaries(mut self, loop_summaries: bool) -> Self {
+        self.loop_summaries = loop_summaries;
+        self
+    }
 }
 
 impl <'a, Store: ParseStore<PPos, char> + ?Sized> Clone for ExecuteArgs<'a, Store> {
@@ -153,6 +471,18 @@ impl <'a, Store: ParseStore<PPos, char> + ?Sized> Clone for ExecuteArgs<'a, Stor
             store: self.store,
             ids: self.ids.clone(),
             max_loop_iter: self.max_loop_iter,
+            int_ty: self.int_ty,
+            fns: self.fns.clone(),
+            structs: self.structs.clone(),
+            enums: self.enums.clone(),
+            call_depth: self.call_depth,
+            max_call_depth: self.max_call_depth,
+            summaries: self.summaries.clone(),
+            returns: self.returns,
+            solver: self.solver.clone(),
+            scheduler: self.scheduler.clone(),
+            merge: self.merge,
+            loop_summaries: self.loop_summaries,
         }
     }
 }
@@ -164,6 +494,10 @@ pub trait Execute<Store: ParseStore<PPos, char> + ?Sized> {
 impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RCrate {
     fn execute<'a>(&self, engine: &mut Vec<SymExEngine>, args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
         let mut results = Vec::new();
+        let fns = Rc::new(self.fn_map(args.store));
+        let structs = Rc::new(self.struct_map(args.store));
+        let enums = Rc::new(self.enum_map(args.store));
+        let args = args.with_fns(fns).with_structs(structs).with_enums(enums);
 
         for item in self.items.iter() {
             let res = item.execute(engine, args.clone().with_ids(HashSet::new()))?;
@@ -178,6 +512,9 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RItem {
     fn execute<'a>(&self, engine: &mut Vec<SymExEngine>,  args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
         match self {
             RItem::Fn {span, vis, val} => val.execute(engine, args),
+            // a struct or enum only gives the shape of the values of its
+            // type
+            RItem::Struct { .. } | RItem::Enum { .. } => Ok(ExOk { cont: true, res: Vec::new(), continues: HashSet::new() }),
         }
     }
 }
@@ -185,7 +522,12 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RItem {
 impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RFn {
     fn execute<'a>(&self, engine: &mut Vec<SymExEngine>,  args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
         let fn_name = &self.id.into_string(args.store);
-        let ids = HashSet::from([symex::new_engine(engine, fn_name)]);
+        let id = symex::new_engine(engine, fn_name, &args.solver)?;
+        args.scheduler.borrow_mut().track(engine[id].pi.clock());
+        let ids = HashSet::from([id]);
+
+        let ret_ty = self.ret_int_ty(args.store);
+        engine[id].ret_ty = ret_ty;
 
         // add the params
         for arg in &self.args {
@@ -193,10 +535,147 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RFn {
         }
 
         // execute the body now that we have the params
-        let mut res = self.body.execute(engine, args.clone().with_ids(ids))?;
-        res.continues.clear();
+        let mut res = self.execute_body(engine, args.clone().with_ids(ids))?;
+
+        let mut paths: Vec<usize> = res.continues.drain().collect();
+        paths.sort();
+        for id in paths {
+            if engine[id].suspended {
+                if engine[id].pi.satisfiable {
+                    res.res.push(SymexRes::Frontier { fn_name: fn_name.clone(), path: engine[id].path, pi: engine[id].pi.to_string() });
+                }
+                continue;
+            }
+            if let Some((model, ret)) = engine[id].model_with_ret() {
+                res.res.push(SymexRes::Path { path: engine[id].path, model, ret, truncated: engine[id].truncated });
+            }
+            if let Some(value) = args.returns {
+                if let Some(model) = engine[id].check_return(value) {
+                    res.res.push(SymexRes::Returns { fn_name: fn_name.clone(), path: engine[id].path, value, model });
+                }
+            }
+        }
+
+        merge_unreachable_queries(&mut res.res);
+        Ok(res)
+    }
+}
+
+/// 
+/// Decides the reachability of each query over a whole run: a query is only
+/// unreachable if no path reached it, whichever executions of it (loop
+/// iterations, calls, pruned branches) found no path. Drops the `Unreachable`
+/// results of queries that some path reached and keeps one for the others.
+/// 
+fn merge_unreachable_queries(results: &mut Vec<SymexRes>) {
+    let reached: Vec<Span<PPos>> = results.iter().filter_map(|res| match res {
+        SymexRes::Query { symex_pos, outcome, .. } if *outcome != QueryOutcome::Unreachable => Some(symex_pos.clone()),
+        _ => None,
+    }).collect();
+
+    let mut unreachable: Vec<Span<PPos>> = Vec::new();
+    results.retain(|res| match res {
+        SymexRes::Query { symex_pos, outcome: QueryOutcome::Unreachable, .. } => {
+            if reached.contains(symex_pos) || unreachable.contains(symex_pos) {
+                return false;
+            }
+            unreachable.push(symex_pos.clone());
+            true
+        },
+        _ => true,
+    });
+}
+
+impl RFn {
+    /// The int (or `bool`) type this function returns, if it returns one.
+    pub fn ret_int_ty<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Option<IntTy> {
+        match &self.ret_type {
+            Some(RReturnType::Type(ty)) => IntTy::from_scalar_str(&ty.into_string(store)),
+            _ => None,
+        }
+    }
+
+    /// 
+    /// Executes the body of this function on the paths `args.ids`, whose
+    /// parameters must already be in `sigma`, and sets the return value of
+    /// every path the body ends on. Those paths are the `continues` of the
+    /// result.
+    /// 
+    fn execute_body<'a, Store: ParseStore<PPos, char> + ?Sized>(&self, engine: &mut Vec<SymExEngine>, args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
+        let ret_ty = self.ret_int_ty(args.store);
+        let res = self.body.execute(engine, args.clone().with_int_ty(ret_ty.unwrap_or(IntTy::I32)))?;
+
+        // the tail expression is the return value of every path that did not
+        // return early
+        for id in res.continues.iter().copied() {
+            if engine[id].returned {
+                continue;
+            }
+
+            let ret = ret_ty.and_then(|ret_ty| {
+                let (value, ty) = self.body.value(args.store, &engine[id].sigma, ret_ty)?;
+                Some(value.cast(ty, ret_ty))
+            });
+            engine[id].set_ret(ret);
+        }
+
         Ok(res)
     }
+
+    /// 
+    /// Returns the summary of this function (named `name`) from
+    /// `args.summaries`, summarising it first if it has not been called
+    /// before. Returns `None` if summaries are off, if this function is
+    /// being summarised already (it is recursive) or if it could not be.
+    /// 
+    fn cached_summary<'a, Store: ParseStore<PPos, char> + ?Sized>(&self, name: &str, args: ExecuteArgs<'a, Store>) -> Option<Rc<FnSummary>> {
+        let cache = args.summaries.clone()?;
+        if let Some(summary) = cache.borrow().get(name) {
+            return summary.clone();
+        }
+
+        cache.borrow_mut().insert(name.to_string(), None);
+        // a summary cut short by the budget misses paths
+        let summary = self.summarise(args).ok().filter(|summary| !summary.suspended).map(Rc::new);
+        cache.borrow_mut().insert(name.to_string(), summary.clone());
+        summary
+    }
+
+    /// 
+    /// Executes this function on symbolic parameters and returns its summary:
+    /// the assertions and return value of every path through it and the
+    /// assertions under which each of its panics happens. Only functions
+    /// whose parameters are all ints can be summarised.
+    /// 
+    pub fn summarise<'a, Store: ParseStore<PPos, char> + ?Sized>(&self, args: ExecuteArgs<'a, Store>) -> Result<FnSummary, ExErr> {
+        let mut engine = Vec::new();
+        let id = symex::new_engine(&mut engine, &self.id.into_string(args.store), &args.solver)?;
+        args.scheduler.borrow_mut().track(engine[id].pi.clock());
+        engine[id].ret_ty = self.ret_int_ty(args.store);
+
+        for arg in &self.args {
+            arg.execute(&mut engine, args.clone().with_ids(HashSet::from([id])))?;
+        }
+        let res = self.execute_body(&mut engine, args.clone().with_ids(HashSet::from([id])))?;
+
+        let suspended = engine.iter().any(|e| e.suspended);
+        let mut summary = FnSummary { params: engine[id].params.clone(), paths: Vec::new(), panics: Vec::new(), suspended };
+
+        let mut paths: Vec<usize> = res.continues.into_iter().collect();
+        paths.sort();
+        for id in paths {
+            if engine[id].pi.satisfiable {
+                let path = summary.path(&engine[id].pi, engine[id].ret.clone(), engine[id].truncated);
+                summary.paths.push(path);
+            }
+        }
+
+        for e in engine.iter_mut() {
+            summary.panics.append(&mut e.panics);
+        }
+
+        Ok(summary)
+    }
 }
 
 impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RBlock {
@@ -204,17 +683,30 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RBlock {
         let mut results = Vec::new();
         let mut continues = args.ids.clone();
 
-        for id in args.ids.clone() {
-            let mut internal_continues = HashSet::from([id]);
+        // every path waits here for its next statement until the scheduler
+        // picks it; paths that returned skip the rest of the function
+        let mut roots: Vec<usize> = args.ids.iter().copied().collect();
+        roots.sort();
+        let mut worklist: Vec<Pending> = roots.into_iter().map(|id| (id, 0)).collect();
+
+        loop {
+            let next = args.scheduler.borrow_mut().pick(engine, &mut worklist);
+            let Some((id, i)) = next else {
+                break;
+            };
+            let Some(stmt) = self.statements.get(i) else {
+                continue;
+            };
+
+            let res = stmt.execute(engine, args.clone().with_ids(HashSet::from([id])))?;
+            results.extend(res.res);
+            continues.extend(res.continues.iter().copied());
 
-            for stmt in &self.statements {
-                let res = stmt.execute(engine, args.clone().with_ids(internal_continues.clone()))?;
-                results.extend(res.res);
-                internal_continues.extend(res.continues.clone());
-                continues.extend(res.continues);
-                if !res.cont {
-                    break;
-                }
+            if res.cont {
+                let mut next: Vec<usize> = res.continues.into_iter().chain([id]).collect();
+                next.sort();
+                next.dedup();
+                worklist.extend(next.into_iter().map(|id| (id, i + 1)));
             }
         }
 
@@ -222,12 +714,260 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RBlock {
     }
 }
 
+impl RBlock {
+    /// 
+    /// The value of this block on a path that ran to its end, as a term in
+    /// terms of `sigma` along with its type (see `RExpr::value`): that of
+    /// its tail expression, or of the if or loop it ends in.
+    /// 
+    pub fn value<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar], ty: IntTy) -> Option<(SymTerm, IntTy)> {
+        match self.statements.last()? {
+            RStatement::Expr { expr, semi: None } => expr.value(store, sigma, ty),
+            RStatement::If { stmt } => stmt.value(store, sigma),
+            RStatement::Loop { stmt } => {
+                let symbol = stmt.value_symbol(store);
+                sigma.iter().find(|var| var.name == symbol).map(|var| (SymTerm::Var(symbol), var.ty))
+            },
+            _ => None,
+        }
+    }
+
+    /// 
+    /// Reports every query in this block, and in the blocks nested in it, as
+    /// unreachable. Used for blocks a path does not enter; the function keeps
+    /// these only for queries no other path reaches (see
+    /// `merge_unreachable_queries`).
+    /// 
+    pub fn unreachable_queries<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Vec<SymexRes> {
+        let mut res = Vec::new();
+        for stmt in &self.statements {
+            match stmt {
+                RStatement::Comment { comment: RComment::Symex { symex, follow, query: Some(_) } } => {
+                    res.push(SymexRes::Query {
+                        symex_pos: symex.clone(),
+                        query: follow.into_string(store).trim().to_string(),
+                        outcome: QueryOutcome::Unreachable,
+                    });
+                },
+                RStatement::If { stmt } => res.extend(stmt.unreachable_queries(store)),
+                RStatement::Expr { expr: RExpr::If(stmt), .. } => res.extend(stmt.unreachable_queries(store)),
+                RStatement::Loop { stmt } => res.extend(stmt.block().unreachable_queries(store)),
+                RStatement::Expr { expr: RExpr::Loop(stmt), .. } => res.extend(stmt.block().unreachable_queries(store)),
+                RStatement::Expr { expr: RExpr::Block(block), .. } => res.extend(block.unreachable_queries(store)),
+                RStatement::Expr { expr: RExpr::Match(stmt), .. } => res.extend(stmt.unreachable_queries(store)),
+                _ => {},
+            }
+        }
+        res
+    }
+}
+
+impl RIf {
+    /// 
+    /// Merges the paths `ends` that an if executed on `path` ended on, along
+    /// with `path` itself, into as few paths as possible. Each is merged into
+    /// the first path before it that it can be merged with, and is dropped
+    /// from `ends` if it was.
+    /// 
+    fn merge_ends(engine: &mut [SymExEngine], path: usize, ends: &mut HashSet<usize>) {
+        let mut ids: Vec<usize> = ends.iter().copied().chain([path]).collect();
+        ids.sort();
+        ids.dedup();
+
+        let mut kept: Vec<usize> = Vec::new();
+        for id in ids {
+            if !kept.iter().any(|into| symex::merge_engines(engine, *into, id)) {
+                kept.push(id);
+            }
+        }
+        ends.retain(|id| kept.contains(id));
+    }
+
+    /// 
+    /// The SMT-LIB symbol the value of this if is bound to in `sigma` once
+    /// a path has run one of its blocks: its source text as a quoted symbol,
+    /// as for calls.
+    /// 
+    pub fn value_symbol<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> String {
+        format!("|{}|", self.span.into_string(store).replace('|', "/"))
+    }
+
+    /// The value this if took on a path that ran it, and its type, if its
+    /// blocks have one (see `value_symbol`).
+    pub fn value<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar]) -> Option<(SymTerm, IntTy)> {
+        let symbol = self.value_symbol(store);
+        sigma.iter().find(|var| var.name == symbol).map(|var| (SymTerm::Var(symbol), var.ty))
+    }
+
+    /// 
+    /// Binds the value of `block`, run to its end by the paths `ids`, to
+    /// `value_symbol` on each of them that did not leave it otherwise.
+    /// 
+    fn bind_value<Store: ParseStore<PPos, char> + ?Sized>(&self, engine: &mut [SymExEngine], ids: impl IntoIterator<Item = usize>, block: &RBlock, store: &Store, ty: IntTy) {
+        for id in ids {
+            if !engine[id].pi.satisfiable || engine[id].returned || engine[id].exit.is_some() {
+                continue;
+            }
+            if let Some((value, ty)) = block.value(store, &engine[id].sigma, ty) {
+                let value = engine[id].term_as_var0(&value);
+                engine[id].bind_call(self.value_symbol(store), ty, value);
+            }
+        }
+    }
+
+    /// Reports every query in the blocks of this if as unreachable, like
+    /// `RBlock::unreachable_queries`.
+    pub fn unreachable_queries<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Vec<SymexRes> {
+        self.ifs.iter().map(|(_, block)| block).chain(self.else_stmt.iter())
+            .flat_map(|block| block.unreachable_queries(store))
+            .collect()
+    }
+}
+
+impl RLoop {
+    /// 
+    /// Summarises the while loop `while expr block` for `path`, by running an
+    /// iteration of it from unknown values on a copy of the path. Returns the
+    /// summary if the rest of the loop can be run at once with it (see
+    /// `LoopSummary::applies`), and it can tell when the iteration panics.
+    /// Infinite loops are never summarised, as without a condition there is
+    /// no telling after how many iterations they end, so paths still in one
+    /// after the limit are cut off.
+    /// 
+    fn summarise<'a, Store: ParseStore<PPos, char> + ?Sized>(engine: &mut Vec<SymExEngine>, path: usize, expr: &RExpr, block: &RBlock, args: ExecuteArgs<'a, Store>) -> Result<Option<LoopSummary>, ExErr> {
+        if !engine[path].pi.satisfiable || engine[path].returned || engine[path].suspended || engine[path].exit.is_some() {
+            return Ok(None);
+        }
+
+        // the copy is apart from the budget and results of the run, and
+        // dropped afterwards along with every path forked from it
+        let first = engine.len();
+        let id = symex::clone_engine(engine, path);
+        let vars: Vec<(String, IntTy)> = engine[id].sigma.iter().map(|var| (var.name.clone(), var.ty)).collect();
+        let mut pre = Vec::new();
+        for (i, (name, ty)) in vars.into_iter().enumerate() {
+            let before = engine[id].pi.fresh_int(name.trim_matches('|'), ty);
+            engine[id].sigma[i].var0 = SymTerm::Var(before.clone());
+            pre.push((name, before));
+        }
+        let cond = expr.to_term(args.store, &engine[id].sigma, IntTy::I32);
+        engine[id].new_assertion(&cond)?;
+        let result = block.execute(engine, args.clone().with_ids(HashSet::from([id])).with_scheduler(SchedulerRef::default()))?;
+
+        let ends: Vec<usize> = result.continues.union(&HashSet::from([id])).copied()
+            .filter(|end| engine[*end].pi.satisfiable)
+            .collect();
+        let mut summary = match ends[..] {
+            [end] if !engine[end].returned && engine[end].exit.is_none() => Some(LoopSummary::infer(&pre, &engine[end].sigma)),
+            _ => None,
+        };
+
+        // the panics are in terms of the constants of the path and those
+        // the copy declared, which are declared afresh wherever they are
+        // checked
+        let decls = engine[path].pi.decls();
+        let mut panics = Vec::new();
+        for e in engine[first..].iter_mut() {
+            e.pi.satisfiable = false;
+            for mut panic in e.panics.drain(..) {
+                panic.decls.retain(|decl| !decls.contains(decl) && !pre.iter().any(|(_, before)| *before == decl.0));
+                panics.push(panic);
+            }
+        }
+        if let Some(summary) = summary.as_mut() {
+            summary.panics = panics;
+        }
+
+        let cond = expr.to_term(args.store, &engine[path].sigma, IntTy::I32);
+        Ok(summary.filter(|summary| summary.applies(&cond) && summary.panics_known()))
+    }
+
+    /// 
+    /// The SMT-LIB symbol the value of this loop is bound to in `sigma` once
+    /// a path breaks out of it with `break value`: its source text as a
+    /// quoted symbol, as for calls.
+    /// 
+    pub fn value_symbol<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> String {
+        format!("|{}|", self.span().into_string(store).replace('|', "/"))
+    }
+
+    /// 
+    /// Takes the `break`s and `continue`s meant for this loop on the paths
+    /// `ids` that ran its body, and returns the paths that run it again:
+    /// those that finished the body or continued. The paths that break out
+    /// of it leave it, with the value of `break value` bound to
+    /// `value_symbol`. The others (returned, or leaving an outer loop) leave
+    /// it as they are.
+    /// 
+    fn take_exits<Store: ParseStore<PPos, char> + ?Sized>(&self, engine: &mut [SymExEngine], ids: impl IntoIterator<Item = usize>, store: &Store) -> HashSet<usize> {
+        let label = self.label().map(|label| label.into_string(store));
+        let mut looping = HashSet::new();
+        for id in ids {
+            if !engine[id].pi.satisfiable || engine[id].returned || engine[id].suspended {
+                continue;
+            }
+            match engine[id].exit.take() {
+                None => { looping.insert(id); },
+                Some(exit) if !exit.targets(label.as_deref()) => engine[id].exit = Some(exit),
+                Some(LoopExit::Continue { .. }) => { looping.insert(id); },
+                Some(LoopExit::Break { value, .. }) => {
+                    if let Some((value, ty)) = value {
+                        engine[id].bind_call(self.value_symbol(store), ty, value);
+                    }
+                },
+            }
+        }
+        looping
+    }
+
+    /// The body of this loop.
+    pub fn block(&self) -> &RBlock {
+        match self {
+            RLoop::Infinite { block, .. } | RLoop::While { block, .. } | RLoop::For { block, .. } => block,
+        }
+    }
+}
+
 impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RStatement {
     fn execute<'a>(&self, engine: &mut Vec<SymExEngine>,  mut args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
         use RStatement::*; 
         match self {
             Comment{comment} => {
                 match comment {
+                    RComment::Symex { symex, follow, query: Some(query) } => {
+                        let mut res = Vec::new();
+
+                        for id in args.ids.clone() {
+                            engine[id].reached_symex = true;
+                            let outcome = match (query, engine[id].check_condition(&SymTerm::Bool(true))) {
+                                (_, CheckResult::Unsat) => continue,
+                                (_, CheckResult::Unknown) => QueryOutcome::Unknown,
+                                (RSymexQuery::Reach { .. }, CheckResult::Sat(model)) => QueryOutcome::Reached(model),
+                                (RSymexQuery::Assert { expr, .. }, CheckResult::Sat(_)) if matches!(**expr, RExpr::Lit(RLit::Integer(_)))
+                                    || expr.int_ty(args.store, &engine[id].sigma).is_some_and(|ty| !ty.is_bool()) => QueryOutcome::NonBoolAssert,
+                                (RSymexQuery::Assert { expr, .. }, CheckResult::Sat(_)) => {
+                                    let cond = expr.to_term(args.store, &engine[id].sigma, IntTy::I32);
+                                    match engine[id].check_condition(&SymTerm::not(cond)) {
+                                        CheckResult::Sat(model) => QueryOutcome::Counterexample(model),
+                                        CheckResult::Unsat => QueryOutcome::Proved,
+                                        CheckResult::Unknown => QueryOutcome::Unknown,
+                                    }
+                                },
+                            };
+                            res.push(SymexRes::Query { symex_pos: symex.clone(), query: follow.into_string(args.store).trim().to_string(), outcome });
+                        }
+
+                        // no feasible path made it here
+                        if res.is_empty() {
+                            res.push(SymexRes::Query {
+                                symex_pos: symex.clone(),
+                                query: follow.into_string(args.store).trim().to_string(),
+                                outcome: QueryOutcome::Unreachable,
+                            });
+                        }
+
+                        Ok(ExOk { cont: true, res, continues: HashSet::new() })
+                    },
                     RComment::Symex { symex, .. } => {
                         let mut res = Vec::new();
 
@@ -236,7 +976,8 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RStatement {
 
                             res.push(SymexRes::Symex {
                                 symex_pos: symex.clone(),
-                                res: engine[id].to_string()
+                                res: engine[id].to_string(),
+                                model: engine[id].model(),
                             });
                         }
                         
@@ -247,24 +988,70 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RStatement {
             },
             Expr {expr, semi} => expr.execute(engine, args),
             Return { expr, .. } => {
-                let mut res = expr.execute(engine, args)?;
+                let mut res = expr.execute(engine, args.clone())?;
+                for id in args.ids.union(&res.continues).copied() {
+                    let ret = engine[id].ret_ty.map(|ty| expr.to_term(args.store, &engine[id].sigma, ty));
+                    engine[id].set_ret(ret);
+                }
                 res.cont = false;
                 Ok(res)
             },
             SColon { .. } => Ok(ExOk { cont: true, res: Vec::new(), continues: HashSet::new() }),
             If {stmt} => stmt.execute(engine, args),
             Loop {stmt} => stmt.execute(engine, args),
-            Assign {ident, ty, equal_value,..} => {
+            Assign {pattern, ty, equal_value,..} => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
+
+                let declared = ty.clone().map(|v|v.into_string(args.store));
+                let array_of = ty.as_ref().and_then(|ty| ty.array_of(args.store));
+
                 for id in args.ids.clone() {
-                    engine[id].new_variable_assign(
-                        ident.into_string(args.store),
-                        ty.clone().map(|v|v.into_string(args.store)).unwrap_or_else(||"i32".to_string()),
-                        equal_value.span().into_string(args.store),
-                        equal_value.into_lisp(args.store)
-                    );
+                    let ctx_ty = declared.as_deref()
+                        .and_then(IntTy::from_scalar_str)
+                        .or(array_of.map(|(ty, _)| ty))
+                        .or_else(|| equal_value.int_ty(args.store, &engine[id].sigma))
+                        .unwrap_or(IntTy::I32);
+
+                    let result = equal_value.execute(engine, args.clone().with_ids(HashSet::from([id])).with_int_ty(ctx_ty))?;
+                    res.res.extend(result.res);
+                    res.continues.extend(result.continues.iter().copied());
+
+                    // calls only have a type once they have been executed
+                    for id in result.continues.union(&HashSet::from([id])).copied() {
+                        let value = equal_value.match_value(args.store, &engine[id], &args.structs, &args.enums, ty.as_ref());
+                        if let (RPattern::Ident { ident, sub: None, .. }, MatchValue::Scalar(..) | MatchValue::Array(..)) = (&**pattern, &value) {
+                            let name = ident.into_string(args.store);
+                            if let Some((ty, items)) = equal_value.array_items(args.store, &engine[id].sigma, array_of.map(|(ty, _)| ty)) {
+                                engine[id].new_array_assign(&name, ty, items);
+                                continue;
+                            }
+
+                            let ty = declared.clone()
+                                .or_else(|| equal_value.int_ty(args.store, &engine[id].sigma).map(|t| t.to_string()))
+                                .unwrap_or_else(||"i32".to_string());
+                            let value = equal_value.to_term(args.store, &engine[id].sigma, IntTy::from_scalar_str(&ty).unwrap_or(IntTy::I32));
+                            engine[id].new_variable_assign(name, ty, value);
+                            continue;
+                        }
+
+                        // a tuple is destructured item by item, and anything
+                        // else destructured is unknown
+                        let value = match (&**pattern, value) {
+                            (RPattern::Tuple { .. }, MatchValue::Scalar(..)) => pattern.unknown_value(args.store, ty.as_ref(), &mut engine[id]),
+                            (_, value) => value,
+                        };
+                        let mut binds = Vec::new();
+                        pattern.cond(args.store, &args.enums, &value, &mut engine[id], &mut binds);
+                        for name in pattern.bound_names(args.store) {
+                            engine[id].drop_cells(&name);
+                        }
+                        for (name, ty, value) in binds {
+                            engine[id].bind_variable(&name, ty, value);
+                        }
+                    }
                 }
 
-                Ok(ExOk { cont: true, res: Vec::new(), continues: HashSet::new() })
+                Ok(res)
             }
         }
     }
@@ -274,7 +1061,7 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RStatement {
 impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RParam {
     fn execute<'a>(&self, engine: &mut Vec<SymExEngine>,  args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
         for id in args.ids.clone() {
-            engine[id].new_variable(self.id.into_string(args.store), self.ty.into_string(args.store));
+            self.pattern.declare(args.store, &args.structs, &args.enums, &self.ty, &mut engine[id])?;
         }
         return Ok(ExOk { cont: true, res: Vec::new(), continues: HashSet::new() });
     }
@@ -291,1449 +1078,3683 @@ impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RExpr {
             Block(b) => return b.execute(engine, args),
             If(i) => return i.execute(engine, args),
             Loop(l) => return l.execute(engine, args),
-            Call { span, ident, args } => {},
-            Deref { span, star, expr } => {},
-            Borrow { span, and, expr } => {},
-            BorrowMut { span, and, mutable, expr } => {},
-            Negate { span, neg, expr } => {},
-            Not { span, not, expr } => {},
-            AssignOp { span, left, op, op_span, right } => {
-                for id in args.ids {
-                    engine[id].assign_symvar_value(
-                        right.span().into_string(args.store),
-                        left.span().into_string(args.store),
-                        right.into_lisp(args.store)
-                    );
-                }
-            },
-            BinOp { span, left, op, op_span, right } => { },
-        }       
-        Ok(ExOk { cont: true, res: Vec::new(), continues: HashSet::new() })
-    }
-}
-
-impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RIf {
-    fn execute<'a>(&self, engine: &mut Vec<SymExEngine>, args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
-        let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
-
-        for bad_path in args.ids.iter().map(|v|*v) {
-            for (expr, block) in self.ifs.iter() {
-                let good_path = new_assert(engine, bad_path, expr.span().into_string(args.clone().store), expr.into_lisp(args.clone().store));
-                let result = block.execute(engine, args.clone().with_ids(HashSet::from([good_path])))?;
-                res.res.extend(result.res);
-                res.continues.insert(good_path);
-                res.continues.extend(result.continues);
-            }
-
-            if let Some(block) = &self.else_stmt {
-                let result = block.execute(engine, args.clone().with_ids(HashSet::from([bad_path])))?;
-                res.res.extend(result.res);
-                res.continues.extend(result.continues);
-            }
-        }
-
-        Ok(res)
-    }
-}
-
-impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RLoop {
-    fn execute<'a>(&self, engine: &mut Vec<SymExEngine>,  args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
-        let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
+            Match(m) => return m.execute(engine, args),
+            Tuple { items, .. } | Array { items, .. } => return execute_items(items.iter(), engine, args),
+            ArrayRepeat { item, .. } => return item.execute(engine, args),
+            Struct { fields, base, .. } => return execute_items(fields.iter().map(|(_, item)| item).chain(base.as_deref()), engine, args),
+            Index { expr, index, .. } => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
 
-        match self {
-            RLoop::Infinite { span, block } => {
-                for good_path in args.ids.iter().map(|v|*v) {
-                    for i in 0..args.max_loop_iter {
-                        let result = block.execute(
-                            engine, 
-                            args.clone().with_ids(HashSet::from([good_path]))
-                        )?;
+                for id in args.ids.clone() {
+                    let mut live = HashSet::from([id]);
+                    let index_ty = index.int_ty(args.store, &engine[id].sigma).unwrap_or(IntTy::USIZE);
+                    for (part, ty) in [(expr, args.int_ty), (index, index_ty)] {
+                        let result = part.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(ty))?;
                         res.res.extend(result.res);
-                        res.continues.extend(result.continues);
+                        live.extend(result.continues);
+                    }
 
-                        if i == args.max_loop_iter - 1 {
-                            res.res.push(SymexRes::InfiniteLoopMaxIterHit);
+                    for id in live.iter().copied() {
+                        // the items of anything but an array variable are
+                        // unknown
+                        if self.indexed_cells(args.store, &engine[id].sigma).is_empty() {
+                            let value = SymTerm::Var(engine[id].pi.fresh_int("index", args.int_ty));
+                            engine[id].bind_call(self.call_symbol(args.store), args.int_ty, value);
                         }
+                        res.res.extend(check_panics(engine, id, self, args.store, args.int_ty)?);
                     }
+                    live.remove(&id);
+                    res.continues.extend(live);
                 }
-            },
-            RLoop::While { span, expr, block } => {
-                for bad_path in args.ids.iter().map(|v|*v) {
-                    let mut curr_path = bad_path;
-                    for i in 0..args.max_loop_iter {
-                        let good_path = new_assert(engine, curr_path, expr.span().into_string(args.store), expr.into_lisp(args.store));
-                        res.continues.insert(good_path);
-                        curr_path = good_path;
 
-                        if engine[good_path].pi.satisfiable {
-                            let result = block.execute(engine, args.clone().with_ids(HashSet::from([good_path])))?;
-                            res.res.extend(result.res);
-                            res.continues.extend(result.continues);
-                        } else {
-                            break
-                        }
+                return Ok(res);
+            },
+            Field { expr, .. } => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
 
-                        if i == args.max_loop_iter - 1 {
-                            res.res.push(SymexRes::InfiniteLoopMaxIterHit);
+                for id in args.ids.clone() {
+                    let result = expr.execute(engine, args.clone().with_ids(HashSet::from([id])))?;
+                    res.res.extend(result.res);
+                    res.continues.extend(result.continues.iter().copied());
+
+                    // the items of anything but a tuple variable are unknown
+                    for id in result.continues.union(&HashSet::from([id])).copied() {
+                        let symbol = self.field_symbol(args.store, &engine[id].sigma);
+                        let item = self.place(args.store).and_then(|path| MatchValue::of_place(&engine[id].sigma, &path));
+                        if symbol == self.call_symbol(args.store) && item.is_none() && !engine[id].sigma.iter().any(|v| v.name == symbol) {
+                            let value = SymTerm::Var(engine[id].pi.fresh_int("field", args.int_ty));
+                            engine[id].bind_call(symbol, args.int_ty, value);
                         }
                     }
                 }
+
+                return Ok(res);
             },
-            RLoop::For { span, var, expr, block } => { panic!("no for loop support yet!") },
-        }
+            // a tuple struct or variant is built from its fields
+            Call { args: items, .. } if self.is_constructor(args.store, &args.structs, &args.enums) => return execute_items(items.iter(), engine, args),
+            Call { span, ident, args: call_args } => return self.execute_call(engine, args, ident, call_args),
+            MethodCall { expr, method, args: call_args, .. } => {
+                let mut res = execute_items(std::iter::once(&**expr).chain(call_args), engine, args.clone())?;
+                let symbol = self.call_symbol(args.store);
+                let method = method.into_string(args.store);
+
+                let ends: Vec<usize> = args.ids.iter().chain(res.continues.iter()).copied().collect();
+                for id in ends {
+                    if !engine[id].pi.satisfiable || engine[id].returned || engine[id].exit.is_some() {
+                        continue;
+                    }
+                    // unwrapping the wrong variant panics, and what any
+                    // other method returns is unknown
+                    res.res.extend(check_panics(engine, id, self, args.store, args.int_ty)?);
+                    let (ty, value) = match self.method_value(args.store, &engine[id], &args.structs, &args.enums) {
+                        Some(value) => value,
+                        None => (args.int_ty, SymTerm::Var(engine[id].pi.fresh_int(&method, args.int_ty))),
+                    };
+                    engine[id].bind_call(symbol.clone(), ty, value);
+                }
 
-        Ok(res)
-    }
-}
+                return Ok(res);
+            },
+            Let { expr, .. } => return expr.execute(engine, args),
+            Macro { kind, args: macro_args, .. } => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
 
-pub trait IntoLisp<Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> {
-    fn into_lisp(&self, store: &Store) -> String;
-}
+                for id in args.ids.clone() {
+                    // only the condition of an assertion is evaluated unless
+                    // the macro panics
+                    let mut live = HashSet::from([id]);
+                    if let (MacroKind::Assert | MacroKind::DebugAssert, Some(cond)) = (kind, macro_args.first()) {
+                        let result = cond.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(IntTy::I32))?;
+                        res.res.extend(result.res);
+                        live.extend(result.continues);
+                    }
 
-impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> IntoLisp<Store, Pos> for Span<Pos> {
-    fn into_lisp(&self, store: &Store) -> String {
-        self.into_string(store)
-    }
-}
+                    // the paths on which the macro panics are reported and
+                    // then cut off
+                    for id in live.iter().copied() {
+                        res.res.extend(check_panics(engine, id, self, args.store, args.int_ty)?);
+                    }
+                    live.remove(&id);
+                    res.continues.extend(live);
+                }
 
-impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos, T: IntoLisp<Store, Pos> + ?Sized> IntoLisp<Store, Pos> for Box<T> {
-    fn into_lisp(&self, store: &Store) -> String {
-        (**self).into_lisp(store)
-    }
-}
+                return Ok(res);
+            },
+            Deref { span, star, expr } => {},
+            Borrow { span, and, expr } => {},
+            BorrowMut { span, and, mutable, expr } => {},
+            Negate { span, neg, expr } | Not { span, not: neg, expr } => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
 
-impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos, T: IntoLisp<Store, Pos>> IntoLisp<Store, Pos> for Vec<T> {
-    fn into_lisp(&self, store: &Store) -> String {
-        let mut out = String::new();
-        if self.len() > 0 {
-            out.extend(self[0].into_lisp(store).chars());
+                for id in args.ids.clone() {
+                    let ty = expr.int_ty(args.store, &engine[id].sigma).unwrap_or(args.int_ty);
+                    let result = expr.execute(engine, args.clone().with_ids(HashSet::from([id])).with_int_ty(ty))?;
+                    res.res.extend(result.res);
+                    res.continues.extend(result.continues.iter().copied());
 
-            for i in 1..self.len() {
-                out.push(' ');
-                out.extend(self[i].into_lisp(store).chars());
-            }
-        }
-        out
-    }
-}
+                    for id in result.continues.union(&HashSet::from([id])).copied() {
+                        res.res.extend(check_panics(engine, id, self, args.store, args.int_ty)?);
+                    }
+                }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RParam {
-    pub mutable: Option<Span<PPos>>,
-    pub id: Span<PPos>,
-    pub ty: RType,
-}
+                return Ok(res);
+            },
+            AssignOp { span, left, op, op_span, right } => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RBlock {
-    pub span: Span<PPos>,
-    pub statements: Vec<RStatement>,
-}
+                for id in args.ids.clone() {
+                    let ty = left.int_ty(args.store, &engine[id].sigma).unwrap_or(IntTy::I32);
+
+                    // `x op= y` evaluates (and can panic) like `x op y`
+                    let (value, value_str) = match op.bin_op() {
+                        Some(op) => {
+                            let value_str = format!("{} {} ({})", left.span().into_string(args.store), op.as_ref(), right.span().into_string(args.store));
+                            (BinOp { span: span.clone(), left: left.clone(), op, op_span: op_span.clone(), right: right.clone() }, value_str)
+                        },
+                        None => ((**right).clone(), right.span().into_string(args.store)),
+                    };
+
+                    let mut live = HashSet::from([id]);
+                    let result = value.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(ty))?;
+                    res.res.extend(result.res);
+                    live.extend(result.continues);
+
+                    // `a[i] = v` indexes (and can panic) once `v` is
+                    // evaluated, where `a[i] op= v` already has
+                    if let (None, Index { .. }) = (op.bin_op(), &**left) {
+                        let result = left.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(ty))?;
+                        res.res.extend(result.res);
+                        live.extend(result.continues);
+                    }
 
-/// 
-/// An if statement or chain of if statments.
-/// 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RIf {
-    /// The span of the `RIf`.
-    span: Span<PPos>,
-    /// The list of if statements.
-    ifs: Vec<(RExpr, RBlock)>,
-    /// The optional else block to run if all if statements fail.
-    else_stmt: Option<RBlock>
-}
+                    for id in live.iter().copied() {
+                        let name = left.place(args.store).unwrap_or_else(|| left.span().into_string(args.store));
+                        if let Index { .. } = **left {
+                            let value = value.to_term(args.store, &engine[id].sigma, ty);
+                            left.assign_index(args.store, &mut engine[id], value);
+                        } else if let Some((_, items)) = value.array_items(args.store, &engine[id].sigma, None).filter(|_| !array_cells(&engine[id].sigma, &name).is_empty()) {
+                            let items = items.iter().map(|item| engine[id].term_as_var0(item)).collect();
+                            engine[id].assign_array(&name, items);
+                        } else if let (Some(path), whole @ (MatchValue::Tuple(_) | MatchValue::Struct(_) | MatchValue::Enum { .. })) = (left.place(args.store), value.match_value(args.store, &engine[id], &args.structs, &args.enums, None)) {
+                            // a whole tuple or struct is assigned item by
+                            // item, and an enum replaces the tag and fields
+                            // it had
+                            if let MatchValue::Enum { .. } = whole {
+                                engine[id].drop_cells(&path);
+                            }
+                            for (cell, ty, item) in whole.cells(&path) {
+                                engine[id].bind_call(cell, ty, item);
+                            }
+                        } else if let Field { .. } = **left {
+                            let value = engine[id].term_as_var0(&value.to_term(args.store, &engine[id].sigma, ty));
+                            let symbol = left.field_symbol(args.store, &engine[id].sigma);
+                            engine[id].bind_call(symbol, ty, value);
+                        } else {
+                            let value = value.to_term(args.store, &engine[id].sigma, ty);
+                            engine[id].assign_symvar_value(&name, value);
+                        }
+                    }
+                    live.remove(&id);
+                    res.continues.extend(live);
+                }
 
-impl RIf {
-    pub fn span(&self) -> Span<PPos> {
-        self.span.clone()
-    } 
-}
+                return Ok(res);
+            },
+            BinOp { span, left, op, op_span, right } => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RLoop {
-    Infinite {
-        span: Span<PPos>,
-        block: RBlock,
-    },
-    While {
-        span: Span<PPos>,
-        expr: RExpr,
-        block: RBlock,
-    },
-    For {
-        span: Span<PPos>,
-        var: Span<PPos>,
-        expr: RExpr,
-        block: RBlock,
-    },
-}
+                for id in args.ids.clone() {
+                    let (left_ty, right_ty) = self.operand_tys(args.store, &engine[id].sigma, args.int_ty);
+                    let mut live = HashSet::from([id]);
+                    let result = left.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(left_ty))?;
+                    res.res.extend(result.res);
+                    live.extend(result.continues);
+
+                    // the right side of a cast is a type, not a value
+                    if *op != self::BinOp::As {
+                        let result = right.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(right_ty))?;
+                        res.res.extend(result.res);
+                        live.extend(result.continues);
+                    }
 
-impl RLoop {
-    pub fn span(&self) -> Span<PPos> {
-        use RLoop::*;
-        match self {
-            Infinite { span, .. } => span.clone(),
-            While { span, .. } => span.clone(),
-            For { span, .. } => span.clone(),
-        }
-    } 
-}
+                    for id in live.iter().copied() {
+                        res.res.extend(check_panics(engine, id, self, args.store, args.int_ty)?);
+                    }
+                    live.remove(&id);
+                    res.continues.extend(live);
+                }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RType {
-    Array {
-        span: Span<PPos>,
-        /// The type of every item in the array (since arrays can only hold 1
-        /// type of item, this is that type).
-        item_type: Box<Self>,
-        /// The number of items in the array.
-        item_number: PPos,
-    },
-    Tuple {
-        span: Span<PPos>,
-        /// The types in the tuple, stored in the same order as given.
-        types: Vec<Self>,
-    },
-    Template {
-        span: Span<PPos>,
-        /// The name of the type.
-        name: Span<PPos>,
-        /// The arguments for the type.
-        args: Vec<Self>,
-    },
-}
+                return Ok(res);
+            },
+            Break { label, expr, .. } => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
+                let label = label.as_ref().map(|label| label.into_string(args.store));
 
-impl RType {
-    pub fn into_string<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> String {
-        match self {
-            RType::Array { span, .. } => span.into_string(store),
-            RType::Tuple { span, .. } => span.into_string(store),
-            RType::Template { span, .. } => span.into_string(store),
-        }
-    }
-}
+                for id in args.ids.clone() {
+                    let mut live = HashSet::from([id]);
+                    if let Some(expr) = expr {
+                        let ty = expr.int_ty(args.store, &engine[id].sigma).unwrap_or(args.int_ty);
+                        let result = expr.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(ty))?;
+                        res.res.extend(result.res);
+                        live.extend(result.continues);
+                    }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RExpr {
-    Lit(RLit),
-    Var(Span<PPos>),
-    Path(Span<PPos>, Vec<Span<PPos>>),
-    Block(RBlock),
-    If(Box<RIf>),
-    Loop(Box<RLoop>),
-    Group { span: Span<PPos>, expr: Box<RExpr> },
+                    // every path skips the rest of the loop body from here
+                    for id in live.iter().copied() {
+                        let value = expr.as_ref().map(|expr| {
+                            let ty = expr.int_ty(args.store, &engine[id].sigma).unwrap_or(args.int_ty);
+                            (engine[id].term_as_var0(&expr.to_term(args.store, &engine[id].sigma, ty)), ty)
+                        });
+                        engine[id].exit = Some(LoopExit::Break { label: label.clone(), value });
+                    }
+                    live.remove(&id);
+                    res.continues.extend(live);
+                }
 
-    Call      { span: Span<PPos>, ident: Span<PPos>, args: Vec<RExpr> },
+                return Ok(res);
+            },
+            Continue { label, .. } => {
+                let label = label.as_ref().map(|label| label.into_string(args.store));
+                for id in args.ids.iter().copied() {
+                    engine[id].exit = Some(LoopExit::Continue { label: label.clone() });
+                }
+            },
+            Range { start, end, .. } => {
+                let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
 
-    Deref     { span: Span<PPos>, star: Span<PPos>, expr: Box<RExpr> },
-    Borrow    { span: Span<PPos>, and: Span<PPos>, expr: Box<RExpr> },
-    BorrowMut { span: Span<PPos>, and: Span<PPos>, mutable: Span<PPos>, expr: Box<RExpr> },
-    Negate    { span: Span<PPos>, neg: Span<PPos>, expr: Box<RExpr> },
-    Not       { span: Span<PPos>, not: Span<PPos>, expr: Box<RExpr> },
+                for id in args.ids.clone() {
+                    let mut live = HashSet::from([id]);
+                    for bound in [start, end] {
+                        let result = bound.execute(engine, args.clone().with_ids(live.clone()))?;
+                        res.res.extend(result.res);
+                        live.extend(result.continues);
+                    }
+                    live.remove(&id);
+                    res.continues.extend(live);
+                }
 
-    AssignOp  { span: Span<PPos>, left: Box<RExpr>, op: AssignOp, op_span: Span<PPos>, right: Box<RExpr> },
-    BinOp     { span: Span<PPos>, left: Box<RExpr>, op: BinOp, op_span: Span<PPos>, right: Box<RExpr> },
+                return Ok(res);
+            },
+        }       
+        Ok(ExOk { cont: true, res: Vec::new(), continues: HashSet::new() })
+    }
 }
 
 impl RExpr {
-    pub fn span(&self) -> Span<PPos> {
-        use RExpr::*;
+    /// 
+    /// The SMT-LIB symbol the value of this call is bound to in `sigma`: its
+    /// source text as a quoted symbol.
+    /// 
+    pub fn call_symbol<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> String {
+        format!("|{}|", self.span().into_string(store).replace('|', "/"))
+    }
+
+    /// Whether this call builds a tuple struct in `structs` or a variant of
+    /// an enum (`Some(x)`, `Shape::Circle(r)`) rather than calling a function.
+    pub fn is_constructor<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, structs: &HashMap<String, RStruct>, enums: &HashMap<String, REnum>) -> bool {
         match self {
-            Lit(l) => l.span(),
-            Var(v) => v.clone(),
-            Path(p, _) => p.clone(),
-            Group { span, .. } => span.clone(),
-            Block(b) => b.span.clone(),
-            If(f) => f.span().clone(),
-            Loop(l) => l.span().clone(),
-            Call { span, .. } => span.clone(),
-            Deref { span, .. } => span.clone(),
-            Borrow { span, .. } => span.clone(),
-            BorrowMut { span, .. } => span.clone(),
-            Negate { span, .. } => span.clone(),
-            Not { span, .. } => span.clone(),
-            AssignOp { span, .. } => span.clone(),
-            BinOp { span, .. } => span.clone(),
+            RExpr::Call { ident, .. } => {
+                let name = ident.into_string(store);
+                structs.contains_key(&name) || resolve_variant(store, enums, &path_names(&name)).is_some()
+            },
+            _ => false,
         }
     }
-}
 
-
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RExpr {
-    fn into_lisp(&self, store: &Store) -> String {
-        //use RExpr::*;
-        match self {
-            RExpr::Lit(l) => l.into_lisp(store),
-            RExpr::Var(v) => v.into_lisp(store),
-            RExpr::Path(span, _) => span.into_lisp(store),
-            RExpr::Block(_) => format!("Block"),
-            RExpr::If(_) => format!("IfStatement"),
-            RExpr::Loop(_) => format!("Loop"),
-            RExpr::Group { expr, .. } => expr.into_lisp(store),
-            RExpr::Call { ident, args, .. } => {
-                format!("({} {})", ident.into_lisp(store), args.into_lisp(store))
+    /// 
+    /// The type and value (in terms of the parameters) of this method call
+    /// on the path `engine`, if it is a method of `Option` or `Result` that
+    /// is understood: `is_some`, `is_none`, `is_ok`, `is_err`, and `unwrap`,
+    /// `expect`, `unwrap_or` and `unwrap_or_default` of an int or bool.
+    /// 
+    pub fn method_value<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, engine: &SymExEngine, structs: &HashMap<String, RStruct>, enums: &HashMap<String, REnum>) -> Option<(IntTy, SymTerm)> {
+        let RExpr::MethodCall { expr, method, args, .. } = self else { return None };
+        let value = expr.match_value(store, engine, structs, enums, None);
+        let MatchValue::Enum { name, tag, .. } = &value else { return None };
+        let (_, ok) = ok_variant(name)?;
+        let is_ok = SymTerm::eq(tag.clone(), SymTerm::Int(ok as i128, IntTy::TAG));
+        let field = match value.unwrapped() {
+            Some(MatchValue::Scalar(term, ty)) => Some((term.clone(), *ty)),
+            _ => None,
+        };
+
+        match method.into_string(store).as_str() {
+            "is_some" | "is_ok" => Some((IntTy::BOOL, is_ok)),
+            "is_none" | "is_err" => Some((IntTy::BOOL, SymTerm::not(is_ok))),
+            "unwrap" | "expect" => field.map(|(term, ty)| (ty, term)),
+            "unwrap_or" => {
+                let default = args.first()?;
+                let ty = field.as_ref().map(|(_, ty)| *ty)
+                    .or_else(|| default.int_ty(store, &engine.sigma))
+                    .unwrap_or(IntTy::I32);
+                let default = engine.term_as_var0(&default.to_term(store, &engine.sigma, ty));
+                match field {
+                    Some((term, _)) => Some((ty, SymTerm::ite(is_ok, term, default))),
+                    None => Some((ty, default)),
+                }
             },
-            RExpr::Deref { expr, .. } => {
-                format!("*{}", expr.into_lisp(store))
+            "unwrap_or_default" => {
+                let (term, ty) = field?;
+                let default = if ty.is_bool() { SymTerm::Bool(false) } else { SymTerm::Int(0, ty) };
+                Some((ty, SymTerm::ite(is_ok, term, default)))
             },
-            RExpr::Borrow { expr, .. } => {
-                format!("&{}", expr.into_lisp(store))
+            _ => None,
+        }
+    }
+
+    /// 
+    /// The items of the array variable (or array field of a variable) this
+    /// index expression indexes, or an empty list if it indexes anything
+    /// else.
+    /// 
+    pub fn indexed_cells<'s, Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &'s [SymVar]) -> Vec<&'s SymVar> {
+        match self {
+            RExpr::Index { expr, .. } => match expr.place(store) {
+                Some(path) => array_cells(sigma, &path),
+                None => Vec::new(),
             },
-            RExpr::BorrowMut { expr, .. } => {
-                format!("&{}", expr.into_lisp(store))
+            _ => Vec::new(),
+        }
+    }
+
+    /// 
+    /// The variable this field expression reads in `sigma`: the cell of the
+    /// tuple item it names, or else the unknown value bound to
+    /// `call_symbol`.
+    /// 
+    pub fn field_symbol<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar]) -> String {
+        self.place(store)
+            .map(|path| format!("|{}|", path))
+            .filter(|cell| sigma.iter().any(|v| &v.name == cell))
+            .unwrap_or_else(|| self.call_symbol(store))
+    }
+
+    /// The index of this index expression as a term, and its type.
+    pub fn index_term<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar]) -> Option<(SymTerm, IntTy)> {
+        match self {
+            RExpr::Index { index, .. } => {
+                let ty = index.int_ty(store, sigma).unwrap_or(IntTy::USIZE);
+                Some((index.to_term(store, sigma, ty), ty))
             },
-            RExpr::Negate { expr, .. } => {
-                format!("-{}", expr.into_lisp(store))
+            _ => None,
+        }
+    }
+
+    /// 
+    /// The items (in terms of the current variables) and item type of this
+    /// expression if it is an array: a literal (`[a, b]`, or `[v; n]` with a
+    /// literal `n`) or an array variable. `ty` is the declared item type, if
+    /// any.
+    /// 
+    pub fn array_items<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar], ty: Option<IntTy>) -> Option<(IntTy, Vec<SymTerm>)> {
+        match self {
+            RExpr::Group { expr, .. } => expr.array_items(store, sigma, ty),
+            RExpr::Array { items, .. } => {
+                let ty = ty.or_else(|| items.iter().find_map(|item| item.int_ty(store, sigma))).unwrap_or(IntTy::I32);
+                Some((ty, items.iter().map(|item| item.to_term(store, sigma, ty)).collect()))
             },
-            RExpr::Not { expr, .. } => {
-                format!("!{}", expr.into_lisp(store))
-            },
-            RExpr::AssignOp { left, op, op_span, right, .. } => {
-                format!("(setq {} {})", left.into_lisp(store), right.into_lisp(store))
-            },
-            RExpr::BinOp { left, op, op_span, right, .. } => {
-                let op = match op {
-                    BinOp::As => "as",
-                    BinOp::EqEq => "=",
-                    BinOp::NotEq => return format!("(not (= {} {}))", left.into_lisp(store), right.into_lisp(store)),
-                    BinOp::LessThan => "<",
-                    BinOp::MoreThan => ">",
-                    BinOp::LessThanEq => "<=",
-                    BinOp::MoreThanEq => ">=",
-                    BinOp::And => "&&",
-                    BinOp::Or => "||",
-                    BinOp::LSh => "<<",
-                    BinOp::RSh => ">>",
-                    BinOp::BitAnd => "&",
-                    BinOp::BitOr => "|",
-                    BinOp::BitXOr => "^",
-                    BinOp::Add => "+",
-                    BinOp::Sub => "-",
-                    BinOp::Div => "/",
-                    BinOp::Mod => "%",
-                    BinOp::Mul => "*",
+            RExpr::ArrayRepeat { item, len, .. } => {
+                let RExpr::Lit(RLit::Integer(len)) = &**len else {
+                    return None;
                 };
-                format!("({} {} {})", op.to_string(), left.into_lisp(store), right.into_lisp(store))
+                let ty = ty.or_else(|| item.int_ty(store, sigma)).unwrap_or(IntTy::I32);
+                Some((ty, vec![item.to_term(store, sigma, ty); len.value(store) as usize]))
+            },
+            _ => {
+                let cells = array_cells(sigma, &self.place(store)?);
+                let ty = cells.first()?.ty;
+                Some((ty, cells.iter().map(|cell| SymTerm::Var(cell.name.clone())).collect()))
             },
-    
         }
     }
-}
 
+    /// 
+    /// Writes `value` (in terms of the current variables) to the item of the
+    /// array variable this index expression indexes on the path `engine`.
+    /// Every item the index may be becomes `value` if it is that item.
+    /// 
+    pub fn assign_index<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, engine: &mut SymExEngine, value: SymTerm) {
+        let (RExpr::Index { expr, .. }, Some((at, at_ty))) = (self, self.index_term(store, &engine.sigma)) else {
+            return;
+        };
+        let cells: Vec<String> = self.indexed_cells(store, &engine.sigma).iter().map(|cell| cell.name.clone()).collect();
+        let items = cells.into_iter().enumerate().map(|(i, cell)| {
+            let old = SymTerm::Var(cell);
+            let item = match at {
+                SymTerm::Int(at, _) if at == i as i128 => value.clone(),
+                SymTerm::Int(..) => old,
+                _ => SymTerm::ite(SymTerm::eq(at.clone(), SymTerm::Int(i as i128, at_ty)), value.clone(), old),
+            };
+            engine.term_as_var0(&item)
+        }).collect();
+        engine.assign_array(&expr.place(store).unwrap_or_default(), items);
+    }
 
+    /// 
+    /// Executes the call `ident(call_args)` (this expression) by evaluating
+    /// the arguments and then the body of the callee with its parameters bound
+    /// to them. Each path the callee ends on comes back with its return value
+    /// bound to `call_symbol`. Calls to functions outside the crate, or deeper
+    /// than `max_call_depth`, return an unconstrained value instead. When
+    /// `args.summaries` is set the callee's summary is instantiated instead of
+    /// executing its body.
+    /// 
+    fn execute_call<'a, Store: ParseStore<PPos, char> + ?Sized>(&self, engine: &mut Vec<SymExEngine>, args: ExecuteArgs<'a, Store>, ident: &Span<PPos>, call_args: &[RExpr]) -> Result<ExOk, ExErr> {
+        let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
+        let name = ident.into_string(args.store);
+        let symbol = self.call_symbol(args.store);
+        let fns = args.fns.clone();
+
+        // only int and bool parameters can be bound
+        let callee = fns.get(&name).filter(|callee| {
+            args.call_depth < args.max_call_depth
+                && callee.args.len() == call_args.len()
+                && callee.args.iter().all(|param| param.ident().is_some() && IntTy::from_scalar_str(&param.ty.into_string(args.store)).is_some())
+        });
+        let param_tys: Vec<Option<IntTy>> = match callee {
+            Some(callee) => callee.args.iter().map(|param| IntTy::from_scalar_str(&param.ty.into_string(args.store))).collect(),
+            None => vec![None; call_args.len()],
+        };
+        let ret_ty = fns.get(&name).and_then(|callee| callee.ret_int_ty(args.store));
+        let summary = callee.and_then(|callee| callee.cached_summary(&name, args.clone().with_call_depth(args.call_depth + 1)));
+        let call = self.span().into_string(args.store);
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum BinOp {
-    // Type Cast
-    As,
+        for id in args.ids.clone() {
+            // evaluate the arguments from left to right
+            let mut live = HashSet::from([id]);
+            for (arg, ty) in call_args.iter().zip(param_tys.iter()) {
+                let ty = ty.or_else(|| arg.int_ty(args.store, &engine[id].sigma)).unwrap_or(IntTy::I32);
+                let result = arg.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(ty))?;
+                res.res.extend(result.res);
+                live.extend(result.continues);
+            }
 
-    // Logical
-    EqEq,
-    NotEq,
-    LessThan,
-    MoreThan,
-    LessThanEq,
-    MoreThanEq,
-    And,
-    Or,
+            for path in live.clone() {
+                let Some(callee) = callee else {
+                    let ty = ret_ty.unwrap_or(args.int_ty);
+                    let fresh = engine[path].pi.fresh_int(&name, ty);
+                    engine[path].bind_call(symbol.clone(), ty, SymTerm::Var(fresh));
+                    continue;
+                };
 
-    // Bits
-    LSh,
-    RSh,
-    BitAnd,
-    BitOr,
-    BitXOr,
+                let mut params = Vec::new();
+                for ((param, arg), ty) in callee.args.iter().zip(call_args).zip(param_tys.iter()) {
+                    let ty = ty.unwrap_or(IntTy::I32);
+                    let value = arg.to_term(args.store, &engine[path].sigma, ty);
+                    params.push((param.ident().map(|ident| ident.into_string(args.store)).unwrap_or_default(), ty, value));
+                }
 
-    // Arithmetic
-    Add,
-    Sub,
-    Div,
-    Mod,
-    Mul,
-}
+                if let Some(summary) = &summary {
+                    let call_args: Vec<SymTerm> = params.into_iter().map(|(.., value)| engine[path].term_as_var0(&value)).collect();
+                    let (panics, ends) = instantiate_summary(engine, path, summary, &call_args, &call, &symbol, ret_ty)?;
+                    res.res.extend(panics);
+                    let pos = self.span().start.key();
+                    for (arm, end) in ends.iter().copied().enumerate() {
+                        match arm {
+                            0 => args.scheduler.borrow_mut().took(engine, end, pos, arm),
+                            _ => args.scheduler.borrow_mut().forked(engine, end, pos, arm),
+                        }
+                    }
+                    live.extend(ends);
+                    continue;
+                }
 
-impl AsRef<str> for BinOp {
-    fn as_ref(&self) -> &str {
-        match self.clone() {
-            BinOp::As => "as",
-            BinOp::EqEq => "=",
-            BinOp::NotEq => "/=",
-            BinOp::LessThan => "<",
-            BinOp::MoreThan => ">",
-            BinOp::LessThanEq => "<=",
-            BinOp::MoreThanEq => ">=",
-            BinOp::And => "&&",
-            BinOp::Or => "||",
-            BinOp::LSh => "<<",
-            BinOp::RSh => ">>",
-            BinOp::BitAnd => "&",
-            BinOp::BitOr => "|",
-            BinOp::BitXOr => "^",
-            BinOp::Add => "+",
-            BinOp::Sub => "-",
-            BinOp::Div => "/",
-            BinOp::Mod => "%",
-            BinOp::Mul => "*",
-        }
-    }
-}
+                let frame = engine[path].enter_call(name.clone(), ret_ty, params);
+                let result = callee.execute_body(
+                    engine,
+                    args.clone().with_ids(HashSet::from([path])).with_call_depth(args.call_depth + 1)
+                )?;
+                res.res.extend(result.res);
 
-impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> IntoLisp<Store, Pos> for BinOp {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.as_ref())
-    }
-}
+                for end in result.continues.iter().copied() {
+                    let ret = engine[end].leave_call(frame.clone());
+                    if let (Some(ty), Some(ret)) = (ret_ty, ret) {
+                        engine[end].bind_call(symbol.clone(), ty, ret);
+                    }
+                    live.insert(end);
+                }
+            }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum AssignOp {
-    // Assignment
-    Assign,
+            live.remove(&id);
+            res.continues.extend(live);
+        }
 
-    // Compound Assignment
-    AAdd,
-    ASub,
-    AMul,
-    ADiv,
-    AMod,
-    AAnd,
-    AOr,
-    AXOr,
-    ALSh,
-    ARSh,
+        Ok(res)
+    }
 }
 
-impl AsRef<str> for AssignOp {
-    fn as_ref(&self) -> &str {
-        match self.clone() {
-            AssignOp::Assign => "=",
-            AssignOp::AAdd => "+=",
-            AssignOp::ASub => "-=",
-            AssignOp::AMul => "*=",
-            AssignOp::ADiv => "/=",
-            AssignOp::AMod => "%=",
-            AssignOp::AAnd => "&=",
-            AssignOp::AOr => "|=",
-            AssignOp::AXOr => "^=",
-            AssignOp::ALSh => "<<=",
-            AssignOp::ARSh => ">>=",
+/// 
+/// Instantiates `summary` for a call `call` on path `path` with the arguments
+/// `call_args` (in terms of the parameters), returning the callee's panics
+/// these arguments can reach and the paths the call ends on. The path is
+/// forked once per path in the summary, each assuming that path's assertions
+/// with its return value bound to `symbol`.
+/// 
+fn instantiate_summary(engine: &mut Vec<SymExEngine>, path: usize, summary: &FnSummary, call_args: &[SymTerm], call: &str, symbol: &str, ret_ty: Option<IntTy>) -> Result<(Vec<SymexRes>, Vec<usize>), SolverError> {
+    let mut res = Vec::new();
+
+    for panic in summary.panics.iter() {
+        let cond = panic.instantiate(summary, call_args, &mut engine[path].pi);
+        match engine[path].check_condition_var0(cond.clone()) {
+            CheckResult::Sat(model) => res.push(SymexRes::Panic { span: panic.span.clone(), kind: panic.kind.clone(), model }),
+            CheckResult::Unknown => res.push(SymexRes::UnknownPanic { span: panic.span.clone(), kind: panic.kind.clone() }),
+            CheckResult::Unsat => continue,
         }
+        engine[path].record_panic(panic.span.clone(), panic.kind.clone(), cond);
     }
-}
 
-impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> IntoLisp<Store, Pos> for AssignOp {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.as_ref())
+    // a callee that never returns leaves nothing to continue with
+    if summary.paths.is_empty() {
+        engine[path].new_assertion_var0(format!("!{}", call), "false".to_string())?;
     }
-}
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RStatement {
-    Comment {
-        comment: RComment,
-    },
-    /// An expression with an optional semicolon after it (semicolon can only be omitted if it is at the end of a group).
-    Expr {
-        expr: RExpr,
-        semi: Option<Span<PPos>>
-    },
-    /// A return statement.
-    Return {
-        return_span: Span<PPos>,
-        expr: RExpr,
-        semi: Option<Span<PPos>>
-    },
-    /// A semicolon
-    SColon {
-        semi: Span<PPos>
-    },
-    /// An if statement or chain of if statements.
-    If {
-        stmt: RIf,
-    },
-    /// A loop of one form or another.
-    Loop {
-        stmt: RLoop,
-    },
-    /// Assign a variable a value
-    Assign {
-        /// The `let` keyword span.
-        let_: Span<PPos>,
-        /// The span of the mutable keyword (if given)
-        mutable: Option<Span<PPos>>,
-        /// The variable name.
-        ident: Span<PPos>,
-        /// The optionally-specified type.
-        ty: Option<RType>,
-        /// The `=` sign.
-        equal_sign: Span<PPos>,
-        /// The expression after the `=`.
-        equal_value: RExpr,
-        /// The optional semicolon after the assignment statement (can only be
-        /// omitted if on last line of group).
-        semicolon: Option<Span<PPos>>,
-    },
-}
+    let mut ends = vec![path];
+    for _ in 1..summary.paths.len() {
+        ends.push(symex::clone_engine(engine, path));
+    }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RReturnType {
-    /// Returns the given type.
-    Type(RType),
-    /// Function never returns.
-    Never,
-}
+    for (i, (&end, summary_path)) in ends.iter().zip(summary.paths.iter()).enumerate() {
+        let (cond, ret) = summary_path.instantiate(summary, call_args, &mut engine[end].pi);
+        engine[end].new_assertion_var0(format!("{}@{}", call, i), cond)?;
+        engine[end].truncated |= summary_path.truncated;
+        if let (Some(ty), Some(ret)) = (ret_ty, ret) {
+            engine[end].bind_call(symbol.to_string(), ty, ret);
+        }
+    }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RFn {
-    pub span: Span<PPos>,
-    pub fn_span: Span<PPos>,
-    pub id: Span<PPos>,
-    pub args: Vec<RParam>,
-    pub ret_type: Option<RReturnType>,
-    pub body: RBlock,
+    Ok((res, ends))
 }
 
-// Comments
-
 /// 
-/// A comment in Rust.
+/// Executes `items` from left to right on each path of `args.ids`, each
+/// with the type it has on its own, as the items of a tuple are.
 /// 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RComment {
-    Symex {
-        symex: Span<PPos>,
-        follow: Span<PPos>,
-    },
-    Line {
-        /// Span including "//"
-        span: Span<PPos>,
-        /// Span of just the comment text.
-        text: Span<PPos>,
-    },
-    Block {
-        /// Span including "/*" and "*/"
-        span: Span<PPos>,
-        /// The comment's text.
-        text: Span<PPos>,
-    },
-    InnerLineDoc {
-        /// Span including "//!".
-        span: Span<PPos>,
-        /// The doc comment's text.
-        text: Span<PPos>,
-    },
-    InnerBlockDoc {
-        /// Span including "/*!" and "*/".
-        span: Span<PPos>,
-        /// The doc comment's text.
-        text: Span<PPos>,
-    },
-    OuterLineDoc {
-        /// Span including "///".
-        span: Span<PPos>,
-        /// The doc comment's text.
-        text: Span<PPos>,
-    },
-    OuterBlockDoc {
-        /// Span including "/**" and "*/".
-        span: Span<PPos>,
-        /// The doc comment's text.
-        text: Span<PPos>,
-    },
-}
-
+fn execute_items<'a, 'e, Store: ParseStore<PPos, char> + ?Sized>(items: impl Iterator<Item = &'e RExpr> + Clone, engine: &mut Vec<SymExEngine>, args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
+    let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
+
+    for id in args.ids.clone() {
+        let mut live = HashSet::from([id]);
+        for item in items.clone() {
+            let ty = item.int_ty(args.store, &engine[id].sigma).unwrap_or(IntTy::I32);
+            let result = item.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(ty))?;
+            res.res.extend(result.res);
+            live.extend(result.continues);
+        }
+        live.remove(&id);
+        res.continues.extend(live);
+    }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum Whitespace {
-    Comment(RComment),
-    Blank(Span<PPos>),
+    Ok(res)
 }
 
-// -- Literals --
-
-///
-/// A literal that cannot have a sign.
 /// 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RLit {
-    Char(RCharLit),
-    String(RStrLit),
-    RawString(RRawStrLit),
-    Byte(RByteLit),
-    ByteString(RByteStrLit),
-    RawByteString(RRawByteStrLit),
-    Integer(RIntLit),
-    Float(RFloatLit),
-    Bool(RBoolLit),
-}
+/// Checks every way evaluating `expr` itself (not its operands) on path `id`
+/// can panic, returning a `SymexRes::Panic` for each one that is satisfiable
+/// and a `SymexRes::UnknownPanic` for each one the solver could not decide.
+/// The path then assumes none of them happened.
+/// 
+fn check_panics<Store: ParseStore<PPos, char> + ?Sized>(engine: &mut [SymExEngine], id: usize, expr: &RExpr, store: &Store, ty: IntTy) -> Result<Vec<SymexRes>, SolverError> {
+    let mut res = Vec::new();
 
-impl RLit {
-    pub fn span(&self) -> Span<PPos> {
-        use RLit::*;
-        match self {
-            Char(s) => s.span.clone(),
-            String(s) => s.span.clone(),
-            RawString(s) => s.span.clone(),
-            Byte(s) => s.span.clone(),
-            ByteString(s) => s.span.clone(),
-            RawByteString(s) => s.span.clone(),
-            Integer(s) => s.span(),
-            Float(s) => s.span.clone(),
-            Bool(s) => s.span(),
+    for (kind, cond) in expr.panic_conditions(store, &engine[id].sigma, ty) {
+        if !engine[id].pi.satisfiable {
+            break;
         }
-    }
-}
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        use RLit::*;
-        match self {
-            Char(c) => c.into_lisp(store),
-            String(s) => s.into_lisp(store),
-            RawString(s) => s.into_lisp(store),
-            Byte(b) => b.into_lisp(store),
-            ByteString(b) => b.into_lisp(store),
-            RawByteString(b) => b.into_lisp(store),
-            Integer(i) => i.into_lisp(store),
-            Float(f) => f.into_lisp(store),
-            Bool(b) => b.into_lisp(store),
+        // an index past the end is reported with the index it takes for
+        // the inputs found
+        let found = match (&kind, expr.index_term(store, &engine[id].sigma)) {
+            (PanicKind::IndexOutOfBounds { len, .. }, Some((at, at_ty))) => {
+                engine[id].check_condition_value(&cond, &at, at_ty)
+                    .map(|(model, index)| (model, PanicKind::IndexOutOfBounds { len: *len, index }))
+            },
+            _ => engine[id].check_condition(&cond).map(|model| (model, kind.clone())),
+        };
+        match found {
+            CheckResult::Sat((model, kind)) => {
+                res.push(SymexRes::Panic { span: expr.span(), kind: kind.clone(), model });
+                let lisp = engine[id].term_as_var0(&cond).to_lisp();
+                engine[id].record_panic(expr.span(), kind.clone(), lisp);
+            },
+            CheckResult::Unknown => {
+                res.push(SymexRes::UnknownPanic { span: expr.span(), kind: kind.clone() });
+                let lisp = engine[id].term_as_var0(&cond).to_lisp();
+                engine[id].record_panic(expr.span(), kind.clone(), lisp);
+            },
+            CheckResult::Unsat => {},
         }
+        let a = expr.to_term(store, &engine[id].sigma, ty);
+        engine[id].assume_not(kind.name(), &a, &cond)?;
     }
-}
-
-// --- Crate ---
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RCrate {
-    pub utf8bom: Option<Span<PPos>>,
-    pub shebang: Option<Span<PPos>>,
-    // pub attrs: RInnerAttributes,
-    pub items: Vec<RItem>,
+    Ok(res)
 }
 
-/// 
-/// An item in a crate.
-/// 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RItem {
-//    Mod {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RMod,
-//    },
-//    ExternCrate {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RExternCrate,
-//    },
-//    UseDecl {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RUseDecl,
-//    },
-    Fn {
-        span: Span<PPos>,
-        vis: Option<RVis>,
-        val: RFn,
-    },
-//    TypeAlias {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RTypeAlias,
-//    },
-//    Struct {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RStruct,
-//    },
-//    Enum {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: REnum,
-//    },
-//    Union {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RUnion,
-//    },
-//    Const {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RConstItem,
-//    },
-//    Static {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RStaticItem,
-//    },
-//    Trait {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RTrait,
-//    },
-//    Impl {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RImpl,
-//    },
-//    ExternBlock {
-//        span: Span<PPos>,
-//        vis: Option<RVis>,
-//        val: RExternBlock,
-//    },
-}
+impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RIf {
+    fn execute<'a>(&self, engine: &mut Vec<SymExEngine>, args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
+        let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-/// The visibility of an item.
-pub enum RVis {
-    VisPub   { span: Span<PPos>, },
-    VisCrate { span: Span<PPos>, },
-    VisSelf  { span: Span<PPos>, },
-    VisSuper { span: Span<PPos>, },
-//    VisPath  {
-//        span: Span<PPos>,
-//        path: RSimplePath,
-//    }
-}
+        for root in args.ids.iter().copied() {
+            let mut ends = HashSet::new();
+            let mut reached = vec![false; self.ifs.len()];
+
+            // each arm is tried on the paths no earlier arm was taken on
+            let mut rest = vec![root];
+            for (i, (expr, block)) in self.ifs.iter().enumerate() {
+                for bad_path in std::mem::take(&mut rest) {
+                    let result = expr.execute(engine, args.clone().with_ids(HashSet::from([bad_path])).with_int_ty(IntTy::I32))?;
+                    res.res.extend(result.res);
+
+                    // a call in the condition may return along several paths,
+                    // and each of them takes the arms on its own
+                    let mut paths: Vec<usize> = result.continues.union(&HashSet::from([bad_path])).copied().collect();
+                    paths.sort();
+                    for path in paths {
+                        if !engine[path].pi.satisfiable || engine[path].returned || engine[path].suspended || engine[path].exit.is_some() {
+                            if path != root {
+                                res.continues.insert(path);
+                            }
+                            continue;
+                        }
 
-// --- Literals ---
+                        // `if let` takes the block when the pattern matches, with the
+                        // variables it binds shadowing any of their names there
+                        let mut binds = Vec::new();
+                        let good_path = match expr {
+                            RExpr::Let { pattern, expr: value, .. } => {
+                                let value = match value.match_value(args.store, &engine[path], &args.structs, &args.enums, None) {
+                                    value @ MatchValue::Scalar(..) => pattern.unknown_enum(args.store, &args.enums, &mut engine[path])?.unwrap_or(value),
+                                    value => value,
+                                };
+                                let cond = pattern.cond(args.store, &args.enums, &value, &mut engine[path], &mut binds);
+                                symex::new_assert_var0(engine, path, cond)?
+                            },
+                            _ => {
+                                let cond = expr.to_term(args.store, &engine[path].sigma, IntTy::I32);
+                                new_assert(engine, path, cond)?
+                            },
+                        };
+                        let pos = expr.span().start.key();
+                        args.scheduler.borrow_mut().forked(engine, good_path, pos, 0);
+                        args.scheduler.borrow_mut().took(engine, path, pos, 1);
+                        if engine[path].pi.satisfiable {
+                            rest.push(path);
+                        }
+                        if !engine[good_path].pi.satisfiable {
+                            continue;
+                        }
+                        reached[i] = true;
+
+                        let shadowed: Vec<(String, Option<SymVar>)> = binds.into_iter()
+                            .map(|(name, ty, value)| {
+                                let old = engine[good_path].bind_variable(&name, ty, value);
+                                (name, old)
+                            })
+                            .collect();
+                        let result = block.execute(engine, args.clone().with_ids(HashSet::from([good_path])))?;
+                        res.res.extend(result.res);
+                        self.bind_value(engine, result.continues.iter().copied().chain([good_path]), block, args.store, args.int_ty);
+                        for id in result.continues.iter().copied().chain([good_path]) {
+                            for (name, old) in shadowed.iter().rev() {
+                                engine[id].unbind_variable(name, old.clone());
+                            }
+                        }
+                        ends.insert(good_path);
+                        ends.extend(result.continues);
+                    }
+                }
+            }
+            for ((_, block), reached) in self.ifs.iter().zip(reached) {
+                if !reached {
+                    res.res.extend(block.unreachable_queries(args.store));
+                }
+            }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RBoolLit {
-    True  { span: Span<PPos> },
-    False { span: Span<PPos> }
-}
+            // the paths no arm was taken on take the else block, or go on
+            // past the if
+            match &self.else_stmt {
+                Some(block) if rest.is_empty() => res.res.extend(block.unreachable_queries(args.store)),
+                Some(block) => for bad_path in rest {
+                    let result = block.execute(engine, args.clone().with_ids(HashSet::from([bad_path])))?;
+                    res.res.extend(result.res);
+                    self.bind_value(engine, result.continues.iter().copied().chain([bad_path]), block, args.store, args.int_ty);
+                    ends.extend(result.continues);
+                    if bad_path != root {
+                        ends.insert(bad_path);
+                    }
+                },
+                None => ends.extend(rest.into_iter().filter(|id| *id != root)),
+            }
 
-impl RBoolLit {
-    pub fn span(&self) -> Span<PPos> {
-        use RBoolLit::*;
-        match self {
-            True { span } => span.clone(),
-            False { span } => span.clone(),
+            if args.merge {
+                RIf::merge_ends(engine, root, &mut ends);
+            }
+            res.continues.extend(ends);
         }
+
+        Ok(res)
     }
 }
 
-impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> IntoLisp<Store, Pos> for RBoolLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        use RBoolLit::*;
+impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RLoop {
+    fn execute<'a>(&self, engine: &mut Vec<SymExEngine>,  args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
+        let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
+
         match self {
-            True { .. } => format!("true"),
-            False { .. } => format!("false"),
-        }
-    }
-}
+            RLoop::Infinite { block, .. } => {
+                for good_path in args.ids.iter().map(|v|*v) {
+                    let mut looping = HashSet::from([good_path]);
+                    for i in 0..args.max_loop_iter {
+                        let result = block.execute(engine, args.clone().with_ids(looping.clone()))?;
+                        res.res.extend(result.res);
+                        res.continues.extend(result.continues.iter().copied());
+                        looping = self.take_exits(engine, looping.union(&result.continues).copied(), args.store);
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RCharLit {
-    pub span: Span<PPos>,
-    pub value: Span<PPos>,
-    pub suffix: Option<Span<PPos>>,
-}
+                        if looping.is_empty() {
+                            break
+                        }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RCharLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        self.span.into_lisp(store)
-    }
-}
+                        if i == args.max_loop_iter - 1 {
+                            res.res.push(SymexRes::InfiniteLoopMaxIterHit);
+                        }
+                    }
 
-/// 
-/// A string where escape sequences are allowed.
-/// 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RStrLit {
-    pub span: Span<PPos>,
-    pub text: Span<PPos>,
-    pub suffix: Option<Span<PPos>>,
-}
+                    // only a `break` or returning leaves the loop, so whatever
+                    // follows it is never really reached by the rest, and the
+                    // value they would break with is unknown
+                    for id in looping {
+                        engine[id].truncated = true;
+                        let value = SymTerm::Var(engine[id].pi.fresh_int("loop", args.int_ty));
+                        engine[id].bind_call(self.value_symbol(args.store), args.int_ty, value);
+                    }
+                }
+            },
+            RLoop::While { expr, block, .. } => {
+                let pos = expr.span().start.key();
+                for bad_path in args.ids.iter().map(|v|*v) {
+                    let mut looping = HashSet::from([bad_path]);
+                    for i in 0..args.max_loop_iter {
+                        let mut entered = HashSet::new();
+                        for curr_path in std::mem::take(&mut looping) {
+                            let result = expr.execute(engine, args.clone().with_ids(HashSet::from([curr_path])).with_int_ty(IntTy::I32))?;
+                            res.res.extend(result.res);
+                            res.continues.extend(result.continues.iter().copied());
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RStrLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
-    }
-}
+                            for curr_path in result.continues.union(&HashSet::from([curr_path])).copied() {
+                                if !engine[curr_path].pi.satisfiable || engine[curr_path].returned || engine[curr_path].suspended {
+                                    continue;
+                                }
+                                let cond = expr.to_term(args.store, &engine[curr_path].sigma, IntTy::I32);
+                                let good_path = new_assert(engine, curr_path, cond)?;
+                                args.scheduler.borrow_mut().forked(engine, good_path, pos, 0);
+                                args.scheduler.borrow_mut().took(engine, curr_path, pos, 1);
+                                if engine[good_path].pi.satisfiable {
+                                    res.continues.insert(good_path);
+                                    entered.insert(good_path);
+                                } else if i == 0 {
+                                    res.res.extend(block.unreachable_queries(args.store));
+                                }
+                            }
+                        }
+                        if entered.is_empty() {
+                            break
+                        }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RByteStrLit {
-    pub span: Span<PPos>,
-    pub value: Span<PPos>,
-    pub suffix: Option<Span<PPos>>,
-}
+                        let result = block.execute(engine, args.clone().with_ids(entered.clone()))?;
+                        res.res.extend(result.res);
+                        res.continues.extend(result.continues.iter().copied());
+                        looping = self.take_exits(engine, entered.union(&result.continues).copied(), args.store);
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RByteStrLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
-    }
-}
+                        // the paths still looping continue without `!expr`,
+                        // unless they run the rest of the loop at once
+                        if i == args.max_loop_iter - 1 {
+                            for id in looping.iter().copied() {
+                                engine[id].truncated = true;
+                                if !args.loop_summaries {
+                                    continue;
+                                }
+                                let Some(summary) = RLoop::summarise(engine, id, expr, block, args.clone())? else {
+                                    continue;
+                                };
+                                let cond = expr.to_term(args.store, &engine[id].sigma, IntTy::I32);
+                                let rest = new_assert(engine, id, cond.clone())?;
+                                args.scheduler.borrow_mut().forked(engine, rest, pos, 0);
+                                args.scheduler.borrow_mut().took(engine, id, pos, 1);
+                                engine[id].truncated = false;
+                                if engine[rest].pi.satisfiable {
+                                    res.res.extend(summary.apply(&mut engine[rest], &cond)?);
+                                    engine[rest].truncated = false;
+                                    res.continues.insert(rest);
+                                }
+                            }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RRawByteStrLit {
-    pub span: Span<PPos>,
-    pub text: Span<PPos>,
-    pub suffix: Option<Span<PPos>>,
-}
+                            if looping.iter().any(|id| engine[*id].truncated) {
+                                res.res.push(SymexRes::WhileLoopMaxIterHit);
+                            }
+                        }
+                    }
+                }
+            },
+            RLoop::For { var, expr, block, .. } => {
+                let name = var.into_string(args.store);
+                let pos = expr.span().start.key();
+                for bad_path in args.ids.iter().copied() {
+                    // only ranges are iterated, and paths looping over
+                    // anything else are cut off
+                    let RExpr::Range { start, end, inclusive, .. } = expr else {
+                        engine[bad_path].truncated = true;
+                        continue;
+                    };
+                    let ty = start.int_ty(args.store, &engine[bad_path].sigma)
+                        .or_else(|| end.int_ty(args.store, &engine[bad_path].sigma))
+                        .unwrap_or(IntTy::I32);
+
+                    let result = expr.execute(engine, args.clone().with_ids(HashSet::from([bad_path])).with_int_ty(ty))?;
+                    res.res.extend(result.res);
+                    res.continues.extend(result.continues.iter().copied());
+
+                    for path in result.continues.union(&HashSet::from([bad_path])).copied() {
+                        if !engine[path].pi.satisfiable || engine[path].returned || engine[path].suspended {
+                            continue;
+                        }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RRawByteStrLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.text.into_lisp(store))
-    }
-}
+                        // the range is evaluated once, before the first
+                        // iteration, and the loop variable shadows any
+                        // variable of its name until the loop ends
+                        let first = engine[path].term_as_var0(&start.to_term(args.store, &engine[path].sigma, ty));
+                        let last = engine[path].term_as_var0(&end.to_term(args.store, &engine[path].sigma, ty));
+                        let shadowed = engine[path].sigma.iter().find(|v| v.name == name).cloned();
+
+                        let mut looping = HashSet::from([path]);
+                        let mut prev = first.clone();
+                        for i in 0..args.max_loop_iter {
+                            let at = match i {
+                                0 => first.clone(),
+                                _ => SymTerm::bin(BinOp::Add, ty.signed, first.clone(), SymTerm::Int(i as i128, ty)),
+                            };
+                            // the value before `at` was below `last`, so `at`
+                            // does not overflow
+                            let cond = match (inclusive, i) {
+                                (true, 0) => SymTerm::bin(BinOp::LessThanEq, ty.signed, first.clone(), last.clone()),
+                                (true, _) => SymTerm::bin(BinOp::LessThan, ty.signed, prev.clone(), last.clone()),
+                                (false, _) => SymTerm::bin(BinOp::LessThan, ty.signed, at.clone(), last.clone()),
+                            };
+
+                            let mut next = HashSet::new();
+                            for id in std::mem::take(&mut looping) {
+                                let good_path = symex::new_assert_var0(engine, id, cond.clone())?;
+                                args.scheduler.borrow_mut().forked(engine, good_path, pos, 0);
+                                args.scheduler.borrow_mut().took(engine, id, pos, 1);
+                                engine[id].unbind_variable(&name, shadowed.clone());
+                                if engine[good_path].pi.satisfiable {
+                                    engine[good_path].bind_variable(&name, ty, at.clone());
+                                    res.continues.insert(good_path);
+                                    next.insert(good_path);
+                                } else if i == 0 {
+                                    res.res.extend(block.unreachable_queries(args.store));
+                                }
+                            }
+                            if next.is_empty() {
+                                break
+                            }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RByteLit {
-    pub span: Span<PPos>,
-    pub value: Span<PPos>,
-    pub suffix: Option<Span<PPos>>
-}
+                            let result = block.execute(engine, args.clone().with_ids(next.clone()))?;
+                            res.res.extend(result.res);
+                            res.continues.extend(result.continues.iter().copied());
+                            let ran: Vec<usize> = next.union(&result.continues).copied().collect();
+                            looping = self.take_exits(engine, ran.iter().copied(), args.store);
+                            for id in ran {
+                                if !looping.contains(&id) {
+                                    engine[id].unbind_variable(&name, shadowed.clone());
+                                }
+                            }
+                            prev = at;
+                        }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RByteLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.value.into_lisp(store))
+                        // the paths still looping continue without leaving
+                        // the range
+                        if !looping.is_empty() {
+                            res.res.push(SymexRes::ForLoopMaxIterHit);
+                        }
+                        for id in looping {
+                            engine[id].truncated = true;
+                            engine[id].unbind_variable(&name, shadowed.clone());
+                        }
+                    }
+                }
+            },
+        }
+
+        Ok(res)
     }
 }
 
-/// 
-/// A string where no escape sequences are allowed i.e. the string is
-/// exactly as it looks.
-/// 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RRawStrLit {
-    pub span: Span<PPos>,
-    pub text: Span<PPos>,
-    pub suffix: Option<Span<PPos>>,
-}
+impl RExpr {
+    /// 
+    /// This expression as the value a match on it inspects (or a let
+    /// destructures), on the path `engine`. A tuple or struct, whether a
+    /// literal or a variable, is inspected item by item. `structs` gives
+    /// the fields of struct literals and `ty` is the declared type of the
+    /// value, if any.
+    /// 
+    pub fn match_value<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, engine: &SymExEngine, structs: &HashMap<String, RStruct>, enums: &HashMap<String, REnum>, ty: Option<&RType>) -> MatchValue {
+        if let Some(value) = self.variant_value(store, engine, structs, enums, ty) {
+            return value;
+        }
+        match self {
+            RExpr::Group { expr, .. } => expr.match_value(store, engine, structs, enums, ty),
+            RExpr::Tuple { items, .. } => MatchValue::Tuple(items.iter().enumerate().map(|(i, item)| {
+                item.match_value(store, engine, structs, enums, ty.and_then(|ty| ty.item(i)))
+            }).collect()),
+            RExpr::Struct { path, fields, base, .. } => {
+                // the fields not given are those of the base
+                let base = base.as_ref().map(|base| base.match_value(store, engine, structs, enums, None));
+                let declared: Vec<(String, Option<&RType>)> = match structs.get(&path.into_string(store)) {
+                    Some(declared) => declared.fields(store).into_iter().map(|(field, ty)| (field, Some(ty))).collect(),
+                    None => fields.iter().map(|(field, _)| (field.into_string(store), None)).collect(),
+                };
+                MatchValue::Struct(declared.into_iter().filter_map(|(name, ty)| {
+                    let value = match fields.iter().find(|(field, _)| field.into_string(store) == name) {
+                        Some((_, expr)) => Some(expr.match_value(store, engine, structs, enums, ty)),
+                        None => base.as_ref().and_then(|base| base.field(&name)).cloned(),
+                    };
+                    value.map(|value| (name, value))
+                }).collect())
+            },
+            RExpr::Call { ident, args, .. } if matches!(structs.get(&ident.into_string(store)), Some(RStruct::Tuple { .. })) => {
+                let declared = structs[&ident.into_string(store)].fields(store);
+                MatchValue::Tuple(args.iter().zip(declared).map(|(arg, (_, ty))| arg.match_value(store, engine, structs, enums, Some(ty))).collect())
+            },
+            _ => {
+                if let Some(value) = self.place(store).and_then(|path| MatchValue::of_place(&engine.sigma, &path)) {
+                    return value;
+                }
+                // unwrapping a tuple or struct gives it as it is
+                if let RExpr::MethodCall { expr, method, .. } = self {
+                    if matches!(method.into_string(store).as_str(), "unwrap" | "expect") {
+                        let value = expr.match_value(store, engine, structs, enums, None);
+                        if let Some(item) = value.unwrapped().filter(|item| !matches!(item, MatchValue::Scalar(..))) {
+                            return item.clone();
+                        }
+                    }
+                }
+                if let Some((item_ty, items)) = self.array_items(store, &engine.sigma, ty.and_then(|ty| ty.array_of(store)).map(|(ty, _)| ty)) {
+                    return MatchValue::Array(items.iter().map(|item| MatchValue::Scalar(engine.term_as_var0(item), item_ty)).collect());
+                }
+                let ty = ty.and_then(|ty| IntTy::from_scalar_str(&ty.into_string(store)))
+                    .or_else(|| self.int_ty(store, &engine.sigma))
+                    .unwrap_or(IntTy::I32);
+                MatchValue::Scalar(engine.term_as_var0(&self.to_term(store, &engine.sigma, ty)), ty)
+            },
+        }
+    }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RRawStrLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
+    /// 
+    /// This expression as a value of an enum if it builds a variant: `None`,
+    /// `Some(x)`, `Shape::Circle(r)` or `Shape::Rect { w, h }`. The fields
+    /// are of the types of the variant in `ty`, if given.
+    /// 
+    fn variant_value<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, engine: &SymExEngine, structs: &HashMap<String, RStruct>, enums: &HashMap<String, REnum>, ty: Option<&RType>) -> Option<MatchValue> {
+        let path = match self {
+            // variables are lowercase, unit variants are not
+            RExpr::Var(name) if name.into_string(store).starts_with(|c: char| c.is_uppercase()) => name.into_string(store),
+            RExpr::Path(path, _) => path.into_string(store),
+            RExpr::Call { ident, .. } => ident.into_string(store),
+            RExpr::Struct { path, .. } => path.into_string(store),
+            _ => return None,
+        };
+        let (enum_name, tag) = resolve_variant(store, enums, &path_names(&path))?;
+        let args = match ty {
+            Some(RType::Template { name, args, .. }) if name.into_string(store) == enum_name => args.as_slice(),
+            _ => &[],
+        };
+        let variants = enum_variants(store, enums, &enum_name, args)?;
+        let (variant, declared) = &variants[tag];
+        let declared_ty = |field: &str| declared.iter().find(|(name, _)| name == field).and_then(|(_, ty)| *ty);
+
+        let payload = match self {
+            RExpr::Call { args, .. } => Some(MatchValue::Tuple(args.iter().enumerate().map(|(i, arg)| {
+                arg.match_value(store, engine, structs, enums, declared_ty(&i.to_string()))
+            }).collect())),
+            RExpr::Struct { fields, .. } => Some(MatchValue::Struct(fields.iter().map(|(field, expr)| {
+                let field = field.into_string(store);
+                let value = expr.match_value(store, engine, structs, enums, declared_ty(&field));
+                (field, value)
+            }).collect())),
+            _ => None,
+        };
+        Some(MatchValue::Enum {
+            name: enum_name,
+            tag: SymTerm::Int(tag as i128, IntTy::TAG),
+            payloads: payload.map(|payload| (variant.clone(), payload)).into_iter().collect(),
+        })
     }
-}
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RFloatLit {
-    pub span: Span<PPos>,
-    pub value: Span<PPos>,
-    pub exp: Option<Span<PPos>>,
-    pub value_exp_span: Span<PPos>,
-    pub suffix: Option<Span<PPos>>
+    /// 
+    /// The path of the variable, or item of a tuple variable, this
+    /// expression names: `t` for `t` and `t.1.0` for `t.1.0`.
+    /// 
+    pub fn place<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Option<String> {
+        match self {
+            RExpr::Var(name) => Some(name.into_string(store)),
+            RExpr::Group { expr, .. } => expr.place(store),
+            RExpr::Field { expr, field, .. } => Some(format!("{}.{}", expr.place(store)?, field.into_string(store))),
+            _ => None,
+        }
+    }
+
+    /// 
+    /// The value of this expression on a path that ran it, as a term in
+    /// terms of `sigma` along with its type, which is `ty` if nothing else
+    /// tells. `None` for expressions without a value, like assignments and
+    /// blocks without a tail.
+    /// 
+    pub fn value<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar], ty: IntTy) -> Option<(SymTerm, IntTy)> {
+        match self {
+            RExpr::Block(block) => block.value(store, sigma, ty),
+            RExpr::If(stmt) => stmt.value(store, sigma),
+            RExpr::AssignOp { .. } | RExpr::Macro { .. } | RExpr::Break { .. } | RExpr::Continue { .. } => None,
+            _ => {
+                let ty = self.int_ty(store, sigma).unwrap_or(ty);
+                Some((self.to_term(store, sigma, ty), ty))
+            },
+        }
+    }
 }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RFloatLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
+impl RPattern {
+    /// The names of the variables this pattern binds.
+    pub fn bound_names<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Vec<String> {
+        match self {
+            RPattern::Ident { ident, sub, .. } if !ident.into_string(store).starts_with(char::is_uppercase) => {
+                let mut names = vec![ident.into_string(store)];
+                names.extend(sub.iter().flat_map(|sub| sub.bound_names(store)));
+                names
+            },
+            RPattern::Tuple { items, .. } | RPattern::TupleStruct { items, .. } => items.iter().flat_map(|item| item.bound_names(store)).collect(),
+            RPattern::Struct { fields, .. } => fields.iter().flat_map(|(_, item)| item.bound_names(store)).collect(),
+            RPattern::Or { alts, .. } => alts.first().map(|alt| alt.bound_names(store)).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 
+    /// A value of the shape of this pattern whose ints are all unknown, of
+    /// the types in `ty` where given. Used to destructure a value that is
+    /// not known to be a tuple.
+    /// 
+    pub fn unknown_value<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, ty: Option<&RType>, engine: &mut SymExEngine) -> MatchValue {
+        match self {
+            RPattern::Tuple { items, .. } => MatchValue::Tuple(items.iter().enumerate().map(|(i, item)| {
+                item.unknown_value(store, ty.and_then(|ty| ty.item(i)), engine)
+            }).collect()),
+            _ => {
+                let ty = ty.and_then(|ty| IntTy::from_scalar_str(&ty.into_string(store))).unwrap_or(IntTy::I32);
+                MatchValue::Scalar(SymTerm::Var(engine.pi.fresh_int("tuple", ty)), ty)
+            },
+        }
+    }
+
+    /// 
+    /// An unknown value of the enum whose variant this pattern (or one of
+    /// its alternatives) names, whose tag is that of one of its variants on
+    /// the path `engine`. Used to match a value that is not known to be of
+    /// an enum, like what a call returns.
+    /// 
+    pub fn unknown_enum<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, enums: &HashMap<String, REnum>, engine: &mut SymExEngine) -> Result<Option<MatchValue>, SolverError> {
+        let path: Vec<String> = match self {
+            RPattern::Or { alts, .. } => {
+                for alt in alts {
+                    if let Some(value) = alt.unknown_enum(store, enums, engine)? {
+                        return Ok(Some(value));
+                    }
+                }
+                return Ok(None);
+            },
+            RPattern::Ident { ident, sub: None, .. } if ident.into_string(store).starts_with(char::is_uppercase) => vec![ident.into_string(store)],
+            RPattern::Path { path, .. } | RPattern::TupleStruct { path, .. } | RPattern::Struct { path, .. } => {
+                path.iter().map(|name| name.into_string(store)).collect()
+            },
+            _ => return Ok(None),
+        };
+        let Some((name, _)) = resolve_variant(store, enums, &path) else {
+            return Ok(None);
+        };
+        let Some(count) = enum_variants(store, enums, &name, &[]).map(|variants| variants.len()) else {
+            return Ok(None);
+        };
+        let tag = SymTerm::Var(engine.pi.fresh_int("tag", IntTy::TAG));
+        engine.new_assertion(&SymTerm::bin(BinOp::LessThan, false, tag.clone(), SymTerm::Int(count as i128, IntTy::TAG)))?;
+        Ok(Some(MatchValue::Enum { name, tag, payloads: Vec::new() }))
+    }
+
+    /// 
+    /// Declares the variables this parameter pattern binds as parameters of
+    /// the matching parts of the type `ty`, on the path `engine`.
+    /// 
+    pub fn declare<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, structs: &HashMap<String, RStruct>, enums: &HashMap<String, REnum>, ty: &RType, engine: &mut SymExEngine) -> Result<(), SolverError> {
+        match self {
+            RPattern::Ident { ident, .. } => {
+                let name = ident.into_string(store);
+                ty.declare(store, structs, enums, &name, name.clone(), engine)?;
+            },
+            RPattern::Tuple { items, .. } => {
+                for (i, item) in items.iter().enumerate() {
+                    if let Some(ty) = ty.item(i) {
+                        item.declare(store, structs, enums, ty, engine)?;
+                    }
+                }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// 
+    /// Returns the condition (in terms of the parameters) under which
+    /// `value` matches this pattern on the path `engine`, adding the
+    /// variables it binds to `binds`. A capitalised name is a constant or
+    /// unit variant rather than a variable, and a variant of an enum value
+    /// matches when its tag is that of the variant (`enums` gives those of
+    /// declared enums) and its fields match. What this pattern compares
+    /// against cannot be known yet (constants, or a shape that does not fit
+    /// the value) matches or not as an unknown `Bool` allows.
+    /// 
+    pub fn cond<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, enums: &HashMap<String, REnum>, value: &MatchValue, engine: &mut SymExEngine, binds: &mut Vec<(String, IntTy, SymTerm)>) -> SymTerm {
+        match (self, value) {
+            (RPattern::Wild { .. }, _) => SymTerm::Bool(true),
+            (RPattern::Or { alts, .. }, _) => {
+                // every alternative binds the same variables
+                let mut alt_binds = Vec::new();
+                let mut conds = alts.iter().map(|alt| {
+                    let mut bound = Vec::new();
+                    let cond = alt.cond(store, enums, value, engine, &mut bound);
+                    if alt_binds.is_empty() {
+                        alt_binds = bound;
+                    }
+                    cond
+                });
+                let first = conds.next().unwrap_or(SymTerm::Bool(false));
+                let cond = conds.fold(first, |a, b| SymTerm::bin(BinOp::Or, false, a, b));
+                binds.extend(alt_binds);
+                cond
+            },
+            (RPattern::Ident { ident, sub, .. }, _) if !ident.into_string(store).starts_with(char::is_uppercase) => {
+                binds.extend(value.cells(&ident.into_string(store)));
+                match sub {
+                    Some(sub) => sub.cond(store, enums, value, engine, binds),
+                    None => SymTerm::Bool(true),
+                }
+            },
+            (RPattern::Lit { lit, .. }, MatchValue::Scalar(term, ty)) => SymTerm::eq(term.clone(), lit.to_term(store, &[], *ty)),
+            (RPattern::Range { start, end, inclusive, .. }, MatchValue::Scalar(term, ty)) => {
+                let above = SymTerm::bin(BinOp::LessThanEq, ty.signed, start.to_term(store, &[], *ty), term.clone());
+                let op = if *inclusive { BinOp::LessThanEq } else { BinOp::LessThan };
+                SymTerm::and(above, SymTerm::bin(op, ty.signed, term.clone(), end.to_term(store, &[], *ty)))
+            },
+            (RPattern::Tuple { items, .. }, MatchValue::Tuple(values)) if items.len() == values.len() => {
+                items.iter().zip(values).fold(SymTerm::Bool(true), |cond, (item, value)| {
+                    SymTerm::and(cond, item.cond(store, enums, value, engine, binds))
+                })
+            },
+            (RPattern::TupleStruct { .. } | RPattern::Struct { .. }, MatchValue::Tuple(_) | MatchValue::Struct(_)) => self.fields_cond(store, enums, Some(value), engine, binds),
+            (_, MatchValue::Enum { name, tag, payloads }) => match self.variant_tag(store, enums, name) {
+                Some((variant, index)) => {
+                    let is_variant = SymTerm::eq(tag.clone(), SymTerm::Int(index as i128, IntTy::TAG));
+                    let payload = payloads.iter().find(|(v, _)| *v == variant).map(|(_, payload)| payload);
+                    SymTerm::and(is_variant, self.fields_cond(store, enums, payload, engine, binds))
+                },
+                None => SymTerm::Var(engine.pi.fresh_const("pattern", "Bool")),
+            },
+            // the fields of a value whose shape is unknown are unknown too
+            (RPattern::TupleStruct { .. } | RPattern::Struct { .. }, _) => {
+                let cond = SymTerm::Var(engine.pi.fresh_const("pattern", "Bool"));
+                SymTerm::and(cond, self.fields_cond(store, enums, None, engine, binds))
+            },
+            _ => SymTerm::Var(engine.pi.fresh_const("pattern", "Bool")),
+        }
+    }
+
+    /// The name and tag of the variant of the enum `enum_name` this
+    /// pattern names, if it names one.
+    fn variant_tag<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, enums: &HashMap<String, REnum>, enum_name: &str) -> Option<(String, usize)> {
+        let variant = match self {
+            RPattern::Ident { ident, sub: None, .. } => ident.into_string(store),
+            RPattern::Path { path, .. } | RPattern::TupleStruct { path, .. } | RPattern::Struct { path, .. } => path.last()?.into_string(store),
+            _ => return None,
+        };
+        let (_, tag) = resolve_variant(store, enums, &[enum_name.to_string(), variant.clone()])?;
+        Some((variant, tag))
+    }
+
+    /// 
+    /// The condition under which the fields of `payload` (a tuple or struct)
+    /// match the items or fields of this tuple struct or struct pattern,
+    /// adding the variables they bind to `binds`. The fields `payload` does
+    /// not have are unknown.
+    /// 
+    fn fields_cond<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, enums: &HashMap<String, REnum>, payload: Option<&MatchValue>, engine: &mut SymExEngine, binds: &mut Vec<(String, IntTy, SymTerm)>) -> SymTerm {
+        let fields: Vec<(String, &RPattern)> = match self {
+            RPattern::TupleStruct { items, .. } => items.iter().enumerate().map(|(i, item)| (i.to_string(), item)).collect(),
+            RPattern::Struct { fields, .. } => fields.iter().map(|(field, item)| (field.into_string(store), item)).collect(),
+            _ => return SymTerm::Bool(true),
+        };
+        fields.into_iter().fold(SymTerm::Bool(true), |cond, (field, item)| {
+            let value = match payload.and_then(|payload| payload.field(&field)) {
+                Some(value) => value.clone(),
+                None => item.unknown_value(store, None, engine),
+            };
+            SymTerm::and(cond, item.cond(store, enums, &value, engine, binds))
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RSFloatLit {
-    pub span: Span<PPos>,
-    pub neg: bool,
-    pub lit: RFloatLit,
+impl RMatch {
+    /// 
+    /// The SMT-LIB symbol the value of this match is bound to in `sigma` once
+    /// an arm has been taken: its source text as a quoted symbol, as for
+    /// calls.
+    /// 
+    pub fn value_symbol<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> String {
+        format!("|{}|", self.span.into_string(store).replace('|', "/"))
+    }
+
+    /// Reports every query in the arms of this match as unreachable.
+    pub fn unreachable_queries<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Vec<SymexRes> {
+        self.arms.iter()
+            .filter_map(|arm| match &arm.expr {
+                RExpr::Block(block) => Some(block.unreachable_queries(store)),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
 }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RSFloatLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
+impl <Store: ParseStore<PPos, char> + ?Sized> Execute<Store> for RMatch {
+    fn execute<'a>(&self, engine: &mut Vec<SymExEngine>, args: ExecuteArgs<'a, Store>) -> Result<ExOk, ExErr> {
+        let mut res = ExOk { cont: true, res: Vec::new(), continues: HashSet::new() };
+        let pos = self.span.start.key();
+        let mut reached = vec![false; self.arms.len()];
+        let mut entered = false;
+
+        for root in args.ids.iter().copied() {
+            let result = self.expr.execute(engine, args.clone().with_ids(HashSet::from([root])))?;
+            res.res.extend(result.res);
+            res.continues.extend(result.continues.iter().copied());
+
+            for path in result.continues.union(&HashSet::from([root])).copied() {
+                if !engine[path].pi.satisfiable || engine[path].returned || engine[path].suspended || engine[path].exit.is_some() {
+                    continue;
+                }
+                let value = match self.expr.match_value(args.store, &engine[path], &args.structs, &args.enums, None) {
+                    // what a call returns is unknown, but its arms say of
+                    // which enum it is
+                    value @ MatchValue::Scalar(..) => {
+                        let mut found = None;
+                        for arm in self.arms.iter() {
+                            found = arm.pattern.unknown_enum(args.store, &args.enums, &mut engine[path])?;
+                            if found.is_some() {
+                                break;
+                            }
+                        }
+                        found.unwrap_or(value)
+                    },
+                    value => value,
+                };
+                entered = true;
+
+                // each arm is tried on the paths no earlier arm was taken on
+                let mut rest = vec![path];
+                for (i, arm) in self.arms.iter().enumerate() {
+                    for bad_path in std::mem::take(&mut rest) {
+                        let mut binds = Vec::new();
+                        let cond = arm.pattern.cond(args.store, &args.enums, &value, &mut engine[bad_path], &mut binds);
+                        let good_path = symex::new_assert_var0(engine, bad_path, cond)?;
+                        args.scheduler.borrow_mut().forked(engine, good_path, pos, i);
+                        args.scheduler.borrow_mut().took(engine, bad_path, pos, i + 1);
+                        if engine[bad_path].pi.satisfiable {
+                            rest.push(bad_path);
+                        }
+                        if !engine[good_path].pi.satisfiable {
+                            continue;
+                        }
+                        reached[i] = true;
+
+                        // the variables the pattern binds shadow any of
+                        // their names in the guard and the arm
+                        let shadowed: Vec<(String, Option<SymVar>)> = binds.into_iter()
+                            .map(|(name, ty, value)| {
+                                let old = engine[good_path].bind_variable(&name, ty, value);
+                                (name, old)
+                            })
+                            .collect();
+                        let unbind = |engine: &mut Vec<SymExEngine>, id: usize| {
+                            for (name, old) in shadowed.iter().rev() {
+                                engine[id].unbind_variable(name, old.clone());
+                            }
+                        };
+
+                        let mut live = HashSet::from([good_path]);
+                        if let Some(guard) = &arm.guard {
+                            let result = guard.execute(engine, args.clone().with_ids(live.clone()).with_int_ty(IntTy::I32))?;
+                            res.res.extend(result.res);
+                            live.extend(result.continues);
+
+                            // a path the guard fails on goes on to the next arm
+                            let guard_pos = guard.span().start.key();
+                            for id in std::mem::take(&mut live) {
+                                if !engine[id].pi.satisfiable {
+                                    continue;
+                                }
+                                let cond = guard.to_term(args.store, &engine[id].sigma, IntTy::I32);
+                                let guarded = new_assert(engine, id, cond)?;
+                                args.scheduler.borrow_mut().forked(engine, guarded, guard_pos, 0);
+                                args.scheduler.borrow_mut().took(engine, id, guard_pos, 1);
+                                if engine[guarded].pi.satisfiable {
+                                    live.insert(guarded);
+                                }
+                                if engine[id].pi.satisfiable {
+                                    unbind(engine, id);
+                                    rest.push(id);
+                                }
+                            }
+                        }
+
+                        let result = arm.expr.execute(engine, args.clone().with_ids(live.clone()))?;
+                        res.res.extend(result.res);
+                        for id in live.union(&result.continues).copied() {
+                            let ends_here = engine[id].pi.satisfiable && !engine[id].returned && engine[id].exit.is_none();
+                            if let (true, Some((value, ty))) = (ends_here, arm.expr.value(args.store, &engine[id].sigma, args.int_ty)) {
+                                let value = engine[id].term_as_var0(&value);
+                                engine[id].bind_call(self.value_symbol(args.store), ty, value);
+                            }
+                            unbind(engine, id);
+                            res.continues.insert(id);
+                        }
+                    }
+                }
+
+                // no arm matched these, which the compiler would not allow
+                for id in rest {
+                    if let Some(model) = engine[id].model() {
+                        res.res.push(SymexRes::MatchNotExhaustive { span: self.span.clone(), model });
+                    }
+                    engine[id].pi.satisfiable = false;
+                }
+            }
+        }
+
+        // a call only sees the arguments of its caller, so arms are
+        // unreachable only if the function on its own never takes them
+        for (arm, reached) in self.arms.iter().zip(reached) {
+            if entered && !reached && args.call_depth == 0 {
+                res.res.push(SymexRes::UnreachableArm { span: arm.span.clone() });
+                if let RExpr::Block(block) = &arm.expr {
+                    res.res.extend(block.unreachable_queries(args.store));
+                }
+            }
+        }
+
+        Ok(res)
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RDecLit {
-    pub span: Span<PPos>,
-    pub value: Span<PPos>,
+pub trait IntoLisp<Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> {
+    fn into_lisp(&self, store: &Store) -> String;
 }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RDecLit {
+impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> IntoLisp<Store, Pos> for Span<Pos> {
     fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
+        self.into_string(store)
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RBinLit {
-    pub span: Span<PPos>,
-    pub value: Span<PPos>,
+impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos, T: IntoLisp<Store, Pos> + ?Sized> IntoLisp<Store, Pos> for Box<T> {
+    fn into_lisp(&self, store: &Store) -> String {
+        (**self).into_lisp(store)
+    }
 }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RBinLit {
+impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos, T: IntoLisp<Store, Pos>> IntoLisp<Store, Pos> for Vec<T> {
     fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
+        let mut out = String::new();
+        if self.len() > 0 {
+            out.extend(self[0].into_lisp(store).chars());
+
+            for i in 1..self.len() {
+                out.push(' ');
+                out.extend(self[i].into_lisp(store).chars());
+            }
+        }
+        out
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct ROctLit {
-    pub span: Span<PPos>,
-    pub value: Span<PPos>,
+pub struct RParam {
+    pub mutable: Option<Span<PPos>>,
+    /// The name of the parameter, or the tuple pattern it is destructured
+    /// by.
+    pub pattern: RPattern,
+    pub ty: RType,
 }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for ROctLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
+impl RParam {
+    /// The name of this parameter, unless it is destructured.
+    pub fn ident(&self) -> Option<&Span<PPos>> {
+        match &self.pattern {
+            RPattern::Ident { ident, sub: None, .. } => Some(ident),
+            _ => None,
+        }
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct RHexLit {
+pub struct RBlock {
     pub span: Span<PPos>,
-    pub value: Span<PPos>,
+    pub statements: Vec<RStatement>,
 }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RHexLit {
-    fn into_lisp(&self, store: &Store) -> String {
-        format!("{}", self.span.into_lisp(store))
-    }
+/// 
+/// An if statement or chain of if statments.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RIf {
+    /// The span of the `RIf`.
+    span: Span<PPos>,
+    /// The list of if statements.
+    ifs: Vec<(RExpr, RBlock)>,
+    /// The optional else block to run if all if statements fail.
+    else_stmt: Option<RBlock>
+}
+
+impl RIf {
+    pub fn span(&self) -> Span<PPos> {
+        self.span.clone()
+    } 
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub enum RIntLit {
-    DecLit {
-        span: Span<PPos>,
-        lit: RDecLit,
-        suffix: Option<Span<PPos>>,
-    },
-    BinLit {
+pub enum RLoop {
+    Infinite {
         span: Span<PPos>,
-        lit: RBinLit,
-        suffix: Option<Span<PPos>>,
+        label: Option<Span<PPos>>,
+        block: RBlock,
     },
-    OctLit {
+    While {
         span: Span<PPos>,
-        lit: ROctLit,
-        suffix: Option<Span<PPos>>,
+        label: Option<Span<PPos>>,
+        expr: RExpr,
+        block: RBlock,
     },
-    HexLit {
+    For {
         span: Span<PPos>,
-        lit: RHexLit,
-        suffix: Option<Span<PPos>>,
+        label: Option<Span<PPos>>,
+        var: Span<PPos>,
+        expr: RExpr,
+        block: RBlock,
     },
 }
 
-impl RIntLit {
+impl RLoop {
     pub fn span(&self) -> Span<PPos> {
-        use RIntLit::*;
+        use RLoop::*;
         match self {
-            DecLit { span, .. } => span.clone(),
-            BinLit { span, .. } => span.clone(),
-            OctLit { span, .. } => span.clone(),
-            HexLit { span, .. } => span.clone(),
+            Infinite { span, .. } => span.clone(),
+            While { span, .. } => span.clone(),
+            For { span, .. } => span.clone(),
+        }
+    } 
+
+    /// The label of this loop (`'outer` in `'outer: loop { .. }`).
+    pub fn label(&self) -> Option<&Span<PPos>> {
+        use RLoop::*;
+        match self {
+            Infinite { label, .. } | While { label, .. } | For { label, .. } => label.as_ref(),
         }
     }
 }
 
-impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RIntLit {
-    fn into_lisp(&self, store: &Store) -> String {
+/// 
+/// A match expression.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RMatch {
+    pub span: Span<PPos>,
+    /// The value being matched on.
+    pub expr: RExpr,
+    pub arms: Vec<RMatchArm>,
+}
+
+/// 
+/// An arm of a match: `pattern if guard => expr`.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RMatchArm {
+    pub span: Span<PPos>,
+    pub pattern: RPattern,
+    pub guard: Option<RExpr>,
+    pub expr: RExpr,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RPattern {
+    /// `_`
+    Wild { span: Span<PPos> },
+    /// A literal, which may be negated (`-1`).
+    Lit { span: Span<PPos>, lit: Box<RExpr> },
+    /// `start..=end`, or `start..end` when not `inclusive`.
+    Range { span: Span<PPos>, start: Box<RExpr>, end: Box<RExpr>, inclusive: bool },
+    /// A variable bound to the value, checked against `sub` in `x @ sub`.
+    Ident { span: Span<PPos>, ident: Span<PPos>, sub: Option<Box<RPattern>> },
+    /// A path to a constant or unit variant (`Ordering::Less`).
+    Path { span: Span<PPos>, path: Vec<Span<PPos>> },
+    /// A tuple struct or variant (`Some(x)`).
+    TupleStruct { span: Span<PPos>, path: Vec<Span<PPos>>, items: Vec<RPattern> },
+    /// A struct or struct variant (`Shape::Rect { w, h: 2, .. }`), whose
+    /// other fields are ignored when `rest`.
+    Struct { span: Span<PPos>, path: Vec<Span<PPos>>, fields: Vec<(Span<PPos>, RPattern)>, rest: bool },
+    Tuple { span: Span<PPos>, items: Vec<RPattern> },
+    /// `a | b | ..`
+    Or { span: Span<PPos>, alts: Vec<RPattern> },
+}
+
+impl RPattern {
+    pub fn span(&self) -> Span<PPos> {
+        use RPattern::*;
         match self {
-            RIntLit::DecLit { lit, .. } => lit.into_lisp(store),
-            RIntLit::BinLit { lit, .. } => lit.into_lisp(store),
-            RIntLit::OctLit { lit, .. } => lit.into_lisp(store),
-            RIntLit::HexLit { lit, .. } => lit.into_lisp(store),
+            Wild { span } | Lit { span, .. } | Range { span, .. } | Ident { span, .. } | Path { span, .. }
+                | TupleStruct { span, .. } | Struct { span, .. } | Tuple { span, .. } | Or { span, .. } => span.clone(),
         }
     }
 }
 
 /// 
-/// Parses a file and returns the result.
+/// The value a match inspects: an int or bool (in terms of the parameters),
+/// the items of a tuple, array or fields of a struct, or a value of an
+/// enum.
 /// 
-pub fn parse_file(file_text: &str) -> ParseResult<RCrate, String, PPos> {
-    //println!("Parsing: \"{}\"", file_text);
+#[derive(Debug, Clone)]
+pub enum MatchValue {
+    Scalar(SymTerm, IntTy),
+    Tuple(Vec<MatchValue>),
+    Struct(Vec<(String, MatchValue)>),
+    Array(Vec<MatchValue>),
+    /// A value of the enum `name` whose variant is the one at `tag`, with
+    /// the fields (a tuple or struct) of the variants it may be by name.
+    Enum { name: String, tag: SymTerm, payloads: Vec<(String, MatchValue)> },
+}
 
-    // create `expr` (it requires a number of recursive child nodes)
-    srule!(w, w_rule);
-    srule!(alpha, alpha_rule);
-    srule!(numeric, numeric_rule);
-    srule!(ident, ident_rule);
-    srule!(param, param_rule);
-    srule!(params, params_rule);
-    srule!(return_type, return_type_rule);
-    srule!(expr, expr_rule);
-    srule!(mul_or_div, mul_or_div_rule);
-    srule!(power, power_rule);
-    srule!(value, value_rule);
-    srule!(block, block_rule);
-    srule!(type_tuple, type_tuple_rule);
-    srule!(ty, ty_rule);
-    srule!(statement, statement_rule);
-    srule!(func, func_rule);
-    srule!(file, file_rule);
-    srule!(item, item_rule);
-    srule!(vis, vis_rule);
-
-    srule!(literal_expression, literal_expression_rule);
-    srule!(char_literal, char_literal_rule);
-    srule!(string_literal, string_literal_rule);
-    srule!(raw_string_literal, raw_string_literal_rule);
-    srule!(byte_literal, byte_literal_rule);
-    srule!(byte_string_literal, byte_string_literal_rule);
-    srule!(raw_byte_string_literal, raw_byte_string_literal_rule);
-    srule!(integer_literal, integer_literal_rule);
-    srule!(float_literal, float_literal_rule);
-    srule!(bool_literal, bool_literal_rule);
-    srule!(dec_literal, dec_literal_rule);
-    srule!(bin_literal, bin_literal_rule);
-    srule!(oct_literal, oct_literal_rule);
-    srule!(hex_literal, hex_literal_rule);
-
-    srule!(quote_escape, quote_escape_rule);
-    srule!(ascii_escape, ascii_escape_rule);
-    srule!(unicode_escape, unicode_escape_rule);
-    srule!(oct_digit, oct_digit_rule);
-    srule!(hex_digit, hex_digit_rule);
-    srule!(string_continue, string_continue_rule);
-    srule!(suffix, suffix_rule);
-    srule!(suffix_no_e, suffix_no_e_rule);
-    srule!(raw_string_content, raw_string_content_rule);
-    srule!(ascii_for_char, ascii_for_char_rule);
-    srule!(byte_escape, byte_escape_rule);
-    srule!(ascii_for_string, ascii_for_string_rule);
-    srule!(raw_byte_string_content, raw_byte_string_content_rule);
-    srule!(ascii, ascii_rule);
-    srule!(dec_digit, dec_digit_rule);
-    srule!(bin_digit, bin_digit_rule);
-    srule!(float_exponent, float_exponent_rule);
-
-    srule!(return_statement, return_statement_rule);
-    srule!(let_statement, let_statement_rule);
-    srule!(expr_semi_statement, expr_semi_statement_rule);
-    srule!(semi_statement, semi_statement_rule);
-
-    srule!(if_statement, if_statement_rule);
-    srule!(loop_statement, loop_statement_rule);
-    srule!(infinite_loop, infinite_loop_rule);
-    srule!(while_loop, while_loop_rule);
-    srule!(for_loop, for_loop_rule);
-
-    srule!(logic_op, logic_op_rule);
-    srule!(add_or_sub, add_or_sub_rule);
-    srule!(assign, assign_rule);
+impl MatchValue {
+    /// 
+    /// The value of the tuple, struct or enum variable `name` in `sigma`,
+    /// read from the cells of its items or fields (and tag), or `None` if
+    /// there is no such variable. The items of a tuple (or tuple struct)
+    /// are named by their index.
+    /// 
+    pub fn of_place(sigma: &[SymVar], name: &str) -> Option<MatchValue> {
+        let prefix = format!("|{}.", name);
+        let mut fields: Vec<&str> = Vec::new();
+        for v in sigma.iter() {
+            if let Some(rest) = v.name.strip_prefix(&prefix) {
+                let field = &rest[..rest.find(['.', '[', ':', '|']).unwrap_or(rest.len())];
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+        }
 
-    srule!(comment, comment_rule);
-    srule!(line_comment, line_comment_rule);
-    srule!(block_comment, block_comment_rule);
-    srule!(inner_line_doc, inner_line_doc_rule);
-    srule!(inner_block_doc, inner_block_doc_rule);
-    srule!(outer_line_doc, outer_line_doc_rule);
-    srule!(outer_block_doc, outer_block_doc_rule);
-    srule!(block_comment_or_doc, block_comment_or_doc_rule);
+        let mut items: Vec<(String, MatchValue)> = fields.into_iter().filter_map(|field| {
+            let cell = field_cell(name, field);
+            let value = match sigma.iter().find(|v| v.name == cell) {
+                Some(v) => Some(MatchValue::Scalar(v.var0.clone(), v.ty)),
+                None => {
+                    let path = format!("{}.{}", name, field);
+                    MatchValue::of_place(sigma, &path).or_else(|| MatchValue::of_array(sigma, &path))
+                },
+            };
+            value.map(|value| (field.to_string(), value))
+        }).collect();
+        if let Some((tag, enum_name)) = tag_of(sigma, name) {
+            return Some(MatchValue::Enum { name: enum_name.to_string(), tag: tag.var0.clone(), payloads: items });
+        }
+        if items.is_empty() {
+            return None;
+        }
 
-    // define function to produce "panic" uniform messages of parse
-    let panic = &|pos: Span<PPos>, fn_name: &str, message: &str| -> String {
-        format!("{}: ({}) {}", pos, fn_name, message)
-    };
+        if items.iter().all(|(field, _)| field.parse::<usize>().is_ok()) {
+            items.sort_by_key(|(field, _)| field.parse::<usize>().unwrap_or(0));
+            return Some(MatchValue::Tuple(items.into_iter().map(|(_, item)| item).collect()));
+        }
+        Some(MatchValue::Struct(items))
+    }
 
-    // IDENTIFIERS
+    /// The value of the array variable `name` in `sigma`, read from its
+    /// cells, or `None` if there is no such array.
+    pub fn of_array(sigma: &[SymVar], name: &str) -> Option<MatchValue> {
+        let cells = array_cells(sigma, name);
+        (!cells.is_empty()).then(|| MatchValue::Array(cells.iter().map(|v| MatchValue::Scalar(v.var0.clone(), v.ty)).collect()))
+    }
 
-    let isolated_cr = &SpanOf(('\r', Not('\n')));
+    /// The field of the `Some` or `Ok` variant of this `Option` or `Result`,
+    /// the value `unwrap` returns, if known.
+    pub fn unwrapped(&self) -> Option<&MatchValue> {
+        let MatchValue::Enum { name, payloads, .. } = self else { return None };
+        let (ok, _) = ok_variant(name)?;
+        payloads.iter().find(|(variant, _)| variant == ok)?.1.field("0")
+    }
 
-    // unicode groups
-    let xid_start = &MapPValue(|span, ch| {
-        if UnicodeXID::is_xid_start(ch) {
-            Okay(span.clone(), span.end)
-        } else {
-            Error(panic(span, "xid_start", "expected character in the [:XID_Start:] unicode group"))
+    /// The item or field of this value named `field`.
+    pub fn field(&self, field: &str) -> Option<&MatchValue> {
+        match self {
+            MatchValue::Scalar(..) | MatchValue::Array(..) | MatchValue::Enum { .. } => None,
+            MatchValue::Tuple(items) => items.get(field.parse::<usize>().ok()?),
+            MatchValue::Struct(fields) => fields.iter().find(|(name, _)| name == field).map(|(_, item)| item),
         }
-    });
-
-//    let xid_continue = MapPValue(|span, ch| {
-//        if UnicodeXID::is_xid_continue(ch) {
-//            Okay(span.clone(), span.end)
-//        } else {
-//            Error(panic(span, "xid_continue", "expected character in the [:XID_Continue:] unicode group"))
-//        }
-//    });
-//    let xid_continue = &xid_continue;
-
-    // --- COMMENTS ---
+    }
 
-    comment_rule.set(Funnel8(
-        MapV(("//", OneOf2(Not(Funnel(['/', '!', '\n'])), "//"), "symex", SpanOf(ZeroOrMore((Not('\n'), AnyV())))), |(_, _, symex, follow)| RComment::Symex { symex, follow }),
-        line_comment,
-        block_comment,
-        inner_line_doc,
-        inner_block_doc,
-        outer_line_doc,
-        outer_block_doc,
-        block_comment_or_doc,
-    ));
+    /// 
+    /// The variables that bind `name` to this value: `name` itself for an
+    /// int or bool, the cell (`array_cell`) of every item of an array, the
+    /// cell (`field_cell`) of every item of a tuple or field of a struct, or
+    /// the tag cell (`tag_cell`) and fields of the variants of an enum.
+    /// 
+    pub fn cells(&self, name: &str) -> Vec<(String, IntTy, SymTerm)> {
+        let fields: Vec<(String, &MatchValue)> = match self {
+            MatchValue::Scalar(term, ty) => return vec![(name.to_string(), *ty, term.clone())],
+            MatchValue::Array(items) => return items.iter().enumerate().flat_map(|(i, item)| match item {
+                MatchValue::Scalar(term, ty) => vec![(array_cell(name, i), *ty, term.clone())],
+                _ => Vec::new(),
+            }).collect(),
+            MatchValue::Tuple(items) => items.iter().enumerate().map(|(i, item)| (i.to_string(), item)).collect(),
+            MatchValue::Struct(fields) => fields.iter().map(|(field, item)| (field.clone(), item)).collect(),
+            MatchValue::Enum { name: enum_name, tag, payloads } => {
+                let mut cells = vec![(tag_cell(name, enum_name), IntTy::TAG, tag.clone())];
+                cells.extend(payloads.iter().flat_map(|(variant, payload)| payload.cells(&format!("{}.{}", name, variant))));
+                return cells;
+            },
+        };
+        fields.into_iter().flat_map(|(field, item)| match item {
+            MatchValue::Scalar(term, ty) => vec![(field_cell(name, &field), *ty, term.clone())],
+            _ => item.cells(&format!("{}.{}", name, field)),
+        }).collect()
+    }
+}
 
-    line_comment_rule.set(MapV(
-        Spanned(OneOf2(
-            ("//", OneOf2(Not(Funnel(['/', '!', '\n'])), "//"), SpanOf(ZeroOrMore((Not('\n'), AnyV())))),
-            "//"
-        )),
-        |(span, any_of_two)| {
-            match any_of_two {
-                AnyOf2::Child1((_, _, text)) => RComment::Line { span, text },
-                AnyOf2::Child2(span)         => RComment::Line { span: span.clone(), text: span },
-            }
-        }
-    ));
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RType {
+    Array {
+        span: Span<PPos>,
+        /// The type of every item in the array (since arrays can only hold 1
+        /// type of item, this is that type).
+        item_type: Box<Self>,
+        /// The number of items in the array.
+        item_number: Box<RExpr>,
+    },
+    Tuple {
+        span: Span<PPos>,
+        /// The types in the tuple, stored in the same order as given.
+        types: Vec<Self>,
+    },
+    Template {
+        span: Span<PPos>,
+        /// The name of the type.
+        name: Span<PPos>,
+        /// The arguments for the type.
+        args: Vec<Self>,
+    },
+}
 
-    block_comment_rule.set(MapV(
-        Spanned(Surround(
-            "/*",
-            SpanOf((
-                OneOf3(
-                    Not(Funnel(['*', '!'])),
-                    "**",
-                    block_comment_or_doc
-                ),
-                ZeroOrMore(OneOf2(
-                    block_comment_or_doc,
-                    (Not("*/"), AnyV())
-                )),
-            )),
-            "*/",
-            |_, _, e| e,
-            |_, start_span, _, _| panic(start_span, "block_comment", "expected end to this block comment"),
-        )),
-        |(span, (_, text, _))| {
-            RComment::Block { span, text }
+impl RType {
+    /// The item type and length of this type if it is an array of a literal
+    /// number of ints or bools.
+    pub fn array_of<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Option<(IntTy, usize)> {
+        match self {
+            RType::Array { item_type, item_number, .. } => {
+                let ty = IntTy::from_scalar_str(&item_type.into_string(store))?;
+                match &**item_number {
+                    RExpr::Lit(RLit::Integer(len)) => Some((ty, len.value(store) as usize)),
+                    _ => None,
+                }
+            },
+            _ => None,
         }
-    ));
+    }
 
-    inner_line_doc_rule.set(MapV(
-        Spanned((
-            "//!",
-            SpanOf(ZeroOrMore((Not(OneOf2('\n', isolated_cr)), AnyV())))
-        )),
-        |(span, (_, text))| {
-            RComment::InnerLineDoc { span, text }
+    /// The type of item `i` of this type if it is a tuple.
+    pub fn item(&self, i: usize) -> Option<&RType> {
+        match self {
+            RType::Tuple { types, .. } => types.get(i),
+            _ => None,
         }
-    ));
+    }
 
-    inner_block_doc_rule.set(MapV(
-        Spanned(Surround(
-            "/*!",
-            SpanOf(ZeroOrMore(OneOf2(block_comment_or_doc, (Not(OneOf2("*/", isolated_cr)), AnyV())))),
-            "*/",
-            |_, _, e| e,
-            |_, start_span, _, _| panic(start_span, "inner_block_doc", "expected end to this block doc comment"),
-        )),
-        |(span, (_, text, _))| {
-            RComment::InnerBlockDoc { span, text }
+    /// 
+    /// Declares a parameter of this type on the path `engine`: `symbol` for
+    /// an int or bool, the cells of an array (`array_cell`), those of
+    /// every item of a tuple or field of a struct in `structs`
+    /// (`field_cell`), or the tag cell (`tag_cell`) and fields of every
+    /// variant of an enum in `enums`, `Option` or `Result`. `path` names
+    /// the array, tuple, struct or enum.
+    /// 
+    pub fn declare<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, structs: &HashMap<String, RStruct>, enums: &HashMap<String, REnum>, path: &str, symbol: String, engine: &mut SymExEngine) -> Result<(), SolverError> {
+        if let RType::Template { name, args, .. } = self {
+            let name = name.into_string(store);
+            if let Some(variants) = enum_variants(store, enums, &name, args) {
+                engine.new_enum(path, &name, variants.iter().map(|(variant, _)| variant.clone()).collect())?;
+                for (variant, fields) in variants {
+                    let variant = format!("{}.{}", path, variant);
+                    for (field, ty) in fields.into_iter().filter_map(|(field, ty)| Some((field, ty?))) {
+                        ty.declare(store, structs, enums, &format!("{}.{}", variant, field), field_cell(&variant, &field), engine)?;
+                    }
+                }
+                return Ok(());
+            }
         }
-    ));
 
-    outer_line_doc_rule.set(MapV(
-        Spanned((
-            "///",
-            SpanOf(Maybe((Not('/'), ZeroOrMore(Not(OneOf2('\n', isolated_cr))))))
-        )),
-        |(span, (_, text))| {
-            RComment::OuterLineDoc { span, text }
+        let declared = match self {
+            RType::Template { name, .. } => structs.get(&name.into_string(store)),
+            _ => None,
+        };
+        match (self.array_of(store), self, declared) {
+            (Some((ty, len)), _, _) => engine.new_array(path, ty, len),
+            (None, RType::Tuple { types, .. }, _) => {
+                for (i, ty) in types.iter().enumerate() {
+                    ty.declare(store, structs, enums, &format!("{}.{}", path, i), tuple_cell(path, i), engine)?;
+                }
+            },
+            (None, _, Some(declared)) => {
+                for (field, ty) in declared.fields(store) {
+                    ty.declare(store, structs, enums, &format!("{}.{}", path, field), field_cell(path, &field), engine)?;
+                }
+            },
+            (None, _, None) => engine.new_variable(symbol, self.into_string(store)),
         }
-    ));
+        Ok(())
+    }
 
-    outer_block_doc_rule.set(MapV(
-        Spanned(Surround(
-            "/**",
-            SpanOf((OneOf2(Not('*'), block_comment_or_doc), ZeroOrMore(OneOf2(block_comment_or_doc, (Not(OneOf2("*/", isolated_cr)), AnyV()))))),
-            "*/",
-            |_, _, e| e,
-            |_, start_span, _, _| panic(start_span, "inner_block_doc", "expected end to this block doc comment"),
-        )),
-        |(span, (_, text, _))| {
-            RComment::OuterBlockDoc { span, text }
+    pub fn into_string<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> String {
+        match self {
+            RType::Array { span, .. } => span.into_string(store),
+            RType::Tuple { span, .. } => span.into_string(store),
+            RType::Template { span, .. } => span.into_string(store),
         }
-    ));
+    }
+}
 
-    block_comment_or_doc_rule.set(
-        MapV(
-            OneOf3(block_comment, outer_block_doc, inner_block_doc),
-            |any_of_three| {
-                match any_of_three {
-                    AnyOf3::Child1(v) => v,
-                    AnyOf3::Child2(v) => v,
-                    AnyOf3::Child3(v) => v,
-                }
-            }
-        )
-    );
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RExpr {
+    Lit(RLit),
+    Var(Span<PPos>),
+    Path(Span<PPos>, Vec<Span<PPos>>),
+    Block(RBlock),
+    If(Box<RIf>),
+    Loop(Box<RLoop>),
+    Match(Box<RMatch>),
+    Group { span: Span<PPos>, expr: Box<RExpr> },
+    /// `(a, b, ..)`, with at least two items or a trailing comma.
+    Tuple { span: Span<PPos>, items: Vec<RExpr> },
+    /// `[a, b, ..]`
+    Array { span: Span<PPos>, items: Vec<RExpr> },
+    /// `[item; len]`
+    ArrayRepeat { span: Span<PPos>, item: Box<RExpr>, len: Box<RExpr> },
+    /// `expr[index]`
+    Index { span: Span<PPos>, expr: Box<RExpr>, index: Box<RExpr> },
+    /// `expr.field`, where `field` is a struct field or the index of a
+    /// tuple item.
+    Field { span: Span<PPos>, expr: Box<RExpr>, field: Span<PPos> },
+    /// `P { x: a, y, ..base }`, where a field given by name alone (`y`)
+    /// takes the variable of that name.
+    Struct { span: Span<PPos>, path: Span<PPos>, fields: Vec<(Span<PPos>, RExpr)>, base: Option<Box<RExpr>> },
 
-    // - SUFFIX -
+    Call      { span: Span<PPos>, ident: Span<PPos>, args: Vec<RExpr> },
+    /// `expr.method(args)`
+    MethodCall { span: Span<PPos>, expr: Box<RExpr>, method: Span<PPos>, args: Vec<RExpr> },
+    Macro     { span: Span<PPos>, name: Span<PPos>, kind: MacroKind, args: Vec<RExpr> },
 
-    suffix_rule.set(ident);
+    Deref     { span: Span<PPos>, star: Span<PPos>, expr: Box<RExpr> },
+    Borrow    { span: Span<PPos>, and: Span<PPos>, expr: Box<RExpr> },
+    BorrowMut { span: Span<PPos>, and: Span<PPos>, mutable: Span<PPos>, expr: Box<RExpr> },
+    Negate    { span: Span<PPos>, neg: Span<PPos>, expr: Box<RExpr> },
+    Not       { span: Span<PPos>, not: Span<PPos>, expr: Box<RExpr> },
 
-    suffix_no_e_rule.set(SpanOf((Not(Funnel(['e', 'E'])), suffix)));
+    AssignOp  { span: Span<PPos>, left: Box<RExpr>, op: AssignOp, op_span: Span<PPos>, right: Box<RExpr> },
+    BinOp     { span: Span<PPos>, left: Box<RExpr>, op: BinOp, op_span: Span<PPos>, right: Box<RExpr> },
+    /// `start..end`, or `start..=end` when `inclusive`.
+    Range     { span: Span<PPos>, start: Box<RExpr>, dots: Span<PPos>, inclusive: bool, end: Box<RExpr> },
 
-    // --- whitespace ---
+    Break     { span: Span<PPos>, label: Option<Span<PPos>>, expr: Option<Box<RExpr>> },
+    Continue  { span: Span<PPos>, label: Option<Span<PPos>> },
+    /// `let pattern = expr`, the condition of an `if let`.
+    Let       { span: Span<PPos>, pattern: Box<RPattern>, expr: Box<RExpr> },
+}
 
-    // a rule that just consumes whitespace space
-    w_rule.set(ZeroOrMore(Funnel2(
-        MapV(SpanOf(OneOf2(..=32u32, 127u32)), |s| Whitespace::Blank(s)),
-        MapV(comment, |c| Whitespace::Comment(c)),
-    )));
+impl RExpr {
+    pub fn span(&self) -> Span<PPos> {
+        use RExpr::*;
+        match self {
+            Lit(l) => l.span(),
+            Var(v) => v.clone(),
+            Path(p, _) => p.clone(),
+            Group { span, .. } => span.clone(),
+            Block(b) => b.span.clone(),
+            If(f) => f.span().clone(),
+            Loop(l) => l.span().clone(),
+            Match(m) => m.span.clone(),
+            Tuple { span, .. } => span.clone(),
+            Array { span, .. } => span.clone(),
+            ArrayRepeat { span, .. } => span.clone(),
+            Index { span, .. } => span.clone(),
+            Field { span, .. } => span.clone(),
+            Struct { span, .. } => span.clone(),
+            Call { span, .. } => span.clone(),
+            MethodCall { span, .. } => span.clone(),
+            Macro { span, .. } => span.clone(),
+            Deref { span, .. } => span.clone(),
+            Borrow { span, .. } => span.clone(),
+            BorrowMut { span, .. } => span.clone(),
+            Negate { span, .. } => span.clone(),
+            Not { span, .. } => span.clone(),
+            AssignOp { span, .. } => span.clone(),
+            BinOp { span, .. } => span.clone(),
+            Range { span, .. } => span.clone(),
+            Break { span, .. } => span.clone(),
+            Continue { span, .. } => span.clone(),
+            Let { span, .. } => span.clone(),
+        }
+    }
+}
 
-    // a rule to parse an ascii letter (lower case or upper case)
-    alpha_rule.set(SpanOf(OneOf2(97..=122, 65..=90)));
-    // a rule to parse an ascii numeric (0,1,2,etc.)
-    numeric_rule.set(SpanOf(48..=57));
-    // a rule to parse an identifier
-    ident_rule.set(SpanOf(
-        (
-            OneOf2(alpha.clone(), '_'),
-            ZeroOrMore(OneOf3(alpha.clone(), numeric.clone(), '_'))
-        )
-    ));
 
-    return_type_rule.set(Map(
-        Leader("->", (w, OneOf2(ty, '!')), |_, arrow_span, _| panic(arrow_span, "return_type", "missing return type")),
-        |res| {
-            res.map_value(|(_arrow, (_, any_of_two))| {
-                match any_of_two {
-                    AnyOf2::Child1(ty) => RReturnType::Type(ty),
-                    AnyOf2::Child2(_) => RReturnType::Never,
+/// 
+/// A macro invocation the engine knows the meaning of.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MacroKind {
+    /// `assert!(cond, ...)`
+    Assert,
+    /// `debug_assert!(cond, ...)`, which checks like `assert!` in the debug
+    /// builds that tests run in.
+    DebugAssert,
+    /// `panic!(...)`
+    Panic,
+    /// `unreachable!(...)`
+    Unreachable,
+}
+
+impl RExpr {
+    /// 
+    /// Infers the integer type of this expression from the typed variables in
+    /// `sigma`, suffixed literals and `as` casts, or `IntTy::BOOL` for a
+    /// condition. Returns `None` when the type is decided by the surrounding
+    /// context (e.g. an unsuffixed literal) or when the expression is neither.
+    /// 
+    pub fn int_ty<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar]) -> Option<IntTy> {
+        use RExpr::*;
+        match self {
+            Lit(RLit::Integer(i)) => i.suffix_ty(store),
+            Lit(RLit::Bool(_)) => Some(IntTy::BOOL),
+            Var(v) => {
+                let name = v.into_string(store);
+                sigma.iter().find(|var| var.name == name).map(|var| var.ty)
+            },
+            Group { expr, .. } => expr.int_ty(store, sigma),
+            Index { .. } => {
+                let symbol = self.call_symbol(store);
+                match self.indexed_cells(store, sigma).first() {
+                    Some(cell) => Some(cell.ty),
+                    None => sigma.iter().find(|var| var.name == symbol).map(|var| var.ty),
                 }
-            })
+            },
+            Field { .. } => {
+                let symbol = self.field_symbol(store, sigma);
+                sigma.iter().find(|var| var.name == symbol).map(|var| var.ty)
+            },
+            Call { .. } | MethodCall { .. } => {
+                let symbol = self.call_symbol(store);
+                sigma.iter().find(|var| var.name == symbol).map(|var| var.ty)
+            },
+            Loop(l) => {
+                let symbol = l.value_symbol(store);
+                sigma.iter().find(|var| var.name == symbol).map(|var| var.ty)
+            },
+            Match(m) => {
+                let symbol = m.value_symbol(store);
+                sigma.iter().find(|var| var.name == symbol).map(|var| var.ty)
+            },
+            If(stmt) => stmt.value(store, sigma).map(|(_, ty)| ty),
+            Block(block) => match block.statements.last() {
+                Some(RStatement::Expr { expr, semi: None }) => expr.int_ty(store, sigma),
+                Some(RStatement::If { stmt }) => stmt.value(store, sigma).map(|(_, ty)| ty),
+                _ => None,
+            },
+            Negate { expr, .. } => expr.int_ty(store, sigma),
+            Not { expr, .. } => expr.int_ty(store, sigma),
+            BinOp { op, left, right, .. } => {
+                match op {
+                    self::BinOp::As => IntTy::from_type_str(&right.span().into_string(store)),
+                    self::BinOp::LSh | self::BinOp::RSh => left.int_ty(store, sigma),
+                    _ if op.is_arithmetic() || op.is_bitwise() => left.int_ty(store, sigma).or_else(|| right.int_ty(store, sigma)),
+                    _ => Some(IntTy::BOOL),
+                }
+            },
+            _ => None,
         }
-    ));
-
-    // --- Literals ---
-
-    literal_expression_rule.set(
-        Funnel9(
-            MapV(char_literal,            |c| RLit::Char(c)),
-            MapV(string_literal,          |s| RLit::String(s)),
-            MapV(raw_string_literal,      |r| RLit::RawString(r)),
-            MapV(byte_literal,            |b| RLit::Byte(b)),
-            MapV(byte_string_literal,     |b| RLit::ByteString(b)),
-            MapV(raw_byte_string_literal, |r| RLit::RawByteString(r)),
-            MapV(integer_literal,         |i| RLit::Integer(i)),
-            MapV(float_literal,           |f| RLit::Float(f)),
-            MapV(bool_literal,            |b| RLit::Bool(b)),
-        )
-    );
+    }
 
-    // - Char Literal -
+    /// 
+    /// Returns this expression as a symbolic term where every integer is a
+    /// bit-vector. `sigma` gives the types of variables and `ty` is the type
+    /// the surrounding context expects (used for unsuffixed literals). What
+    /// cannot be evaluated symbolically becomes a variable named after it.
+    /// 
+    pub fn to_term<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar], ty: IntTy) -> SymTerm {
+        match self {
+            RExpr::Lit(RLit::Integer(i)) => i.to_term(store, ty),
+            RExpr::Lit(RLit::Bool(b)) => SymTerm::Bool(b.into_lisp(store) == "true"),
+            RExpr::Lit(l) => SymTerm::Var(l.into_lisp(store)),
+            RExpr::Var(v) => {
+                let name = v.into_lisp(store);
+                match sigma.iter().find(|var| var.name == name) {
+                    Some(var) => SymTerm::Var(name).cast(var.ty, ty),
+                    None => SymTerm::Var(name),
+                }
+            },
+            RExpr::Path(span, _) => SymTerm::Var(span.into_lisp(store)),
+            // a block or if without a value has the value `()`, which
+            // nothing compares, so any constant stands in for it
+            RExpr::Block(_) | RExpr::If(_) => match self.value(store, sigma, ty) {
+                Some((value, from)) => value.cast(from, ty),
+                None if ty.is_bool() => SymTerm::Bool(false),
+                None => SymTerm::Int(0, ty),
+            },
+            RExpr::Loop(l) => {
+                let symbol = l.value_symbol(store);
+                match sigma.iter().find(|var| var.name == symbol) {
+                    Some(var) => SymTerm::Var(symbol).cast(var.ty, ty),
+                    None => SymTerm::var("Loop"),
+                }
+            },
+            RExpr::Match(m) => {
+                let symbol = m.value_symbol(store);
+                match sigma.iter().find(|var| var.name == symbol) {
+                    Some(var) => SymTerm::Var(symbol).cast(var.ty, ty),
+                    None => SymTerm::var("Match"),
+                }
+            },
+            RExpr::Tuple { .. } => SymTerm::var("Tuple"),
+            RExpr::Struct { .. } => SymTerm::var("Struct"),
+            RExpr::Array { .. } | RExpr::ArrayRepeat { .. } => SymTerm::var("Array"),
+            RExpr::Index { .. } => {
+                let cells = self.indexed_cells(store, sigma);
+                let cell = |cell: &SymVar| SymTerm::Var(cell.name.clone()).cast(cell.ty, ty);
+                let Some((last, rest)) = cells.split_last() else {
+                    let symbol = self.call_symbol(store);
+                    return match sigma.iter().find(|var| var.name == symbol) {
+                        Some(var) => SymTerm::Var(symbol).cast(var.ty, ty),
+                        None => SymTerm::Var(symbol),
+                    };
+                };
+                match self.index_term(store, sigma) {
+                    Some((SymTerm::Int(at, _), _)) if (0..cells.len() as i128).contains(&at) => cell(cells[at as usize]),
+                    Some((at, at_ty)) => rest.iter().enumerate().rev().fold(cell(last), |els, (i, item)| {
+                        SymTerm::ite(SymTerm::eq(at.clone(), SymTerm::Int(i as i128, at_ty)), cell(item), els)
+                    }),
+                    None => cell(last),
+                }
+            },
+            RExpr::Field { .. } => {
+                let symbol = self.field_symbol(store, sigma);
+                match sigma.iter().find(|var| var.name == symbol) {
+                    Some(var) => SymTerm::Var(symbol).cast(var.ty, ty),
+                    None => SymTerm::Var(symbol),
+                }
+            },
+            RExpr::Break { .. } => SymTerm::var("Break"),
+            RExpr::Continue { .. } => SymTerm::var("Continue"),
+            RExpr::Range { .. } => SymTerm::var("Range"),
+            RExpr::Macro { .. } => SymTerm::var("Macro"),
+            RExpr::Group { expr, .. } => expr.to_term(store, sigma, ty),
+            RExpr::Call { ident, args, .. } => {
+                let symbol = self.call_symbol(store);
+                match sigma.iter().find(|var| var.name == symbol) {
+                    Some(var) => SymTerm::Var(symbol).cast(var.ty, ty),
+                    None => SymTerm::Var(format!("({} {})", ident.into_lisp(store), args.into_lisp(store))),
+                }
+            },
+            RExpr::MethodCall { .. } => {
+                let symbol = self.call_symbol(store);
+                match sigma.iter().find(|var| var.name == symbol) {
+                    Some(var) => SymTerm::Var(symbol).cast(var.ty, ty),
+                    None => SymTerm::Var(symbol),
+                }
+            },
+            RExpr::Let { .. } => SymTerm::var("Let"),
+            RExpr::Deref { expr, .. } => SymTerm::Var(format!("*{}", expr.into_lisp(store))),
+            RExpr::Borrow { expr, .. } | RExpr::BorrowMut { expr, .. } => {
+                SymTerm::Var(format!("&{}", expr.into_lisp(store)))
+            },
+            RExpr::Negate { expr, .. } => SymTerm::Neg(Box::new(expr.to_term(store, sigma, ty))),
+            RExpr::Not { expr, .. } => {
+                match expr.int_ty(store, sigma).filter(|ty| !ty.is_bool()) {
+                    Some(ty) => SymTerm::BvNot(Box::new(expr.to_term(store, sigma, ty))),
+                    None => SymTerm::not(expr.to_term(store, sigma, ty)),
+                }
+            },
+            RExpr::AssignOp { left, right, .. } => {
+                SymTerm::Var(format!("(setq {} {})", left.into_lisp(store), right.into_lisp(store)))
+            },
+            RExpr::BinOp { left, op, right, .. } => {
+                if let BinOp::As = op {
+                    let from = left.int_ty(store, sigma).unwrap_or(ty);
+                    let to = IntTy::from_type_str(&right.span().into_string(store)).unwrap_or(ty);
+                    if from.is_bool() {
+                        return SymTerm::ite(left.to_term(store, sigma, from), SymTerm::Int(1, to), SymTerm::Int(0, to));
+                    }
+                    return left.to_term(store, sigma, from).cast(from, to);
+                }
 
-    char_literal_rule.set(MapV(
-        Spanned((
-            '\'',
-            SpanOf(OneOf4(
-                (Not(Funnel(['\'', '\\', '\n', '\r', '\t'])), AnyV()),
-                quote_escape,
-                ascii_escape,
-                unicode_escape
-            )),
-            '\'',
-            Maybe(suffix)
-        )),
-        |(span, (_, value, _, suffix))| {
-            RCharLit { span, value, suffix }
+                let (op_ty, amount_ty) = self.operand_tys(store, sigma, ty);
+                let left = left.to_term(store, sigma, op_ty);
+                let right = match op {
+                    BinOp::LSh | BinOp::RSh => right.to_term(store, sigma, amount_ty).cast(amount_ty, op_ty),
+                    _ => right.to_term(store, sigma, op_ty),
+                };
+                // `&`, `|` and `^` on bools are the logical operators
+                match op {
+                    BinOp::BitAnd if op_ty.is_bool() => SymTerm::and(left, right),
+                    BinOp::BitOr if op_ty.is_bool() => SymTerm::bin(BinOp::Or, false, left, right),
+                    BinOp::BitXOr if op_ty.is_bool() => SymTerm::xor(left, right),
+                    _ => SymTerm::bin(op.clone(), op_ty.signed, left, right),
+                }
+            },
         }
-    ));
-
-    quote_escape_rule.set(SpanOf(Funnel(["\\'", "\\\""])));
+    }
 
-    ascii_escape_rule.set(
-        SpanOf(OneOf6(
-            ("\\x", oct_digit, hex_digit),
-            "\\n",
-            "\\r",
-            "\\t",
-            "\\\\",
-            "\\0"
-        ))
-    );
+    /// Returns this expression as an SMT-LIB term, as `to_term` does.
+    pub fn to_bv_lisp<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar], ty: IntTy) -> String {
+        self.to_term(store, sigma, ty).to_lisp()
+    }
+}
 
-    let _d = (hex_digit, ZeroOrMore('_'));
-    let _d = &_d;
-    unicode_escape_rule.set(
-        ("\\u{",
-            _d, Maybe(_d), Maybe(_d), Maybe(_d), Maybe(_d), Maybe(_d),
-        '}')
-    );
+impl RExpr {
+    /// 
+    /// Cuts this expression off at the end of line `line` by dropping every
+    /// binary operator (along with its right operand) that starts on a later
+    /// line.
+    /// 
+    pub fn trim_to_line(self, line: usize) -> RExpr {
+        match self {
+            RExpr::BinOp { left, op_span, .. } | RExpr::AssignOp { left, op_span, .. } if op_span.start.line > line => {
+                left.trim_to_line(line)
+            },
+            RExpr::BinOp { span, left, op, op_span, right } => {
+                let right = right.trim_to_line(line);
+                RExpr::BinOp { span: Span::new(span.start, right.span().end), left, op, op_span, right: Box::new(right) }
+            },
+            RExpr::AssignOp { span, left, op, op_span, right } => {
+                let right = right.trim_to_line(line);
+                RExpr::AssignOp { span: Span::new(span.start, right.span().end), left, op, op_span, right: Box::new(right) }
+            },
+            expr => expr,
+        }
+    }
 
-    // - STRING LITERAL -
+    /// 
+    /// Returns the types of the left and right operands of a `BinOp` given the
+    /// type `ty` of its context. Both operands share the type of whichever side
+    /// is typed, except for the amount of a shift, which keeps its own type.
+    /// 
+    pub fn operand_tys<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar], ty: IntTy) -> (IntTy, IntTy) {
+        match self {
+            RExpr::BinOp { left, op: BinOp::As, .. } => {
+                let from = left.int_ty(store, sigma).unwrap_or(ty);
+                (from, from)
+            },
+            RExpr::BinOp { left, op: BinOp::LSh | BinOp::RSh, right, .. } => {
+                let op_ty = left.int_ty(store, sigma).unwrap_or(ty);
+                (op_ty, right.int_ty(store, sigma).unwrap_or(op_ty))
+            },
+            // the operands of a comparison are not of the type it is, as
+            // that is `bool`, so unsuffixed literals fall back to `i32`
+            RExpr::BinOp { left, op, right, .. } if !op.is_arithmetic() && !op.is_bitwise() => {
+                let op_ty = left.int_ty(store, sigma).or_else(|| right.int_ty(store, sigma)).unwrap_or(IntTy::I32);
+                (op_ty, op_ty)
+            },
+            RExpr::BinOp { left, right, .. } => {
+                let op_ty = left.int_ty(store, sigma).or_else(|| right.int_ty(store, sigma)).unwrap_or(ty);
+                (op_ty, op_ty)
+            },
+            _ => (ty, ty),
+        }
+    }
 
-    string_literal_rule.set(MapV(Spanned((
-            '"',
-            SpanOf(ZeroOrMore(OneOf5(
-                (Not(OneOf2('"', '\\')), AnyV()),
-                quote_escape,
-                ascii_escape,
-                unicode_escape,
-                string_continue
-            ))),
-            '"',
-            Maybe(suffix)
-        )),
-        |(span, (_, text, _, suffix))| {
-            match suffix {
-                Some(suffix) => {
-                    RStrLit {
-                        span,
-                        text,
-                        suffix: Some(suffix)
-                    }
-                },
-                None => {
-                    RStrLit {
-                        span,
-                        text,
-                        suffix: None
-                    }
+    /// 
+    /// Returns the conditions under which evaluating this operator panics,
+    /// not counting panics in its operands. Only `BinOp`s and `Negate` on
+    /// integers, indexes into arrays, and the panicking macros, can panic.
+    /// 
+    pub fn panic_conditions<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, sigma: &[SymVar], ty: IntTy) -> Vec<(PanicKind, SymTerm)> {
+        let mut conds = Vec::new();
+        match self {
+            // `-128i8` is a literal of its own, not a negated `128i8`
+            RExpr::Negate { expr, .. } if matches!(**expr, RExpr::Lit(_)) => {},
+            RExpr::Negate { expr, .. } => {
+                let ty = expr.int_ty(store, sigma).unwrap_or(ty);
+                if ty.signed {
+                    let a = expr.to_term(store, sigma, ty);
+                    conds.push((PanicKind::NegOverflow, SymTerm::eq(a, SymTerm::Int(ty.min(), ty))));
                 }
-            }
+            },
+            RExpr::BinOp { left, op, right, .. } => {
+                let (op_ty, amount_ty) = self.operand_tys(store, sigma, ty);
+                let a = left.to_term(store, sigma, op_ty);
+                let b = right.to_term(store, sigma, amount_ty);
+                match op {
+                    BinOp::Add | BinOp::Sub | BinOp::Mul => {
+                        conds.push((PanicKind::Overflow(op.clone()), SymTerm::overflow(op.clone(), op_ty, a, b)));
+                    },
+                    BinOp::Div | BinOp::Mod => {
+                        let kind = if let BinOp::Div = op { PanicKind::DivByZero } else { PanicKind::RemByZero };
+                        conds.push((kind, SymTerm::eq(b.clone(), SymTerm::Int(0, op_ty))));
+                        if op_ty.signed {
+                            conds.push((
+                                PanicKind::Overflow(op.clone()),
+                                SymTerm::and(SymTerm::eq(a, SymTerm::Int(op_ty.min(), op_ty)), SymTerm::eq(b, SymTerm::Int(-1, op_ty)))
+                            ));
+                        }
+                    },
+                    BinOp::LSh | BinOp::RSh => {
+                        // negative amounts are huge when read as unsigned
+                        let bits = SymTerm::Int(op_ty.bits as i128, amount_ty);
+                        conds.push((PanicKind::Overflow(op.clone()), SymTerm::bin(BinOp::MoreThanEq, false, b, bits)));
+                    },
+                    _ => {},
+                }
+            },
+            RExpr::Macro { kind: MacroKind::Assert | MacroKind::DebugAssert, args, .. } => {
+                if let Some(cond) = args.first() {
+                    conds.push((self.macro_panic_kind(store), SymTerm::not(cond.to_term(store, sigma, IntTy::I32))));
+                }
+            },
+            RExpr::Macro { kind: MacroKind::Panic | MacroKind::Unreachable, .. } => {
+                conds.push((self.macro_panic_kind(store), SymTerm::Bool(true)));
+            },
+            RExpr::MethodCall { expr, method, args, .. } if matches!(method.into_string(store).as_str(), "unwrap" | "expect") => {
+                let tag = expr.place(store).and_then(|path| tag_of(sigma, &path));
+                if let Some((tag, (_, ok), enum_name)) = tag.and_then(|(tag, enum_name)| Some((tag, ok_variant(enum_name)?, enum_name))) {
+                    let message = match args.first() {
+                        Some(RExpr::Lit(RLit::String(lit))) => lit.text.into_string(store),
+                        _ if enum_name == "Option" => "called `Option::unwrap()` on a `None` value".to_string(),
+                        _ => "called `Result::unwrap()` on an `Err` value".to_string(),
+                    };
+                    let unwrapped = SymTerm::eq(SymTerm::Var(tag.name.clone()), SymTerm::Int(ok as i128, IntTy::TAG));
+                    conds.push((PanicKind::Unwrap(message), SymTerm::not(unwrapped)));
+                }
+            },
+            RExpr::Index { .. } => {
+                let len = self.indexed_cells(store, sigma).len();
+                if let (true, Some((at, at_ty))) = (len > 0, self.index_term(store, sigma)) {
+                    let kind = PanicKind::IndexOutOfBounds { len, index: None };
+                    conds.push((kind, SymTerm::bin(BinOp::MoreThanEq, false, at, SymTerm::Int(len as i128, at_ty))));
+                }
+            },
+            _ => {},
         }
-    ));
-
-    string_continue_rule.set("\\\n");
+        conds
+    }
 
-    // - RAW STRING LITERALS -
+    /// 
+    /// The panic a panicking macro invocation causes, with as much of the
+    /// message it panics with as is known without formatting its arguments.
+    /// 
+    fn macro_panic_kind<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> PanicKind {
+        let RExpr::Macro { kind, args, .. } = self else {
+            return PanicKind::Explicit(String::new());
+        };
+
+        // the message is the format string up to its first argument
+        let message = |fmt: Option<&RExpr>| match fmt {
+            Some(RExpr::Lit(RLit::String(lit))) => {
+                let text = lit.text.into_string(store);
+                Some(text.split('{').next().unwrap_or("").to_string())
+            },
+            _ => None,
+        };
 
-    raw_string_literal_rule.set(MapV(
-        Spanned(('r', raw_string_content, Maybe(suffix))),
-        |(span, (_, text, suffix))| {
-            match suffix {
-                Some(suffix) => { RRawStrLit { span, text, suffix: Some(suffix) } },
-                None => { RRawStrLit { span, text, suffix: None } },
-            }
+        match kind {
+            MacroKind::Assert | MacroKind::DebugAssert => {
+                let cond = args.first().map(|c| c.span().into_string(store)).unwrap_or_default();
+                PanicKind::AssertFailed(message(args.get(1)).unwrap_or_else(|| format!("assertion failed: {}", cond)))
+            },
+            MacroKind::Panic => PanicKind::Explicit(message(args.first()).unwrap_or_else(|| "explicit panic".to_string())),
+            MacroKind::Unreachable => {
+                let prefix = "internal error: entered unreachable code";
+                PanicKind::Unreachable(match message(args.first()) {
+                    Some(message) => format!("{}: {}", prefix, message),
+                    None => prefix.to_string(),
+                })
+            },
         }
-    ));
+    }
+}
 
-    raw_string_content_rule.set(MapV(
-        OneOf2(
-            ('"', SpanOf(ZeroOrMore((Not(isolated_cr), Not('"'), AnyV()))), '"'),
-            ('#', raw_string_content, '#')
-        ),
-        |two| {
-            use AnyOf2::*;
-            match two {
-                Child1((_, text, _)) => text,
-                Child2((_, text, _)) => text 
-            }
-        }
-    ));
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RExpr {
+    fn into_lisp(&self, store: &Store) -> String {
+        self.to_bv_lisp(store, &[], IntTy::I32)
+    }
+}
 
-    // - BYTE LITERAL -
 
-    byte_literal_rule.set(MapV(
-        Spanned(('b', '0', SpanOf(OneOf2(ascii_for_char, byte_escape)), '\'', Maybe(suffix))),
-        |(span, (_, _, value, _, suffix))| { RByteLit { span, value, suffix } }
-    ));
 
-    ascii_for_char_rule.set((Not(Funnel(['\\', '\n', '\r', '\t'])), 0x00..=0x7F));
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BinOp {
+    // Type Cast
+    As,
 
-    byte_escape_rule.set(
-        OneOf8(
-            ("\\x", hex_digit, hex_digit),
-            "\\n",
-            "\\r",
-            "\\t",
-            "\\\\",
-            "\\0",
-            "\\'",
-            "\\\"",
-        )
-    );
+    // Logical
+    EqEq,
+    NotEq,
+    LessThan,
+    MoreThan,
+    LessThanEq,
+    MoreThanEq,
+    And,
+    Or,
 
-    // - BYTE STRING LITERALS -
+    // Bits
+    LSh,
+    RSh,
+    BitAnd,
+    BitOr,
+    BitXOr,
 
-    byte_string_literal_rule.set(MapV(
-        Spanned(("b\"", SpanOf(ZeroOrMore((ascii_for_string, byte_escape, string_continue))), '"', Maybe(suffix))),
-        |(span, (_, value, _, suffix))| { RByteStrLit { span, value, suffix } }
-    ));
+    // Arithmetic
+    Add,
+    Sub,
+    Div,
+    Mod,
+    Mul,
+}
 
-    ascii_for_string_rule.set(
-        (Not(OneOf3('"', '\\', isolated_cr)), 0x00..=0x7F)
-    );
+impl BinOp {
+    /// Whether this is an arithmetic operator (`+ - * / %`).
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(self, BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod)
+    }
 
-    // - RAW BYTE STRING LITERAL -
+    /// Whether this is a bitwise operator (`& | ^`).
+    pub fn is_bitwise(&self) -> bool {
+        matches!(self, BinOp::BitAnd | BinOp::BitOr | BinOp::BitXOr)
+    }
 
-    raw_byte_string_literal_rule.set(MapV(
-        Spanned(("br", raw_byte_string_content, Maybe(suffix))),
-        |(span, (_, text, suffix))| {
-            RRawByteStrLit { span, text, suffix }
+    /// Returns the SMT-LIB bit-vector function for this operator on operands
+    /// of the given signedness.
+    pub fn bv_op(&self, signed: bool) -> &'static str {
+        match (self, signed) {
+            (BinOp::As, _) => "as",
+            (BinOp::EqEq, _) => "=",
+            (BinOp::NotEq, _) => "distinct",
+            (BinOp::LessThan, true) => "bvslt",
+            (BinOp::LessThan, false) => "bvult",
+            (BinOp::MoreThan, true) => "bvsgt",
+            (BinOp::MoreThan, false) => "bvugt",
+            (BinOp::LessThanEq, true) => "bvsle",
+            (BinOp::LessThanEq, false) => "bvule",
+            (BinOp::MoreThanEq, true) => "bvsge",
+            (BinOp::MoreThanEq, false) => "bvuge",
+            (BinOp::And, _) => "and",
+            (BinOp::Or, _) => "or",
+            (BinOp::LSh, _) => "bvshl",
+            (BinOp::RSh, true) => "bvashr",
+            (BinOp::RSh, false) => "bvlshr",
+            (BinOp::BitAnd, _) => "bvand",
+            (BinOp::BitOr, _) => "bvor",
+            (BinOp::BitXOr, _) => "bvxor",
+            (BinOp::Add, _) => "bvadd",
+            (BinOp::Sub, _) => "bvsub",
+            (BinOp::Div, true) => "bvsdiv",
+            (BinOp::Div, false) => "bvudiv",
+            (BinOp::Mod, true) => "bvsrem",
+            (BinOp::Mod, false) => "bvurem",
+            (BinOp::Mul, _) => "bvmul",
         }
-    ));
+    }
+}
 
-    raw_byte_string_content_rule.set(MapV(
-        OneOf2(
-            ('"', SpanOf(ZeroOrMore((Not('"'), ascii))), '"'),
-            ('#', raw_byte_string_content, '#')
-        ),
-        |any_of_two| {
-            use AnyOf2::*;
-            match any_of_two {
-                Child1((_, span, _)) => span,
-                Child2((_, span, _)) => span,
-            }
+impl AsRef<str> for BinOp {
+    fn as_ref(&self) -> &str {
+        match self.clone() {
+            BinOp::As => "as",
+            BinOp::EqEq => "=",
+            BinOp::NotEq => "/=",
+            BinOp::LessThan => "<",
+            BinOp::MoreThan => ">",
+            BinOp::LessThanEq => "<=",
+            BinOp::MoreThanEq => ">=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+            BinOp::LSh => "<<",
+            BinOp::RSh => ">>",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::BitXOr => "^",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::Mul => "*",
         }
-    ));
-
-    ascii_rule.set(0x00..=0x7F);
-
-    // - INTEGER LITERALS -
+    }
+}
 
-    integer_literal_rule.set(MapV(Spanned((
-            OneOf4(
-                dec_literal,
-                bin_literal,
-                oct_literal,
-                hex_literal
-            ),
-            Maybe(suffix_no_e)
-        )),
-        |(span, (any_of_four, suffix))| {
-            use AnyOf4::*;
-            match any_of_four {
-                Child1(dec) => RIntLit::DecLit { span, lit: dec, suffix },
-                Child2(bin) => RIntLit::BinLit { span, lit: bin, suffix },
-                Child3(oct) => RIntLit::OctLit { span, lit: oct, suffix },
-                Child4(hex) => RIntLit::HexLit { span, lit: hex, suffix },
-            }
-        }
-    ));
+impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> IntoLisp<Store, Pos> for BinOp {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.as_ref())
+    }
+}
 
-    dec_literal_rule.set(MapV(
-        SpanOf((
-            dec_digit,
-            ZeroOrMore(OneOf2(
-                dec_digit,
-                '_'
-            ))
-        )),
-        |span| { RDecLit { span: span.clone(), value: span } }
-    ));
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AssignOp {
+    // Assignment
+    Assign,
 
-    bin_literal_rule.set(MapV(
-        Spanned(("0b", SpanOf((ZeroOrMore(OneOf2(bin_digit, '_')), bin_digit, ZeroOrMore(OneOf2(bin_digit, '_')))))),
-        |(span, (_, value))| { RBinLit { span, value } }
-    ));
+    // Compound Assignment
+    AAdd,
+    ASub,
+    AMul,
+    ADiv,
+    AMod,
+    AAnd,
+    AOr,
+    AXOr,
+    ALSh,
+    ARSh,
+}
 
-    oct_literal_rule.set(MapV(
-        Spanned(("0o", SpanOf((ZeroOrMore(OneOf2(oct_digit, '_')), oct_digit, ZeroOrMore(OneOf2(oct_digit, '_')))))),
-        |(span, (_, value))| { ROctLit { span, value } }
-    ));
+impl AssignOp {
+    /// The operator a compound assignment applies, or `None` for `=`.
+    pub fn bin_op(&self) -> Option<BinOp> {
+        match self {
+            AssignOp::Assign => None,
+            AssignOp::AAdd => Some(BinOp::Add),
+            AssignOp::ASub => Some(BinOp::Sub),
+            AssignOp::AMul => Some(BinOp::Mul),
+            AssignOp::ADiv => Some(BinOp::Div),
+            AssignOp::AMod => Some(BinOp::Mod),
+            AssignOp::AAnd => Some(BinOp::BitAnd),
+            AssignOp::AOr => Some(BinOp::BitOr),
+            AssignOp::AXOr => Some(BinOp::BitXOr),
+            AssignOp::ALSh => Some(BinOp::LSh),
+            AssignOp::ARSh => Some(BinOp::RSh),
+        }
+    }
+}
 
-    hex_literal_rule.set(MapV(
-        Spanned(("0x", SpanOf((ZeroOrMore(OneOf2(hex_digit, '_')), hex_digit, ZeroOrMore(OneOf2(hex_digit, '_')))))),
-        |(span, (_, value))| { RHexLit { span, value } }
-    ));
+impl AsRef<str> for AssignOp {
+    fn as_ref(&self) -> &str {
+        match self.clone() {
+            AssignOp::Assign => "=",
+            AssignOp::AAdd => "+=",
+            AssignOp::ASub => "-=",
+            AssignOp::AMul => "*=",
+            AssignOp::ADiv => "/=",
+            AssignOp::AMod => "%=",
+            AssignOp::AAnd => "&=",
+            AssignOp::AOr => "|=",
+            AssignOp::AXOr => "^=",
+            AssignOp::ALSh => "<<=",
+            AssignOp::ARSh => ">>=",
+        }
+    }
+}
 
-    bin_digit_rule.set(Funnel(['0', '1']));
+impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> IntoLisp<Store, Pos> for AssignOp {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.as_ref())
+    }
+}
 
-    oct_digit_rule.set(48..=55);
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RStatement {
+    Comment {
+        comment: RComment,
+    },
+    /// An expression with an optional semicolon after it (semicolon can only be omitted if it is at the end of a group).
+    Expr {
+        expr: RExpr,
+        semi: Option<Span<PPos>>
+    },
+    /// A return statement.
+    Return {
+        return_span: Span<PPos>,
+        expr: RExpr,
+        semi: Option<Span<PPos>>
+    },
+    /// A semicolon
+    SColon {
+        semi: Span<PPos>
+    },
+    /// An if statement or chain of if statements.
+    If {
+        stmt: RIf,
+    },
+    /// A loop of one form or another.
+    Loop {
+        stmt: RLoop,
+    },
+    /// Assign a variable a value
+    Assign {
+        /// The `let` keyword span.
+        let_: Span<PPos>,
+        /// The span of the mutable keyword (if given)
+        mutable: Option<Span<PPos>>,
+        /// The variable name, or the tuple pattern the value is destructured
+        /// by.
+        pattern: Box<RPattern>,
+        /// The optionally-specified type.
+        ty: Option<RType>,
+        /// The `=` sign.
+        equal_sign: Span<PPos>,
+        /// The expression after the `=`.
+        equal_value: RExpr,
+        /// The optional semicolon after the assignment statement (can only be
+        /// omitted if on last line of group).
+        semicolon: Option<Span<PPos>>,
+    },
+}
 
-    dec_digit_rule.set(48..=57);
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RReturnType {
+    /// Returns the given type.
+    Type(RType),
+    /// Function never returns.
+    Never,
+}
 
-    hex_digit_rule.set(SpanOf(OneOf3(
-        dec_digit,
-        65..=70, // upper case
-        97..=102 // lower case
-    )));
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RFn {
+    pub span: Span<PPos>,
+    pub fn_span: Span<PPos>,
+    pub id: Span<PPos>,
+    pub args: Vec<RParam>,
+    pub ret_type: Option<RReturnType>,
+    pub body: RBlock,
+}
 
-    // - FLOAT LITERALS -
+// Comments
 
-    float_literal_rule.set(MapV(
-        Spanned(OneOf3(
-            SpanOf((dec_literal, '.', Not(OneOf3('.', '_', xid_start)))),
-            (SpanOf((dec_literal, '.', dec_literal)), Maybe(suffix_no_e)),
-            (Spanned((SpanOf((dec_literal, Maybe(('.', dec_literal)))), float_exponent)), Maybe(suffix)),
-        )),
-        |(span, three)| {
-            use AnyOf3::*;
+/// 
+/// A comment in Rust.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RComment {
+    Symex {
+        symex: Span<PPos>,
+        /// The rest of the line after "symex".
+        follow: Span<PPos>,
+        /// The query at the start of `follow`, if it holds one.
+        query: Option<RSymexQuery>,
+    },
+    Line {
+        /// Span including "//"
+        span: Span<PPos>,
+        /// Span of just the comment text.
+        text: Span<PPos>,
+    },
+    Block {
+        /// Span including "/*" and "*/"
+        span: Span<PPos>,
+        /// The comment's text.
+        text: Span<PPos>,
+    },
+    InnerLineDoc {
+        /// Span including "//!".
+        span: Span<PPos>,
+        /// The doc comment's text.
+        text: Span<PPos>,
+    },
+    InnerBlockDoc {
+        /// Span including "/*!" and "*/".
+        span: Span<PPos>,
+        /// The doc comment's text.
+        text: Span<PPos>,
+    },
+    OuterLineDoc {
+        /// Span including "///".
+        span: Span<PPos>,
+        /// The doc comment's text.
+        text: Span<PPos>,
+    },
+    OuterBlockDoc {
+        /// Span including "/**" and "*/".
+        span: Span<PPos>,
+        /// The doc comment's text.
+        text: Span<PPos>,
+    },
+}
+
+
+/// 
+/// What a `//symex` comment asks about the paths that reach it.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RSymexQuery {
+    /// `//symex assert <expr>`: the boolean `expr` holds on every path.
+    Assert {
+        span: Span<PPos>,
+        expr: Box<RExpr>,
+    },
+    /// `//symex reach`: some path reaches the comment.
+    Reach {
+        span: Span<PPos>,
+    },
+}
+
+impl RSymexQuery {
+    pub fn span(&self) -> Span<PPos> {
+        match self {
+            RSymexQuery::Assert { span, .. } => span.clone(),
+            RSymexQuery::Reach { span } => span.clone(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Whitespace {
+    Comment(RComment),
+    Blank(Span<PPos>),
+}
+
+// -- Literals --
+
+///
+/// A literal that cannot have a sign.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RLit {
+    Char(RCharLit),
+    String(RStrLit),
+    RawString(RRawStrLit),
+    Byte(RByteLit),
+    ByteString(RByteStrLit),
+    RawByteString(RRawByteStrLit),
+    Integer(RIntLit),
+    Float(RFloatLit),
+    Bool(RBoolLit),
+}
+
+impl RLit {
+    pub fn span(&self) -> Span<PPos> {
+        use RLit::*;
+        match self {
+            Char(s) => s.span.clone(),
+            String(s) => s.span.clone(),
+            RawString(s) => s.span.clone(),
+            Byte(s) => s.span.clone(),
+            ByteString(s) => s.span.clone(),
+            RawByteString(s) => s.span.clone(),
+            Integer(s) => s.span(),
+            Float(s) => s.span.clone(),
+            Bool(s) => s.span(),
+        }
+    }
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        use RLit::*;
+        match self {
+            Char(c) => c.into_lisp(store),
+            String(s) => s.into_lisp(store),
+            RawString(s) => s.into_lisp(store),
+            Byte(b) => b.into_lisp(store),
+            ByteString(b) => b.into_lisp(store),
+            RawByteString(b) => b.into_lisp(store),
+            Integer(i) => i.into_lisp(store),
+            Float(f) => f.into_lisp(store),
+            Bool(b) => b.into_lisp(store),
+        }
+    }
+}
+
+// --- Crate ---
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RCrate {
+    pub utf8bom: Option<Span<PPos>>,
+    pub shebang: Option<Span<PPos>>,
+    // pub attrs: RInnerAttributes,
+    pub items: Vec<RItem>,
+}
+
+impl RCrate {
+    /// Returns every function in the crate by name.
+    pub fn fn_map<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> HashMap<String, RFn> {
+        let mut fns = HashMap::new();
+        for item in self.items.iter() {
+            if let RItem::Fn { val, .. } = item {
+                fns.insert(val.id.into_string(store), val.clone());
+            }
+        }
+        fns
+    }
+
+    /// Returns every struct in the crate by name.
+    pub fn struct_map<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> HashMap<String, RStruct> {
+        let mut structs = HashMap::new();
+        for item in self.items.iter() {
+            if let RItem::Struct { val, .. } = item {
+                structs.insert(val.ident().into_string(store), val.clone());
+            }
+        }
+        structs
+    }
+
+    /// Returns every enum in the crate by name.
+    pub fn enum_map<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> HashMap<String, REnum> {
+        let mut enums = HashMap::new();
+        for item in self.items.iter() {
+            if let RItem::Enum { val, .. } = item {
+                enums.insert(val.ident.into_string(store), val.clone());
+            }
+        }
+        enums
+    }
+}
+
+/// 
+/// An item in a crate.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RItem {
+//    Mod {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RMod,
+//    },
+//    ExternCrate {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RExternCrate,
+//    },
+//    UseDecl {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RUseDecl,
+//    },
+    Fn {
+        span: Span<PPos>,
+        vis: Option<RVis>,
+        val: RFn,
+    },
+//    TypeAlias {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RTypeAlias,
+//    },
+    Struct {
+        span: Span<PPos>,
+        vis: Option<RVis>,
+        val: RStruct,
+    },
+    Enum {
+        span: Span<PPos>,
+        vis: Option<RVis>,
+        val: REnum,
+    },
+//    Union {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RUnion,
+//    },
+//    Const {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RConstItem,
+//    },
+//    Static {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RStaticItem,
+//    },
+//    Trait {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RTrait,
+//    },
+//    Impl {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RImpl,
+//    },
+//    ExternBlock {
+//        span: Span<PPos>,
+//        vis: Option<RVis>,
+//        val: RExternBlock,
+//    },
+}
+
+/// 
+/// A struct declaration: with fields by name (`struct P { x: i32 }`), no
+/// fields (`struct U;`) or fields by position (`struct W(i32, u8);`).
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RStruct {
+    Struct {
+        span: Span<PPos>,
+        ident: Span<PPos>,
+        fields: RStructFields,
+    },
+    Tuple {
+        span: Span<PPos>,
+        ident: Span<PPos>,
+        fields: RTupleFields,
+    },
+}
+
+impl RStruct {
+    pub fn ident(&self) -> &Span<PPos> {
+        match self {
+            RStruct::Struct { ident, .. } | RStruct::Tuple { ident, .. } => ident,
+        }
+    }
+
+    /// The name (the index, in a tuple struct) and type of every field of
+    /// this struct, in order.
+    pub fn fields<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Vec<(String, &RType)> {
+        match self {
+            RStruct::Struct { fields, .. } => fields.fields.iter().map(|field| (field.ident.into_string(store), &field.ty)).collect(),
+            RStruct::Tuple { fields, .. } => fields.fields.iter().enumerate().map(|(i, field)| (i.to_string(), &field.ty)).collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RStructFields {
+    pub span: Span<PPos>,
+    pub fields: Vec<RStructField>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RStructField {
+    pub span: Span<PPos>,
+    pub vis: Option<RVis>,
+    pub ident: Span<PPos>,
+    pub ty: RType,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RTupleFields {
+    pub span: Span<PPos>,
+    pub fields: Vec<RTupleField>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RTupleField {
+    pub span: Span<PPos>,
+    pub vis: Option<RVis>,
+    pub ty: RType,
+}
+
+/// 
+/// An enum declaration. Each variant is declared like a struct of its own,
+/// with fields by name, fields by position or none.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct REnum {
+    pub span: Span<PPos>,
+    pub ident: Span<PPos>,
+    pub variants: Vec<RStruct>,
+}
+
+/// The variants of an enum by tag, with the name and type (where known) of
+/// each of their fields.
+pub type Variants<'a> = Vec<(String, Vec<(String, Option<&'a RType>)>)>;
+
+/// 
+/// The variants of the enum `name`: those of `Option` and `Result`, whose
+/// fields are of the types `args`, or of an enum in `enums`.
+/// 
+pub fn enum_variants<'a, Store: ParseStore<PPos, char> + ?Sized>(store: &Store, enums: &'a HashMap<String, REnum>, name: &str, args: &'a [RType]) -> Option<Variants<'a>> {
+    match name {
+        "Option" => Some(vec![("None".to_string(), Vec::new()), ("Some".to_string(), vec![("0".to_string(), args.first())])]),
+        "Result" => Some(vec![("Ok".to_string(), vec![("0".to_string(), args.first())]), ("Err".to_string(), vec![("0".to_string(), args.get(1))])]),
+        _ => Some(enums.get(name)?.variants.iter().map(|variant| {
+            (variant.ident().into_string(store), variant.fields(store).into_iter().map(|(field, ty)| (field, Some(ty))).collect())
+        }).collect()),
+    }
+}
+
+/// 
+/// The enum and tag of the variant the path `path` names: a variant of
+/// `Option`, `Result` or an enum in `enums`, after the name of its enum
+/// (`Shape::Circle`) or on its own (`Some`).
+/// 
+pub fn resolve_variant<Store: ParseStore<PPos, char> + ?Sized>(store: &Store, enums: &HashMap<String, REnum>, path: &[String]) -> Option<(String, usize)> {
+    let (variant, rest) = path.split_last()?;
+    let names: Vec<String> = match rest.last() {
+        Some(name) => vec![name.clone()],
+        None => {
+            let mut declared: Vec<String> = enums.keys().cloned().collect();
+            declared.sort();
+            ["Option".to_string(), "Result".to_string()].into_iter().chain(declared).collect()
+        },
+    };
+    names.into_iter().find_map(|name| {
+        let tag = enum_variants(store, enums, &name, &[])?.iter().position(|(v, _)| v == variant)?;
+        Some((name, tag))
+    })
+}
+
+/// The names along the path `path` (`Shape::Circle`).
+pub fn path_names(path: &str) -> Vec<String> {
+    path.split("::").map(|name| name.trim().to_string()).collect()
+}
+
+/// The variant of `Option` or `Result` whose field `unwrap` returns
+/// (`Some` or `Ok`), and its tag.
+fn ok_variant(enum_name: &str) -> Option<(&'static str, usize)> {
+    match enum_name {
+        "Option" => Some(("Some", 1)),
+        "Result" => Some(("Ok", 0)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// The visibility of an item.
+pub enum RVis {
+    VisPub   { span: Span<PPos>, },
+    VisCrate { span: Span<PPos>, },
+    VisSelf  { span: Span<PPos>, },
+    VisSuper { span: Span<PPos>, },
+//    VisPath  {
+//        span: Span<PPos>,
+//        path: RSimplePath,
+//    }
+}
+
+// --- Literals ---
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RBoolLit {
+    True  { span: Span<PPos> },
+    False { span: Span<PPos> }
+}
+
+impl RBoolLit {
+    pub fn span(&self) -> Span<PPos> {
+        use RBoolLit::*;
+        match self {
+            True { span } => span.clone(),
+            False { span } => span.clone(),
+        }
+    }
+}
+
+impl <Store: ParseStore<Pos, char> + ?Sized, Pos: ParsePos> IntoLisp<Store, Pos> for RBoolLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        use RBoolLit::*;
+        match self {
+            True { .. } => format!("true"),
+            False { .. } => format!("false"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RCharLit {
+    pub span: Span<PPos>,
+    pub value: Span<PPos>,
+    pub suffix: Option<Span<PPos>>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RCharLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        self.span.into_lisp(store)
+    }
+}
+
+/// 
+/// A string where escape sequences are allowed.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RStrLit {
+    pub span: Span<PPos>,
+    pub text: Span<PPos>,
+    pub suffix: Option<Span<PPos>>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RStrLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RByteStrLit {
+    pub span: Span<PPos>,
+    pub value: Span<PPos>,
+    pub suffix: Option<Span<PPos>>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RByteStrLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RRawByteStrLit {
+    pub span: Span<PPos>,
+    pub text: Span<PPos>,
+    pub suffix: Option<Span<PPos>>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RRawByteStrLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.text.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RByteLit {
+    pub span: Span<PPos>,
+    pub value: Span<PPos>,
+    pub suffix: Option<Span<PPos>>
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RByteLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.value.into_lisp(store))
+    }
+}
+
+/// 
+/// A string where no escape sequences are allowed i.e. the string is
+/// exactly as it looks.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RRawStrLit {
+    pub span: Span<PPos>,
+    pub text: Span<PPos>,
+    pub suffix: Option<Span<PPos>>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RRawStrLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RFloatLit {
+    pub span: Span<PPos>,
+    pub value: Span<PPos>,
+    pub exp: Option<Span<PPos>>,
+    pub value_exp_span: Span<PPos>,
+    pub suffix: Option<Span<PPos>>
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RFloatLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RSFloatLit {
+    pub span: Span<PPos>,
+    pub neg: bool,
+    pub lit: RFloatLit,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RSFloatLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RDecLit {
+    pub span: Span<PPos>,
+    pub value: Span<PPos>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RDecLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RBinLit {
+    pub span: Span<PPos>,
+    pub value: Span<PPos>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RBinLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ROctLit {
+    pub span: Span<PPos>,
+    pub value: Span<PPos>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for ROctLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RHexLit {
+    pub span: Span<PPos>,
+    pub value: Span<PPos>,
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RHexLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        format!("{}", self.span.into_lisp(store))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RIntLit {
+    DecLit {
+        span: Span<PPos>,
+        lit: RDecLit,
+        suffix: Option<Span<PPos>>,
+    },
+    BinLit {
+        span: Span<PPos>,
+        lit: RBinLit,
+        suffix: Option<Span<PPos>>,
+    },
+    OctLit {
+        span: Span<PPos>,
+        lit: ROctLit,
+        suffix: Option<Span<PPos>>,
+    },
+    HexLit {
+        span: Span<PPos>,
+        lit: RHexLit,
+        suffix: Option<Span<PPos>>,
+    },
+}
+
+impl RIntLit {
+    pub fn span(&self) -> Span<PPos> {
+        use RIntLit::*;
+        match self {
+            DecLit { span, .. } => span.clone(),
+            BinLit { span, .. } => span.clone(),
+            OctLit { span, .. } => span.clone(),
+            HexLit { span, .. } => span.clone(),
+        }
+    }
+
+    /// Returns the value of this literal (ignoring its suffix).
+    pub fn value<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> i128 {
+        use RIntLit::*;
+        let (digits, radix) = match self {
+            DecLit { lit, .. } => (&lit.value, 10),
+            BinLit { lit, .. } => (&lit.value, 2),
+            OctLit { lit, .. } => (&lit.value, 8),
+            HexLit { lit, .. } => (&lit.value, 16),
+        };
+        let digits: String = digits.into_string(store).chars().filter(|c| *c != '_').collect();
+        u128::from_str_radix(&digits, radix).expect("integer literal out of range") as i128
+    }
+
+    /// Returns the integer type given by this literal's suffix (e.g. `5u8`),
+    /// if it has one.
+    pub fn suffix_ty<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store) -> Option<IntTy> {
+        use RIntLit::*;
+        match self {
+            DecLit { suffix, .. } | BinLit { suffix, .. } | OctLit { suffix, .. } | HexLit { suffix, .. } => {
+                suffix.as_ref().and_then(|s| IntTy::from_type_str(&s.into_string(store)))
+            },
+        }
+    }
+
+    /// Returns this literal as a bit-vector of its suffix type, or of `ty`
+    /// if it has no suffix.
+    pub fn to_term<Store: ParseStore<PPos, char> + ?Sized>(&self, store: &Store, ty: IntTy) -> SymTerm {
+        SymTerm::Int(self.value(store), self.suffix_ty(store).unwrap_or(ty))
+    }
+}
+
+impl <Store: ParseStore<PPos, char> + ?Sized> IntoLisp<Store, PPos> for RIntLit {
+    fn into_lisp(&self, store: &Store) -> String {
+        match self {
+            RIntLit::DecLit { lit, .. } => lit.into_lisp(store),
+            RIntLit::BinLit { lit, .. } => lit.into_lisp(store),
+            RIntLit::OctLit { lit, .. } => lit.into_lisp(store),
+            RIntLit::HexLit { lit, .. } => lit.into_lisp(store),
+        }
+    }
+}
+
+/// 
+/// Parses a file and returns the result.
+/// 
+pub fn parse_file(file_text: &str) -> ParseResult<RCrate, String, PPos> {
+    //println!("Parsing: \"{}\"", file_text);
+
+    // create `expr` (it requires a number of recursive child nodes)
+    srule!(w, w_rule);
+    srule!(alpha, alpha_rule);
+    srule!(numeric, numeric_rule);
+    srule!(ident, ident_rule);
+    srule!(param, param_rule);
+    srule!(params, params_rule);
+    srule!(return_type, return_type_rule);
+    srule!(expr, expr_rule);
+    srule!(mul_or_div, mul_or_div_rule);
+    srule!(power, power_rule);
+    srule!(value, value_rule);
+    srule!(index, index_rule);
+    srule!(array_expr, array_expr_rule);
+    srule!(struct_expr, struct_expr_rule);
+    srule!(struct_expr_field, struct_expr_field_rule);
+    srule!(block, block_rule);
+    srule!(type_tuple, type_tuple_rule);
+    srule!(type_array, type_array_rule);
+    srule!(ty, ty_rule);
+    srule!(statement, statement_rule);
+    srule!(func, func_rule);
+    srule!(struct_item, struct_item_rule);
+    srule!(enum_item, enum_item_rule);
+    srule!(variant, variant_rule);
+    srule!(struct_field, struct_field_rule);
+    srule!(tuple_field, tuple_field_rule);
+    srule!(file, file_rule);
+    srule!(item, item_rule);
+    srule!(vis, vis_rule);
+
+    srule!(literal_expression, literal_expression_rule);
+    srule!(char_literal, char_literal_rule);
+    srule!(string_literal, string_literal_rule);
+    srule!(raw_string_literal, raw_string_literal_rule);
+    srule!(byte_literal, byte_literal_rule);
+    srule!(byte_string_literal, byte_string_literal_rule);
+    srule!(raw_byte_string_literal, raw_byte_string_literal_rule);
+    srule!(integer_literal, integer_literal_rule);
+    srule!(float_literal, float_literal_rule);
+    srule!(bool_literal, bool_literal_rule);
+    srule!(dec_literal, dec_literal_rule);
+    srule!(bin_literal, bin_literal_rule);
+    srule!(oct_literal, oct_literal_rule);
+    srule!(hex_literal, hex_literal_rule);
+
+    srule!(quote_escape, quote_escape_rule);
+    srule!(ascii_escape, ascii_escape_rule);
+    srule!(unicode_escape, unicode_escape_rule);
+    srule!(oct_digit, oct_digit_rule);
+    srule!(hex_digit, hex_digit_rule);
+    srule!(string_continue, string_continue_rule);
+    srule!(suffix, suffix_rule);
+    srule!(suffix_no_e, suffix_no_e_rule);
+    srule!(raw_string_content, raw_string_content_rule);
+    srule!(ascii_for_char, ascii_for_char_rule);
+    srule!(byte_escape, byte_escape_rule);
+    srule!(ascii_for_string, ascii_for_string_rule);
+    srule!(raw_byte_string_content, raw_byte_string_content_rule);
+    srule!(ascii, ascii_rule);
+    srule!(dec_digit, dec_digit_rule);
+    srule!(bin_digit, bin_digit_rule);
+    srule!(float_exponent, float_exponent_rule);
+
+    srule!(return_statement, return_statement_rule);
+    srule!(let_statement, let_statement_rule);
+    srule!(expr_semi_statement, expr_semi_statement_rule);
+    srule!(semi_statement, semi_statement_rule);
+
+    srule!(if_statement, if_statement_rule);
+    srule!(loop_statement, loop_statement_rule);
+    srule!(infinite_loop, infinite_loop_rule);
+    srule!(while_loop, while_loop_rule);
+    srule!(for_loop, for_loop_rule);
+    srule!(loop_label, loop_label_rule);
+    srule!(match_expr, match_expr_rule);
+    srule!(match_arm, match_arm_rule);
+    srule!(pattern, pattern_rule);
+    srule!(pattern_no_alt, pattern_no_alt_rule);
+    srule!(pattern_lit, pattern_lit_rule);
+    srule!(pattern_path, pattern_path_rule);
+    srule!(pattern_field, pattern_field_rule);
+    srule!(if_cond, if_cond_rule);
+
+    srule!(range, range_rule);
+    srule!(logic_op, logic_op_rule);
+    srule!(bit_op, bit_op_rule);
+    srule!(add_or_sub, add_or_sub_rule);
+    srule!(shift, shift_rule);
+    srule!(cast, cast_rule);
+    srule!(assign, assign_rule);
+
+    srule!(comment, comment_rule);
+    srule!(symex_query, symex_query_rule);
+    srule!(line_comment, line_comment_rule);
+    srule!(block_comment, block_comment_rule);
+    srule!(inner_line_doc, inner_line_doc_rule);
+    srule!(inner_block_doc, inner_block_doc_rule);
+    srule!(outer_line_doc, outer_line_doc_rule);
+    srule!(outer_block_doc, outer_block_doc_rule);
+    srule!(block_comment_or_doc, block_comment_or_doc_rule);
+
+    // define function to produce "panic" uniform messages of parse
+    let panic = &|pos: Span<PPos>, fn_name: &str, message: &str| -> String {
+        format!("{}: ({}) {}", pos, fn_name, message)
+    };
+
+    // IDENTIFIERS
+
+    let isolated_cr = &SpanOf(('\r', Not('\n')));
+
+    // unicode groups
+    let xid_start = &MapPValue(|span, ch| {
+        if UnicodeXID::is_xid_start(ch) {
+            Okay(span.clone(), span.end)
+        } else {
+            Error(panic(span, "xid_start", "expected character in the [:XID_Start:] unicode group"))
+        }
+    });
+
+//    let xid_continue = MapPValue(|span, ch| {
+//        if UnicodeXID::is_xid_continue(ch) {
+//            Okay(span.clone(), span.end)
+//        } else {
+//            Error(panic(span, "xid_continue", "expected character in the [:XID_Continue:] unicode group"))
+//        }
+//    });
+//    let xid_continue = &xid_continue;
+
+    // --- COMMENTS ---
+
+    comment_rule.set(Funnel8(
+        MapV(
+            ("//", OneOf2(Not(Funnel(['/', '!', '\n'])), "//"), "symex", Spanned((Maybe((ZeroOrMore(OneOf2(' ', '\t')), symex_query)), ZeroOrMore((Not('\n'), AnyV()))))),
+            |(_, _, symex, (follow, (query, _)))| RComment::Symex { symex, follow, query: query.map(|(_, q)| q) }
+        ),
+        line_comment,
+        block_comment,
+        inner_line_doc,
+        inner_block_doc,
+        outer_line_doc,
+        outer_block_doc,
+        block_comment_or_doc,
+    ));
+
+    // the query of a `//symex` comment
+    symex_query_rule.set(Map(
+        Funnel2(
+            MapV(Spanned(("assert", OneOrMore(OneOf2(' ', '\t')), expr)), |(span, (_, _, expr))| RSymexQuery::Assert { span, expr: Box::new(expr) }),
+            MapV("reach", |span| RSymexQuery::Reach { span }),
+        ),
+        // the expression rule carries on into the code after the comment when
+        // that starts with an operator, so the query is cut off at the end of
+        // its line and parsing resumes right after it
+        |res| match res {
+            Okay(RSymexQuery::Assert { span, expr }, _) => {
+                let expr = expr.trim_to_line(span.start.line);
+                let span = Span::new(span.start, expr.span().end);
+                if span.start.line != span.end.line {
+                    return Panic(panic(span, "symex_query", "a //symex query must fit on one line"));
+                }
+                let end = span.end;
+                Okay(RSymexQuery::Assert { span, expr: Box::new(expr) }, end)
+            },
+            res => res,
+        }
+    ));
+
+    line_comment_rule.set(MapV(
+        Spanned(OneOf2(
+            ("//", OneOf2(Not(Funnel(['/', '!', '\n'])), "//"), SpanOf(ZeroOrMore((Not('\n'), AnyV())))),
+            "//"
+        )),
+        |(span, any_of_two)| {
+            match any_of_two {
+                AnyOf2::Child1((_, _, text)) => RComment::Line { span, text },
+                AnyOf2::Child2(span)         => RComment::Line { span: span.clone(), text: span },
+            }
+        }
+    ));
+
+    block_comment_rule.set(MapV(
+        Spanned(Surround(
+            "/*",
+            SpanOf((
+                OneOf3(
+                    Not(Funnel(['*', '!'])),
+                    "**",
+                    block_comment_or_doc
+                ),
+                ZeroOrMore(OneOf2(
+                    block_comment_or_doc,
+                    (Not("*/"), AnyV())
+                )),
+            )),
+            "*/",
+            |_, _, e| e,
+            |_, start_span, _, _| panic(start_span, "block_comment", "expected end to this block comment"),
+        )),
+        |(span, (_, text, _))| {
+            RComment::Block { span, text }
+        }
+    ));
+
+    inner_line_doc_rule.set(MapV(
+        Spanned((
+            "//!",
+            SpanOf(ZeroOrMore((Not(OneOf2('\n', isolated_cr)), AnyV())))
+        )),
+        |(span, (_, text))| {
+            RComment::InnerLineDoc { span, text }
+        }
+    ));
+
+    inner_block_doc_rule.set(MapV(
+        Spanned(Surround(
+            "/*!",
+            SpanOf(ZeroOrMore(OneOf2(block_comment_or_doc, (Not(OneOf2("*/", isolated_cr)), AnyV())))),
+            "*/",
+            |_, _, e| e,
+            |_, start_span, _, _| panic(start_span, "inner_block_doc", "expected end to this block doc comment"),
+        )),
+        |(span, (_, text, _))| {
+            RComment::InnerBlockDoc { span, text }
+        }
+    ));
+
+    outer_line_doc_rule.set(MapV(
+        Spanned((
+            "///",
+            SpanOf(Maybe((Not('/'), ZeroOrMore(Not(OneOf2('\n', isolated_cr))))))
+        )),
+        |(span, (_, text))| {
+            RComment::OuterLineDoc { span, text }
+        }
+    ));
+
+    outer_block_doc_rule.set(MapV(
+        Spanned(Surround(
+            "/**",
+            SpanOf((OneOf2(Not('*'), block_comment_or_doc), ZeroOrMore(OneOf2(block_comment_or_doc, (Not(OneOf2("*/", isolated_cr)), AnyV()))))),
+            "*/",
+            |_, _, e| e,
+            |_, start_span, _, _| panic(start_span, "inner_block_doc", "expected end to this block doc comment"),
+        )),
+        |(span, (_, text, _))| {
+            RComment::OuterBlockDoc { span, text }
+        }
+    ));
+
+    block_comment_or_doc_rule.set(
+        MapV(
+            OneOf3(block_comment, outer_block_doc, inner_block_doc),
+            |any_of_three| {
+                match any_of_three {
+                    AnyOf3::Child1(v) => v,
+                    AnyOf3::Child2(v) => v,
+                    AnyOf3::Child3(v) => v,
+                }
+            }
+        )
+    );
+
+    // - SUFFIX -
+
+    suffix_rule.set(ident);
+
+    suffix_no_e_rule.set(SpanOf((Not(Funnel(['e', 'E'])), suffix)));
+
+    // --- whitespace ---
+
+    // a rule that just consumes whitespace space
+    w_rule.set(ZeroOrMore(Funnel2(
+        MapV(SpanOf(OneOf2(..=32u32, 127u32)), |s| Whitespace::Blank(s)),
+        MapV(comment, |c| Whitespace::Comment(c)),
+    )));
+
+    // a rule to parse an ascii letter (lower case or upper case)
+    alpha_rule.set(SpanOf(OneOf2(97..=122, 65..=90)));
+    // a rule to parse an ascii numeric (0,1,2,etc.)
+    numeric_rule.set(SpanOf(48..=57));
+    // a rule to parse an identifier
+    ident_rule.set(SpanOf(
+        (
+            OneOf2(alpha.clone(), '_'),
+            ZeroOrMore(OneOf3(alpha.clone(), numeric.clone(), '_'))
+        )
+    ));
+
+    return_type_rule.set(Map(
+        Leader("->", (w, OneOf2(ty, '!')), |_, arrow_span, _| panic(arrow_span, "return_type", "missing return type")),
+        |res| {
+            res.map_value(|(_arrow, (_, any_of_two))| {
+                match any_of_two {
+                    AnyOf2::Child1(ty) => RReturnType::Type(ty),
+                    AnyOf2::Child2(_) => RReturnType::Never,
+                }
+            })
+        }
+    ));
+
+    // --- Literals ---
+
+    literal_expression_rule.set(
+        Funnel9(
+            MapV(char_literal,            |c| RLit::Char(c)),
+            MapV(string_literal,          |s| RLit::String(s)),
+            MapV(raw_string_literal,      |r| RLit::RawString(r)),
+            MapV(byte_literal,            |b| RLit::Byte(b)),
+            MapV(byte_string_literal,     |b| RLit::ByteString(b)),
+            MapV(raw_byte_string_literal, |r| RLit::RawByteString(r)),
+            MapV(integer_literal,         |i| RLit::Integer(i)),
+            MapV(float_literal,           |f| RLit::Float(f)),
+            MapV(bool_literal,            |b| RLit::Bool(b)),
+        )
+    );
+
+    // - Char Literal -
+
+    char_literal_rule.set(MapV(
+        Spanned((
+            '\'',
+            SpanOf(OneOf4(
+                (Not(Funnel(['\'', '\\', '\n', '\r', '\t'])), AnyV()),
+                quote_escape,
+                ascii_escape,
+                unicode_escape
+            )),
+            '\'',
+            Maybe(suffix)
+        )),
+        |(span, (_, value, _, suffix))| {
+            RCharLit { span, value, suffix }
+        }
+    ));
+
+    quote_escape_rule.set(SpanOf(Funnel(["\\'", "\\\""])));
+
+    ascii_escape_rule.set(
+        SpanOf(OneOf6(
+            ("\\x", oct_digit, hex_digit),
+            "\\n",
+            "\\r",
+            "\\t",
+            "\\\\",
+            "\\0"
+        ))
+    );
+
+    let _d = (hex_digit, ZeroOrMore('_'));
+    let _d = &_d;
+    unicode_escape_rule.set(
+        ("\\u{",
+            _d, Maybe(_d), Maybe(_d), Maybe(_d), Maybe(_d), Maybe(_d),
+        '}')
+    );
+
+    // - STRING LITERAL -
+
+    string_literal_rule.set(MapV(Spanned((
+            '"',
+            SpanOf(ZeroOrMore(OneOf5(
+                (Not(OneOf2('"', '\\')), AnyV()),
+                quote_escape,
+                ascii_escape,
+                unicode_escape,
+                string_continue
+            ))),
+            '"',
+            Maybe(suffix)
+        )),
+        |(span, (_, text, _, suffix))| {
+            match suffix {
+                Some(suffix) => {
+                    RStrLit {
+                        span,
+                        text,
+                        suffix: Some(suffix)
+                    }
+                },
+                None => {
+                    RStrLit {
+                        span,
+                        text,
+                        suffix: None
+                    }
+                }
+            }
+        }
+    ));
+
+    string_continue_rule.set("\\\n");
+
+    // - RAW STRING LITERALS -
+
+    raw_string_literal_rule.set(MapV(
+        Spanned(('r', raw_string_content, Maybe(suffix))),
+        |(span, (_, text, suffix))| {
+            match suffix {
+                Some(suffix) => { RRawStrLit { span, text, suffix: Some(suffix) } },
+                None => { RRawStrLit { span, text, suffix: None } },
+            }
+        }
+    ));
+
+    raw_string_content_rule.set(MapV(
+        OneOf2(
+            ('"', SpanOf(ZeroOrMore((Not(isolated_cr), Not('"'), AnyV()))), '"'),
+            ('#', raw_string_content, '#')
+        ),
+        |two| {
+            use AnyOf2::*;
+            match two {
+                Child1((_, text, _)) => text,
+                Child2((_, text, _)) => text 
+            }
+        }
+    ));
+
+    // - BYTE LITERAL -
+
+    byte_literal_rule.set(MapV(
+        Spanned(('b', '0', SpanOf(OneOf2(ascii_for_char, byte_escape)), '\'', Maybe(suffix))),
+        |(span, (_, _, value, _, suffix))| { RByteLit { span, value, suffix } }
+    ));
+
+    ascii_for_char_rule.set((Not(Funnel(['\\', '\n', '\r', '\t'])), 0x00..=0x7F));
+
+    byte_escape_rule.set(
+        OneOf8(
+            ("\\x", hex_digit, hex_digit),
+            "\\n",
+            "\\r",
+            "\\t",
+            "\\\\",
+            "\\0",
+            "\\'",
+            "\\\"",
+        )
+    );
+
+    // - BYTE STRING LITERALS -
+
+    byte_string_literal_rule.set(MapV(
+        Spanned(("b\"", SpanOf(ZeroOrMore((ascii_for_string, byte_escape, string_continue))), '"', Maybe(suffix))),
+        |(span, (_, value, _, suffix))| { RByteStrLit { span, value, suffix } }
+    ));
+
+    ascii_for_string_rule.set(
+        (Not(OneOf3('"', '\\', isolated_cr)), 0x00..=0x7F)
+    );
+
+    // - RAW BYTE STRING LITERAL -
+
+    raw_byte_string_literal_rule.set(MapV(
+        Spanned(("br", raw_byte_string_content, Maybe(suffix))),
+        |(span, (_, text, suffix))| {
+            RRawByteStrLit { span, text, suffix }
+        }
+    ));
+
+    raw_byte_string_content_rule.set(MapV(
+        OneOf2(
+            ('"', SpanOf(ZeroOrMore((Not('"'), ascii))), '"'),
+            ('#', raw_byte_string_content, '#')
+        ),
+        |any_of_two| {
+            use AnyOf2::*;
+            match any_of_two {
+                Child1((_, span, _)) => span,
+                Child2((_, span, _)) => span,
+            }
+        }
+    ));
+
+    ascii_rule.set(0x00..=0x7F);
+
+    // - INTEGER LITERALS -
+
+    integer_literal_rule.set(MapV(Spanned((
+            OneOf4(
+                dec_literal,
+                bin_literal,
+                oct_literal,
+                hex_literal
+            ),
+            Maybe(suffix_no_e)
+        )),
+        |(span, (any_of_four, suffix))| {
+            use AnyOf4::*;
+            match any_of_four {
+                Child1(dec) => RIntLit::DecLit { span, lit: dec, suffix },
+                Child2(bin) => RIntLit::BinLit { span, lit: bin, suffix },
+                Child3(oct) => RIntLit::OctLit { span, lit: oct, suffix },
+                Child4(hex) => RIntLit::HexLit { span, lit: hex, suffix },
+            }
+        }
+    ));
+
+    dec_literal_rule.set(MapV(
+        SpanOf((
+            dec_digit,
+            ZeroOrMore(OneOf2(
+                dec_digit,
+                '_'
+            ))
+        )),
+        |span| { RDecLit { span: span.clone(), value: span } }
+    ));
+
+    bin_literal_rule.set(MapV(
+        Spanned(("0b", SpanOf((ZeroOrMore(OneOf2(bin_digit, '_')), bin_digit, ZeroOrMore(OneOf2(bin_digit, '_')))))),
+        |(span, (_, value))| { RBinLit { span, value } }
+    ));
+
+    oct_literal_rule.set(MapV(
+        Spanned(("0o", SpanOf((ZeroOrMore(OneOf2(oct_digit, '_')), oct_digit, ZeroOrMore(OneOf2(oct_digit, '_')))))),
+        |(span, (_, value))| { ROctLit { span, value } }
+    ));
+
+    hex_literal_rule.set(MapV(
+        Spanned(("0x", SpanOf((ZeroOrMore(OneOf2(hex_digit, '_')), hex_digit, ZeroOrMore(OneOf2(hex_digit, '_')))))),
+        |(span, (_, value))| { RHexLit { span, value } }
+    ));
+
+    bin_digit_rule.set(Funnel(['0', '1']));
+
+    oct_digit_rule.set(48..=55);
+
+    dec_digit_rule.set(48..=57);
+
+    hex_digit_rule.set(SpanOf(OneOf3(
+        dec_digit,
+        65..=70, // upper case
+        97..=102 // lower case
+    )));
+
+    // - FLOAT LITERALS -
+
+    float_literal_rule.set(MapV(
+        Spanned(OneOf3(
+            SpanOf((dec_literal, '.', Not(OneOf3('.', '_', xid_start)))),
+            (SpanOf((dec_literal, '.', dec_literal)), Maybe(suffix_no_e)),
+            (Spanned((SpanOf((dec_literal, Maybe(('.', dec_literal)))), float_exponent)), Maybe(suffix)),
+        )),
+        |(span, three)| {
+            use AnyOf3::*;
             match three {
                 Child1(value) => {
                     RFloatLit { span: span.clone(), value, exp: None, value_exp_span: span, suffix: None }
@@ -1748,532 +4769,2093 @@ pub fn parse_file(file_text: &str) -> ParseResult<RCrate, String, PPos> {
         }
     ));
 
-    float_exponent_rule.set(SpanOf((
-        Funnel(['e', 'E']),
-        Maybe(Funnel(['+', '-'])),
-        ZeroOrMore(OneOf2(dec_digit, '_')),
-        dec_digit,
-        ZeroOrMore(OneOf2(dec_digit, '_'))
-    )));
-
-    // - BOOLEAN LITERAL -
+    float_exponent_rule.set(SpanOf((
+        Funnel(['e', 'E']),
+        Maybe(Funnel(['+', '-'])),
+        ZeroOrMore(OneOf2(dec_digit, '_')),
+        dec_digit,
+        ZeroOrMore(OneOf2(dec_digit, '_'))
+    )));
+
+    // - BOOLEAN LITERAL -
+
+    bool_literal_rule.set(Funnel2(
+        MapV("true" , |span| RBoolLit::True  { span }),
+        MapV("false", |span| RBoolLit::False { span }),
+    ));
+
+    // --- Expressions ---
+
+    use BinOp::*;
+    use AssignOp::*;
+    {
+        use RExpr::{BinOp, AssignOp};
+
+        expr_rule.set(assign);
+
+        assign_rule.set(
+            RLJoin(range, (w, OneOf11('=', "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>="), w),
+                |left: RExpr, (_, op, _), right| {
+                    let span = Span::new(left.span().start, right.span().end);
+                    use AnyOf11::*;
+                    match op {
+                        Child1(op_span) => AssignOp { span, left: Box::new(left), op: Assign, op_span, right: Box::new(right) },
+                        Child2(op_span) => AssignOp { span, left: Box::new(left), op: AAdd, op_span, right: Box::new(right) },
+                        Child3(op_span) => AssignOp { span, left: Box::new(left), op: ASub, op_span, right: Box::new(right) },
+                        Child4(op_span) => AssignOp { span, left: Box::new(left), op: AMul, op_span, right: Box::new(right) },
+                        Child5(op_span) => AssignOp { span, left: Box::new(left), op: ADiv, op_span, right: Box::new(right) },
+                        Child6(op_span) => AssignOp { span, left: Box::new(left), op: AMod, op_span, right: Box::new(right) },
+                        Child7(op_span) => AssignOp { span, left: Box::new(left), op: AAnd, op_span, right: Box::new(right) },
+                        Child8(op_span) => AssignOp { span, left: Box::new(left), op: AOr, op_span, right: Box::new(right) },
+                        Child9(op_span) => AssignOp { span, left: Box::new(left), op: AXOr, op_span, right: Box::new(right) },
+                        Child10(op_span) => AssignOp { span, left: Box::new(left), op: ALSh, op_span, right: Box::new(right) },
+                        Child11(op_span) => AssignOp { span, left: Box::new(left), op: ARSh, op_span, right: Box::new(right) },
+                    }
+                }
+            )
+        );
+
+        range_rule.set(MapV(
+            Spanned((logic_op, Maybe((w, OneOf2("..=", ".."), w, logic_op)))),
+            |(span, (start, range))| match range {
+                None => start,
+                Some((_, AnyOf2::Child1(dots), _, end)) => RExpr::Range { span, start: Box::new(start), dots, inclusive: true, end: Box::new(end) },
+                Some((_, AnyOf2::Child2(dots), _, end)) => RExpr::Range { span, start: Box::new(start), dots, inclusive: false, end: Box::new(end) },
+            }
+        ));
+
+        logic_op_rule.set(
+            LRJoin(bit_op, (w, OneOf8("&&", "||", "==", "!=", "<=", ">=", "<", ">"), w),
+                |left: RExpr, (_, op, _), right| {
+                    let span = Span::new(left.span().start, right.span().end);
+                    use AnyOf8::*;
+                    match op {
+                        Child1(op_span) => BinOp { span, left: Box::new(left), op: And       , op_span, right: Box::new(right) },
+                        Child2(op_span) => BinOp { span, left: Box::new(left), op: Or        , op_span, right: Box::new(right) },
+                        Child3(op_span) => BinOp { span, left: Box::new(left), op: EqEq      , op_span, right: Box::new(right) },
+                        Child4(op_span) => BinOp { span, left: Box::new(left), op: NotEq     , op_span, right: Box::new(right) },
+                        Child5(op_span) => BinOp { span, left: Box::new(left), op: LessThanEq, op_span, right: Box::new(right) },
+                        Child6(op_span) => BinOp { span, left: Box::new(left), op: MoreThanEq, op_span, right: Box::new(right) },
+                        Child7(op_span) => BinOp { span, left: Box::new(left), op: LessThan  , op_span, right: Box::new(right) },
+                        Child8(op_span) => BinOp { span, left: Box::new(left), op: MoreThan  , op_span, right: Box::new(right) },
+                    }
+                }
+            )
+        );
+
+        // `&` and `|`, but not `&&`, `||`, `&=` or `|=`
+        bit_op_rule.set(
+            LRJoin(shift, (w, OneOf2(SpanOf(('&', Not(OneOf2('&', '=')))), SpanOf(('|', Not(OneOf2('|', '='))))), w),
+            |left: RExpr, (_, op, _), right| {
+                let span = Span::new(left.span().start, right.span().end);
+                match op {
+                    AnyOf2::Child1(op_span) => BinOp { span, left: Box::new(left), op: BitAnd, op_span, right: Box::new(right) },
+                    AnyOf2::Child2(op_span) => BinOp { span, left: Box::new(left), op: BitOr, op_span, right: Box::new(right) },
+                }
+            })
+        );
+
+        shift_rule.set(
+            LRJoin(add_or_sub, (w, OneOf2("<<", ">>"), w),
+            |left: RExpr, (_, op, _), right| {
+                let span = Span::new(left.span().start, right.span().end);
+                match op {
+                    AnyOf2::Child1(op_span) => BinOp { span, left: Box::new(left), op: LSh, op_span, right: Box::new(right) },
+                    AnyOf2::Child2(op_span) => BinOp { span, left: Box::new(left), op: RSh, op_span, right: Box::new(right) },
+                }
+            })
+        );
+
+        add_or_sub_rule.set(
+            LRJoin(mul_or_div, (w, OneOf2('+', '-'), w),
+            |left: RExpr, (_, op, _), right| {
+                let span = Span::new(left.span().start, right.span().end);
+                match op {
+                    AnyOf2::Child1(op_span) => BinOp { span, left: Box::new(left), op: Add, op_span, right: Box::new(right) },
+                    AnyOf2::Child2(op_span) => BinOp { span, left: Box::new(left), op: Sub, op_span, right: Box::new(right) },
+                }
+            })
+        );
+
+        mul_or_div_rule.set(
+            LRJoin(power, (w, OneOf3('*', '/', '%'), w),
+            |left: RExpr, (_, op, _), right| {
+                let span = Span::new(left.span().start, right.span().end);
+                match op {
+                    AnyOf3::Child1(op_span) => BinOp { span, left: Box::new(left), op: Mul, op_span, right: Box::new(right) },
+                    AnyOf3::Child2(op_span) => BinOp { span, left: Box::new(left), op: Div, op_span, right: Box::new(right) },
+                    AnyOf3::Child3(op_span) => BinOp { span, left: Box::new(left), op: Mod, op_span, right: Box::new(right) },
+                }
+            })
+        );
+
+        power_rule.set(
+            LRJoin(cast, (w, '^', w),
+            |left: RExpr, (_, op_span, _), right| {
+                let span = Span::new(left.span().start, right.span().end);
+                BinOp { span, left: Box::new(left), op: BitXOr, op_span, right: Box::new(right) }
+            })
+        );
+
+        cast_rule.set(
+            LRJoin(index, (w, SpanOf(("as", Not(OneOf3(alpha, numeric, '_')))), w),
+            |left: RExpr, (_, op_span, _), right| {
+                let span = Span::new(left.span().start, right.span().end);
+                BinOp { span, left: Box::new(left), op: As, op_span, right: Box::new(right) }
+            })
+        );
+
+        // `a[i][j]`, `t.0.1` or `p.x`, indexing the value before it
+        index_rule.set(
+            MapV(
+                (value, ZeroOrMore(OneOf3(
+                    ('[', w, expr, w, ']'),
+                    ('.', ident, w, '(', w, Maybe((Join(expr, (w, ',', w)), Maybe((w, ',')))), w, ')'),
+                    ('.', SpanOf(OneOf2(OneOrMore(numeric), ident))),
+                ))),
+                |(base, indexes): (RExpr, _)| indexes.into_iter().fold(base, |expr, index| match index {
+                    AnyOf3::Child1((_, _, index, _, close)) => {
+                        RExpr::Index { span: Span::new(expr.span().start, close.end), expr: Box::new(expr), index: Box::new(index) }
+                    },
+                    AnyOf3::Child2((_, method, _, _, _, args, _, close)) => {
+                        let args = args.map(|(args, _)| args).unwrap_or_default();
+                        RExpr::MethodCall { span: Span::new(expr.span().start, close.end), expr: Box::new(expr), method, args }
+                    },
+                    AnyOf3::Child3((_, field)) => {
+                        RExpr::Field { span: Span::new(expr.span().start, field.end), expr: Box::new(expr), field }
+                    },
+                })
+            )
+        );
+
+        array_expr_rule.set(
+            Funnel2(
+                MapV(
+                    Spanned(('[', w, expr, w, ';', w, expr, w, ']')),
+                    |(span, (_, _, item, _, _, _, len, _, _))| RExpr::ArrayRepeat { span, item: Box::new(item), len: Box::new(len) }
+                ),
+                MapV(
+                    Spanned(('[', w, Maybe((Join(expr, (w, ',', w)), Maybe((w, ',')))), w, ']')),
+                    |(span, (_, _, items, _, _))| RExpr::Array { span, items: items.map(|(items, _)| items).unwrap_or_default() }
+                ),
+            )
+        );
+
+        value_rule.set(
+            Funnel16(
+                MapV(Spanned(('!', w, expr)), |(span, (not, _, expr))| RExpr::Not { span, not, expr: Box::new(expr) }),
+                MapV(Spanned(('*', w, expr)), |(span, (star, _, expr))| RExpr::Deref { span, star, expr: Box::new(expr) }),
+                MapV(Spanned(('&', w, "mut", w, expr)), |(span, (and, _, mutable, _, expr))| RExpr::BorrowMut { span, and, mutable, expr: Box::new(expr) }),
+                MapV(Spanned(('&', w, expr)), |(span, (and, _, expr))| RExpr::Borrow { span, and, expr: Box::new(expr) }),
+                MapV(Spanned(('-', w, expr)), |(span, (neg, _, expr))| RExpr::Negate { span, neg, expr: Box::new(expr) }),
+                MapV(block, |group| RExpr::Block(group)),
+                MapV(
+                    Spanned(('(', w, Join(expr, (w, ',', w)), Maybe((w, ',')), w, ')')),
+                    |(span, (_, _, mut items, comma, _, _))| match (items.len(), comma) {
+                        (1, None) => RExpr::Group { span, expr: Box::new(items.remove(0)) },
+                        _ => RExpr::Tuple { span, items },
+                    }
+                ),
+                Funnel2(array_expr, MapV(literal_expression, RExpr::Lit)),
+                MapV(if_statement, |if_| RExpr::If(Box::new(if_))),
+                MapV(loop_statement, |loop_| RExpr::Loop(Box::new(loop_))),
+                MapV(match_expr, |match_| RExpr::Match(Box::new(match_))),
+                MapV(
+                    Spanned((SpanOf(("break", Not(OneOf3(alpha, numeric, '_')))), Maybe((w, loop_label)), Maybe((w, expr)))),
+                    |(span, (_, label, expr))| RExpr::Break { span, label: label.map(|(_, label)| label), expr: expr.map(|(_, expr)| Box::new(expr)) }
+                ),
+                MapV(
+                    Spanned((SpanOf(("continue", Not(OneOf3(alpha, numeric, '_')))), Maybe((w, loop_label)))),
+                    |(span, (_, label))| RExpr::Continue { span, label: label.map(|(_, label)| label) }
+                ),
+                MapV(
+                    Spanned((OneOf4("debug_assert", "assert", "panic", "unreachable"), w, '!', w, '(', w, Maybe((Join(expr, (w, ',', w)), Maybe((w, ',')))), w, ')')),
+                    |(span, (name, _, _, _, _, _, args, _, _))| {
+                        let (name, kind) = match name {
+                            AnyOf4::Child1(name) => (name, MacroKind::DebugAssert),
+                            AnyOf4::Child2(name) => (name, MacroKind::Assert),
+                            AnyOf4::Child3(name) => (name, MacroKind::Panic),
+                            AnyOf4::Child4(name) => (name, MacroKind::Unreachable),
+                        };
+                        RExpr::Macro { span, name, kind, args: args.map(|(args, _)| args).unwrap_or_default() }
+                    }
+                ),
+                MapV(Spanned((SpanOf(pattern_path), '(', w, Join(expr, (w, ',', w)), Maybe((w, ',', w)), w, ')')), |(span, (ident, _, _, args, _, _, _))| RExpr::Call { span, ident, args }),
+                Funnel2(struct_expr, MapV(Spanned(pattern_path), |(span, mut path): (Span<PPos>, Vec<Span<PPos>>)| match path.len() {
+                    1 => RExpr::Var(path.remove(0)),
+                    _ => RExpr::Path(span, path),
+                })),
+            ),
+        );
+
+        // only a capitalised name starts a struct literal, so that the
+        // block after `if x` or `match x` is not taken for one
+        struct_expr_rule.set(
+            MapV(
+                Spanned((
+                    SpanOf((65..=90, ZeroOrMore(OneOf3(alpha, numeric, '_')), ZeroOrMore((w, "::", w, ident)))), w, '{', w,
+                    Maybe((Join(struct_expr_field, (w, ',', w)), Maybe((w, ',')))), w,
+                    Maybe(("..", w, expr, w)),
+                    '}'
+                )),
+                |(span, (path, _, _, _, fields, _, base, _))| RExpr::Struct {
+                    span,
+                    path,
+                    fields: fields.map(|(fields, _)| fields).unwrap_or_default(),
+                    base: base.map(|(_, _, base, _)| Box::new(base)),
+                }
+            )
+        );
+
+        struct_expr_field_rule.set(
+            MapV(
+                (ident, Maybe((w, ':', w, expr))),
+                |(field, value)| match value {
+                    Some((_, _, _, value)) => (field, value),
+                    None => (field.clone(), RExpr::Var(field)),
+                }
+            )
+        );
+    }
+
+    block_rule.set(
+        MapV(
+            Spanned(Surround(
+                    '{',
+                        (w, ZeroOrMore((statement, w)), Maybe((expr, w))),
+                    '}',
+                    |_, _, e| e,
+                    |_, ocbrace_span, _, _| panic(ocbrace_span, "block", "openning curly brace is missing its complementary closing curly brace to end the scope"),
+                )),
+            |(span, (_lcbrace, (w, statements, expr), _rcbrace))| {
+                RBlock {
+                    span,
+                    statements: {
+                        let mut stmts: Vec<RStatement> = Vec::new();
+                        for wh in w {
+                            match wh {
+                                Whitespace::Blank(_) => {},
+                                Whitespace::Comment(comment) => stmts.push(RStatement::Comment { comment })
+                            }
+                        }
+                        for (stmt, whitespace) in statements {
+                            stmts.push(stmt);
+                            for w in whitespace {
+                                match w {
+                                    Whitespace::Blank(_) => {},
+                                    Whitespace::Comment(comment) => stmts.push(RStatement::Comment { comment })
+                                }
+                            }
+                        }
+                        if let Some(expr) = expr.map(|(e, _)| e) {
+                            stmts.push(RStatement::Expr { expr, semi: None });
+                        }
+                        stmts
+                    },
+                }
+            }
+        )
+    );
+
+    type_tuple_rule.set(
+        MapV(
+            Spanned(Surround(
+                '(',
+                    (
+                        w,
+                        Maybe((ty, w, ZeroOrMore((',', w, ty, w))))
+                    ),
+                ')',
+                |_, _, e| e,
+                |_, oparen_span, _, _| panic(oparen_span, "type_tuple", "missing closing parenthesis after this open parenthesis"),
+            )),
+            |(span, (_, (_, maybe_types), _))| {
+                match maybe_types {
+                    Some((t1, _, types)) => {
+                        let mut types: Vec<RType> = types.into_iter().map(|v|v.2).collect();
+                        types.insert(0, t1);
+                        RType::Tuple { span, types }
+                    },
+                    None => RType::Tuple { span, types: Vec::new() },
+                }
+            }
+        )
+    );
+
+    type_array_rule.set(
+        MapV(
+            Spanned(('[', w, ty, w, ';', w, expr, w, ']')),
+            |(span, (_, _, item_type, _, _, _, item_number, _, _))| RType::Array { span, item_type: Box::new(item_type), item_number: Box::new(item_number) }
+        )
+    );
+
+    ty_rule.set(
+        MapV(
+            Spanned(OneOf3(
+                type_tuple,
+                type_array,
+                (
+                    ident,
+                    Maybe((
+                        w,
+                        Surround(
+                            '<',
+                            ( 
+                                w,
+                                ZeroOrMore((ty, w, Maybe((',', w))))
+                            ),
+                            '>',
+                        |_, oarrow_span, _| panic(oarrow_span, "ty", "expected values within this type bounds"),
+                            |_, oarrow_span, _, _| panic(oarrow_span, "ty", "expected closing arrow ('>') after this openning arrow ('<')"),
+                        )
+                    ))
+                )
+            )),
+            |(span, any_of_three)| {
+                match any_of_three {
+                    AnyOf3::Child1(ty) | AnyOf3::Child2(ty) => {
+                        ty
+                    },
+                    AnyOf3::Child3((ident, args)) => {
+                        RType::Template {
+                            span,
+                            name: ident,
+                            args: match args {
+                                Some((_, (_, (_, args), _))) => {
+                                    args.into_iter().map(|v|v.0).collect()
+                                },
+                                None => Vec::new(),
+                            },
+                        }
+                    }
+                }
+            }
+        )
+    );
+
+    // --- STATEMENTS ---
+
+    statement_rule.set(Funnel6(
+            MapV(if_statement,   |stmt| RStatement::If { stmt }),
+            MapV(loop_statement, |stmt| RStatement::Loop { stmt }),
+            return_statement,
+            let_statement,
+            expr_semi_statement,
+            semi_statement,
+        ),
+    );
+
+    return_statement_rule.set(
+        MapV(
+            Leader(
+                "return",
+                (w, expr, Maybe((w, ';'))),
+                |_, return_span, _| panic(return_span, "statement", "expected expression after this \"return\" keyword")
+            ),
+            |(return_span, (_, expr, maybe_semi))| {
+                RStatement::Return {
+                    return_span,
+                    expr,
+                    semi: match maybe_semi { Some(v) => Some(v.1), None => None }
+                }
+            }
+        ),
+    );
+
+    let_statement_rule.set(
+        MapV(
+            Leader(
+                "let",
+                (w, Maybe(("mut", w)), pattern_no_alt, Maybe((w, ':', w, ty)), w, '=', w, expr, Maybe((w, ';'))),
+                |_, let_span, _| panic(let_span, "statement", "expected_variable assignment after this let statement")
+            ),
+            |(let_span, (_, mutable, pattern, maybe_type, _, eq_span, _, expr, maybe_semi))| {
+                RStatement::Assign {
+                    let_: let_span,
+                    mutable: mutable.map(|(s, _)|s),
+                    pattern: Box::new(pattern),
+                    ty: match maybe_type {
+                        Some((_, _colon_span, _, ty)) => Some(ty),
+                        None => None,
+                    },
+                    equal_sign: eq_span,
+                    equal_value: expr,
+                    semicolon: match maybe_semi {
+                        Some((_, span)) => Some(span),
+                        None => None,
+                    },
+                }
+            }
+        ),
+    );
+
+    expr_semi_statement_rule.set(MapV(
+        (expr, Maybe((w, ';'))),
+        |(expr, maybe_semi)| {
+            RStatement::Expr { expr, semi: maybe_semi.map(|(_, s)|s) }
+        }
+    ));
+
+    semi_statement_rule.set(MapV(
+        ";",
+        |semi| RStatement::SColon { semi }
+        ),
+    );
+
+//     Spanned(Leader(
+//            "if", (w, expr, w, block,
+//                Maybe((w, Leader("else", (w, OneOf2(block, if_statement)),
+//                    |_, pos, _| panic(pos, "if_statement", "expected code block after this \"else\"")
+//                )))
+//            )
+
+    if_statement_rule.set(MapV(Spanned((
+            OJoin(
+                MapV(
+                    Leader(SpanOf(("if", Not(OneOf3(alpha, numeric, '_')))), (w, if_cond, w, block), |_, pos, _| panic(pos, "if_statement", "expected expression and body of the if statement after this \"if\" keyword")),
+                    |(_, (_, expr, _, block))| (expr, block) 
+                ),
+                (w, "else", w)
+            ),
+            Maybe(MapV((w, "else", w, block), |(_, _, _, block)| block))
+        )),
+        |(span, (ifs, else_stmt))| {
+            RIf { span, ifs, else_stmt }
+        }
+    ));
+
+    // the condition of an `if`, or the pattern and value of an `if let`
+    if_cond_rule.set(Funnel2(
+        MapV(
+            Spanned((SpanOf(("let", Not(OneOf3(alpha, numeric, '_')))), w, pattern, w, '=', w, expr)),
+            |(span, (_, _, pattern, _, _, _, expr))| RExpr::Let { span, pattern: Box::new(pattern), expr: Box::new(expr) }
+        ),
+        expr,
+    ));
 
-    bool_literal_rule.set(Funnel2(
-        MapV("true" , |span| RBoolLit::True  { span }),
-        MapV("false", |span| RBoolLit::False { span }),
+    loop_statement_rule.set(Funnel3(
+        infinite_loop,
+        while_loop,
+        for_loop,
     ));
 
-    // --- Expressions ---
+    infinite_loop_rule.set(MapV(
+        Spanned((Maybe((loop_label, w, ':', w)), "loop", w, block)),
+        |(span, (label, _, _, block))| RLoop::Infinite { span, label: label.map(|(label, ..)| label), block }
+    ));
 
-    use BinOp::*;
-    use AssignOp::*;
-    {
-        use RExpr::{BinOp, AssignOp};
+    while_loop_rule.set(MapV(
+        Spanned((Maybe((loop_label, w, ':', w)), "while", w, expr, w, block)),
+        |(span, (label, _, _, expr, _, block))| RLoop::While { span, label: label.map(|(label, ..)| label), expr, block }
+    ));
 
-        expr_rule.set(assign);
+    for_loop_rule.set(MapV(
+        Spanned((Maybe((loop_label, w, ':', w)), "for", w, ident, w, "in", w, expr, w, block)),
+        |(span, (label, _, _, var, _, _, _, expr, _, block))| RLoop::For { span, label: label.map(|(label, ..)| label), var, expr, block }
+    ));
 
-        assign_rule.set(
-            RLJoin(logic_op, (w, OneOf11('=', "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>="), w),
-                |left: RExpr, (_, op, _), right| {
-                    let span = Span::new(left.span().start, right.span().end);
-                    use AnyOf11::*;
-                    match op {
-                        Child1(op_span) => AssignOp { span, left: Box::new(left), op: Assign, op_span, right: Box::new(right) },
-                        Child2(op_span) => AssignOp { span, left: Box::new(left), op: AAdd, op_span, right: Box::new(right) },
-                        Child3(op_span) => AssignOp { span, left: Box::new(left), op: ASub, op_span, right: Box::new(right) },
-                        Child4(op_span) => AssignOp { span, left: Box::new(left), op: AMul, op_span, right: Box::new(right) },
-                        Child5(op_span) => AssignOp { span, left: Box::new(left), op: ADiv, op_span, right: Box::new(right) },
-                        Child6(op_span) => AssignOp { span, left: Box::new(left), op: AMod, op_span, right: Box::new(right) },
-                        Child7(op_span) => AssignOp { span, left: Box::new(left), op: AAnd, op_span, right: Box::new(right) },
-                        Child8(op_span) => AssignOp { span, left: Box::new(left), op: AOr, op_span, right: Box::new(right) },
-                        Child9(op_span) => AssignOp { span, left: Box::new(left), op: AXOr, op_span, right: Box::new(right) },
-                        Child10(op_span) => AssignOp { span, left: Box::new(left), op: ALSh, op_span, right: Box::new(right) },
-                        Child11(op_span) => AssignOp { span, left: Box::new(left), op: ARSh, op_span, right: Box::new(right) },
-                    }
-                }
-            )
-        );
+    loop_label_rule.set(SpanOf(('\'', ident)));
 
-        logic_op_rule.set(
-            LRJoin(add_or_sub, (w, OneOf8("&&", "||", "==", "!=", "<=", ">=", "<", ">"), w),
-                |left: RExpr, (_, op, _), right| {
-                    let span = Span::new(left.span().start, right.span().end);
-                    use AnyOf8::*;
-                    match op {
-                        Child1(op_span) => BinOp { span, left: Box::new(left), op: And       , op_span, right: Box::new(right) },
-                        Child2(op_span) => BinOp { span, left: Box::new(left), op: Or        , op_span, right: Box::new(right) },
-                        Child3(op_span) => BinOp { span, left: Box::new(left), op: EqEq      , op_span, right: Box::new(right) },
-                        Child4(op_span) => BinOp { span, left: Box::new(left), op: NotEq     , op_span, right: Box::new(right) },
-                        Child5(op_span) => BinOp { span, left: Box::new(left), op: LessThanEq, op_span, right: Box::new(right) },
-                        Child6(op_span) => BinOp { span, left: Box::new(left), op: MoreThanEq, op_span, right: Box::new(right) },
-                        Child7(op_span) => BinOp { span, left: Box::new(left), op: LessThan  , op_span, right: Box::new(right) },
-                        Child8(op_span) => BinOp { span, left: Box::new(left), op: MoreThan  , op_span, right: Box::new(right) },
-                    }
-                }
-            )
-        );
+    // --- Match ---
 
-        add_or_sub_rule.set(
-            LRJoin(mul_or_div, (w, OneOf2('+', '-'), w),
-            |left: RExpr, (_, op, _), right| {
-                let span = Span::new(left.span().start, right.span().end);
-                match op {
-                    AnyOf2::Child1(op_span) => BinOp { span, left: Box::new(left), op: Add, op_span, right: Box::new(right) },
-                    AnyOf2::Child2(op_span) => BinOp { span, left: Box::new(left), op: Sub, op_span, right: Box::new(right) },
-                }
-            })
-        );
+    match_expr_rule.set(MapV(
+        Spanned((SpanOf(("match", Not(OneOf3(alpha, numeric, '_')))), w, expr, w, '{', w, ZeroOrMore((match_arm, w, Maybe((',', w)))), '}')),
+        |(span, (_, _, expr, _, _, _, arms, _))| RMatch { span, expr, arms: arms.into_iter().map(|(arm, ..)| arm).collect() }
+    ));
 
-        mul_or_div_rule.set(
-            LRJoin(power, (w, OneOf3('*', '/', '%'), w),
-            |left: RExpr, (_, op, _), right| {
-                let span = Span::new(left.span().start, right.span().end);
-                match op {
-                    AnyOf3::Child1(op_span) => BinOp { span, left: Box::new(left), op: Mul, op_span, right: Box::new(right) },
-                    AnyOf3::Child2(op_span) => BinOp { span, left: Box::new(left), op: Div, op_span, right: Box::new(right) },
-                    AnyOf3::Child3(op_span) => BinOp { span, left: Box::new(left), op: Mod, op_span, right: Box::new(right) },
-                }
-            })
-        );
+    match_arm_rule.set(MapV(
+        Spanned((pattern, Maybe((w, SpanOf(("if", Not(OneOf3(alpha, numeric, '_')))), w, expr)), w, "=>", w, expr)),
+        |(span, (pattern, guard, _, _, _, expr))| RMatchArm { span, pattern, guard: guard.map(|(_, _, _, guard)| guard), expr }
+    ));
 
-        power_rule.set(
-            LRJoin(value, (w, '^', w),
-            |left: RExpr, (_, op_span, _), right| {
-                let span = Span::new(left.span().start, right.span().end);
-                BinOp { span, left: Box::new(left), op: BitXOr, op_span, right: Box::new(right) }
-            })
-        );
+    pattern_rule.set(MapV(
+        Spanned(Join(pattern_no_alt, (w, '|', w))),
+        |(span, mut alts)| match alts.len() {
+            1 => alts.remove(0),
+            _ => RPattern::Or { span, alts },
+        }
+    ));
 
-        value_rule.set(
-            Funnel12(
-                MapV(Spanned(('!', w, expr)), |(span, (not, _, expr))| RExpr::Not { span, not, expr: Box::new(expr) }),
-                MapV(Spanned(('*', w, expr)), |(span, (star, _, expr))| RExpr::Deref { span, star, expr: Box::new(expr) }),
-                MapV(Spanned(('&', w, "mut", w, expr)), |(span, (and, _, mutable, _, expr))| RExpr::BorrowMut { span, and, mutable, expr: Box::new(expr) }),
-                MapV(Spanned(('&', w, expr)), |(span, (and, _, expr))| RExpr::Borrow { span, and, expr: Box::new(expr) }),
-                MapV(Spanned(('-', w, expr)), |(span, (neg, _, expr))| RExpr::Negate { span, neg, expr: Box::new(expr) }),
-                MapV(block, |group| RExpr::Block(group)),
-                MapV(Spanned(('(', w, expr, w, ')')), |(span, (_, _, e, _, _))| RExpr::Group { span, expr: Box::new(e) }),
-                MapV(literal_expression, |lit| RExpr::Lit(lit)),
-                MapV(if_statement, |if_| RExpr::If(Box::new(if_))),
-                MapV(loop_statement, |loop_| RExpr::Loop(Box::new(loop_))),
-                MapV(Spanned((ident, '(', w, Join(expr, (w, ',', w)), Maybe((w, ',', w)), w, ')')), |(span, (ident, _, _, args, _, _, _))| RExpr::Call { span, ident, args }),
-                MapV(ident, |span| RExpr::Var(span)),
+    pattern_no_alt_rule.set(Funnel6(
+        MapV(
+            Spanned((pattern_lit, w, OneOf2("..=", ".."), w, pattern_lit)),
+            |(span, (start, _, dots, _, end))| RPattern::Range { span, start: Box::new(start), end: Box::new(end), inclusive: matches!(dots, AnyOf2::Child1(_)) }
+        ),
+        MapV(pattern_lit, |lit| RPattern::Lit { span: lit.span(), lit: Box::new(lit) }),
+        MapV(SpanOf(('_', Not(OneOf3(alpha, numeric, '_')))), |span| RPattern::Wild { span }),
+        MapV(
+            Spanned(('(', w, Join(pattern, (w, ',', w)), Maybe((w, ',')), w, ')')),
+            |(span, (_, _, mut items, comma, _, _))| match (items.len(), comma) {
+                (1, None) => items.remove(0),
+                _ => RPattern::Tuple { span, items },
+            }
+        ),
+        Funnel2(
+            MapV(
+                Spanned((pattern_path, w, '{', w, Maybe((Join(pattern_field, (w, ',', w)), Maybe((w, ',')))), w, Maybe(("..", w)), '}')),
+                |(span, (path, _, _, _, fields, _, rest, _))| RPattern::Struct { span, path, fields: fields.map(|(fields, _)| fields).unwrap_or_default(), rest: rest.is_some() }
             ),
-        );
-    }
+            MapV(
+                Spanned((pattern_path, w, '(', w, Maybe((Join(pattern, (w, ',', w)), Maybe((w, ',')))), w, ')')),
+                |(span, (path, _, _, _, items, _, _))| RPattern::TupleStruct { span, path, items: items.map(|(items, _)| items).unwrap_or_default() }
+            ),
+        ),
+        MapV(
+            Spanned((Maybe((SpanOf(("ref", Not(OneOf3(alpha, numeric, '_')))), w)), Maybe((SpanOf(("mut", Not(OneOf3(alpha, numeric, '_')))), w)), pattern_path, Maybe((w, '@', w, pattern_no_alt)))),
+            |(span, (_, _, mut path, sub))| match (path.len(), sub) {
+                (1, sub) => RPattern::Ident { span, ident: path.remove(0), sub: sub.map(|(_, _, _, sub)| Box::new(sub)) },
+                _ => RPattern::Path { span, path },
+            }
+        ),
+    ));
 
-    block_rule.set(
+    // a field of a struct pattern, binding a variable of its name when no
+    // pattern is given
+    pattern_field_rule.set(MapV(
+        (ident, Maybe((w, ':', w, pattern))),
+        |(field, pattern)| match pattern {
+            Some((_, _, _, pattern)) => (field, pattern),
+            None => (field.clone(), RPattern::Ident { span: field.clone(), ident: field, sub: None }),
+        }
+    ));
+
+    pattern_lit_rule.set(Funnel2(
+        MapV(Spanned(('-', w, literal_expression)), |(span, (neg, _, lit))| RExpr::Negate { span, neg, expr: Box::new(RExpr::Lit(lit)) }),
+        MapV(literal_expression, RExpr::Lit),
+    ));
+
+    pattern_path_rule.set(MapV(
+        (ident, ZeroOrMore((w, "::", w, ident))),
+        |(first, rest)| std::iter::once(first).chain(rest.into_iter().map(|(_, _, _, ident)| ident)).collect::<Vec<_>>()
+    ));
+
+    // --- Function ---
+
+    // the rule to parse a function
+    func_rule.set(
         MapV(
-            Spanned(Surround(
-                    '{',
-                        (w, ZeroOrMore((statement, w)), Maybe((expr, w))),
-                    '}',
-                    |_, _, e| e,
-                    |_, ocbrace_span, _, _| panic(ocbrace_span, "block", "openning curly brace is missing its complementary closing curly brace to end the scope"),
-                )),
-            |(span, (_lcbrace, (w, statements, expr), _rcbrace))| {
-                RBlock {
+            Spanned(Leader(
+                "fn",
+                (w, Leader(ident,
+                        (w,
+                            Surround(
+                                '(', (w, params, w), ')',
+                                |_, oparen_span, _| panic(oparen_span, "func", "expected parameters in this function argument scope"),
+                                |_, oparen_span, _, _| panic(oparen_span, "func", "expected closing parenthesis to match this open parenthesis")
+                            ),
+                            Maybe((w, return_type)),
+                            Req((w, block), |_, p, _| panic(Span::new(p.clone(), p), "func", "function requires a function body"))
+                        ),
+                        |_, ident_span, _| panic(ident_span, "func", "expected function parameters and body after function identifier")
+                    )
+                ),
+                |_, fn_span, _| panic(fn_span, "func", "expected correct function syntax after 'fn' keyword"))),
+            |(span, (fn_span, (_, (id_span, (_, (_oparen, (_, params, _), _cparen), ret_type, (_, body))))))| {
+                RFn {
                     span,
-                    statements: {
-                        let mut stmts: Vec<RStatement> = Vec::new();
-                        for wh in w {
-                            match wh {
-                                Whitespace::Blank(_) => {},
-                                Whitespace::Comment(comment) => stmts.push(RStatement::Comment { comment })
-                            }
-                        }
-                        for (stmt, whitespace) in statements {
-                            stmts.push(stmt);
-                            for w in whitespace {
-                                match w {
-                                    Whitespace::Blank(_) => {},
-                                    Whitespace::Comment(comment) => stmts.push(RStatement::Comment { comment })
-                                }
-                            }
-                        }
-                        if let Some(expr) = expr.map(|(e, _)| e) {
-                            stmts.push(RStatement::Expr { expr, semi: None });
-                        }
-                        stmts
-                    },
+                    fn_span,
+                    id: id_span,
+                    args: params,
+                    ret_type: ret_type.map(|(_, t)| t),
+                    body,
                 }
             }
         )
     );
 
-    type_tuple_rule.set(
+    // a vector of function parameters
+    params_rule.set(MapV(
+        (Join(param, (w, ',', w)), Maybe((w, ','))),
+        |(params, _)| params
+    ));
+
+    // function parameter
+    param_rule.set(MapV(
+        (
+            Maybe(("mut", w)),
+            Leader(
+                pattern_no_alt,
+                (w, Leader(
+                        ':', (w, ty),
+                        |_, colon_span, _| panic(colon_span, "param", "missing type after this colon"),
+                    )
+                ),
+                |_, pattern, _| panic(pattern.span(), "param", "missing arg's type"),
+            )
+        ),
+        |(mutable, (pattern, (_, (_colon, (_, ty)))))| RParam { mutable: mutable.map(|(s, _)|s), pattern, ty, }
+    ));
+
+    vis_rule.set(
+        Funnel4(
+            MapV(SpanOf(("pub", w, '(', w, "crate", w, ')')), |span| RVis::VisCrate { span }),
+            MapV(SpanOf(("pub", w, '(', w, "self" , w, ')')), |span| RVis::VisSelf  { span }),
+            MapV(SpanOf(("pub", w, '(', w, "super", w, ')')), |span| RVis::VisSuper { span }),
+            MapV("pub", |span| RVis::VisPub { span }),
+            //MapV(("pub", w, '(', w, "in", w, simple_path, w, ')'), |(_, _, _, _, _, _, path, _, _)| RVis::VisPath { span, path })
+        ),
+    );
+
+    item_rule.set(
+        Funnel3(
+            MapV(Spanned((Maybe(vis), w, func)), |(span, (vis, _, val))| RItem::Fn { span, vis, val }),
+            MapV(Spanned((Maybe(vis), w, struct_item)), |(span, (vis, _, val))| RItem::Struct { span, vis, val }),
+            MapV(Spanned((Maybe(vis), w, enum_item)), |(span, (vis, _, val))| RItem::Enum { span, vis, val }),
+        )
+    );
+
+    // the rule to parse a struct
+    struct_item_rule.set(
+        MapV(
+            Spanned(Leader(
+                "struct",
+                (w, ident, w, OneOf3(
+                    Spanned(('{', w, Maybe((Join(struct_field, (w, ',', w)), Maybe((w, ',')))), w, '}')),
+                    Spanned(('(', w, Maybe((Join(tuple_field, (w, ',', w)), Maybe((w, ',')))), w, ')', w, ';')),
+                    SpanOf(';'),
+                )),
+                |_, struct_span, _| panic(struct_span, "struct", "expected a name and fields after this \"struct\" keyword"),
+            )),
+            |(span, (_, (_, ident, _, body)))| match body {
+                AnyOf3::Child1((fields_span, (_, _, fields, _, _))) => {
+                    RStruct::Struct { span, ident, fields: RStructFields { span: fields_span, fields: fields.map(|(fields, _)| fields).unwrap_or_default() } }
+                },
+                AnyOf3::Child2((fields_span, (_, _, fields, _, _, _, _))) => {
+                    RStruct::Tuple { span, ident, fields: RTupleFields { span: fields_span, fields: fields.map(|(fields, _)| fields).unwrap_or_default() } }
+                },
+                AnyOf3::Child3(fields_span) => RStruct::Struct { span, ident, fields: RStructFields { span: fields_span, fields: Vec::new() } },
+            }
+        )
+    );
+
+    // the rule to parse an enum
+    enum_item_rule.set(
+        MapV(
+            Spanned(Leader(
+                "enum",
+                (w, ident, w, '{', w, Maybe((Join(variant, (w, ',', w)), Maybe((w, ',')))), w, '}'),
+                |_, enum_span, _| panic(enum_span, "enum", "expected a name and variants after this \"enum\" keyword"),
+            )),
+            |(span, (_, (_, ident, _, _, _, variants, _, _)))| REnum { span, ident, variants: variants.map(|(variants, _)| variants).unwrap_or_default() }
+        )
+    );
+
+    // a variant of an enum, declared like a struct without the keyword
+    variant_rule.set(
         MapV(
-            Spanned(Surround(
-                '(',
-                    (
-                        w,
-                        Maybe((ty, w, ZeroOrMore((',', w, ty, w))))
-                    ),
-                ')',
-                |_, _, e| e,
-                |_, oparen_span, _, _| panic(oparen_span, "type_tuple", "missing closing parenthesis after this open parenthesis"),
-            )),
-            |(span, (_, (_, maybe_types), _))| {
-                match maybe_types {
-                    Some((t1, _, types)) => {
-                        let mut types: Vec<RType> = types.into_iter().map(|v|v.2).collect();
-                        types.insert(0, t1);
-                        RType::Tuple { span, types }
-                    },
-                    None => RType::Tuple { span, types: Vec::new() },
-                }
+            Spanned((ident, Maybe((w, OneOf2(
+                Spanned(('{', w, Maybe((Join(struct_field, (w, ',', w)), Maybe((w, ',')))), w, '}')),
+                Spanned(('(', w, Maybe((Join(tuple_field, (w, ',', w)), Maybe((w, ',')))), w, ')')),
+            ))))),
+            |(span, (ident, body))| match body {
+                Some((_, AnyOf2::Child1((fields_span, (_, _, fields, _, _))))) => {
+                    RStruct::Struct { span, ident, fields: RStructFields { span: fields_span, fields: fields.map(|(fields, _)| fields).unwrap_or_default() } }
+                },
+                Some((_, AnyOf2::Child2((fields_span, (_, _, fields, _, _))))) => {
+                    RStruct::Tuple { span, ident, fields: RTupleFields { span: fields_span, fields: fields.map(|(fields, _)| fields).unwrap_or_default() } }
+                },
+                None => {
+                    let fields_span = Span::new(ident.end, ident.end);
+                    RStruct::Struct { span, ident, fields: RStructFields { span: fields_span, fields: Vec::new() } }
+                },
             }
         )
     );
 
-    ty_rule.set(
+    struct_field_rule.set(
         MapV(
-            Spanned(OneOf2(
-                type_tuple,
-                (
-                    ident,
-                    Maybe((
-                        w,
-                        Surround(
-                            '<',
-                            ( 
-                                w,
-                                ZeroOrMore((ty, w))
-                            ),
-                            '>',
-                        |_, oarrow_span, _| panic(oarrow_span, "ty", "expected values within this type bounds"),
-                            |_, oarrow_span, _, _| panic(oarrow_span, "ty", "expected closing arrow ('>') after this openning arrow ('<')"),
-                        )
-                    ))
-                )
-            )),
-            |(span, any_of_two)| {
-                match any_of_two {
-                    AnyOf2::Child1(ty) => {
-                        ty
-                    },
-                    AnyOf2::Child2((ident, args)) => {
-                        RType::Template {
-                            span,
-                            name: ident,
-                            args: match args {
-                                Some((_, (_, (_, args), _))) => {
-                                    args.into_iter().map(|v|v.0).collect()
-                                },
-                                None => Vec::new(),
-                            },
-                        }
-                    }
-                }
-            }
+            Spanned((Maybe((vis, w)), ident, w, ':', w, ty)),
+            |(span, (vis, ident, _, _, _, ty))| RStructField { span, vis: vis.map(|(vis, _)| vis), ident, ty }
         )
     );
 
-    // --- STATEMENTS ---
+    tuple_field_rule.set(
+        MapV(
+            Spanned((Maybe((vis, w)), ty)),
+            |(span, (vis, ty))| RTupleField { span, vis: vis.map(|(vis, _)| vis), ty }
+        )
+    );
 
-    statement_rule.set(Funnel6(
-            MapV(if_statement,   |stmt| RStatement::If { stmt }),
-            MapV(loop_statement, |stmt| RStatement::Loop { stmt }),
-            return_statement,
-            let_statement,
-            expr_semi_statement,
-            semi_statement,
+    // the rule to parse a `File`
+
+    file_rule.set(MapV((
+            w,
+            Maybe(("\\uFEFF", w)),
+            Maybe((SpanOf(("#!", OneOrMore((Not('\n'), AnyV())))), w)),
+            //ZeroOrMore((inner_attribute, w)),
+            ZeroOrMore((item, w)),
+            Req(End(), |_, pos: PPos, _| panic(Span::new(pos.clone(), pos.clone()), "file", "parser failed to reach the end of the file (from this pos)"))
         ),
-    );
+        |(_, utf8bom, shebang, items, _)| {
+            RCrate {
+                utf8bom: utf8bom.map(|(s, _)|s),
+                shebang: shebang.map(|(s, _)|s),
+                items: items.into_iter().map(|(i, _)|i).collect()
+            }
+        }
+    ));
 
-    return_statement_rule.set(
-        MapV(
-            Leader(
-                "return",
-                (w, expr, Maybe((w, ';'))),
-                |_, return_span, _| panic(return_span, "statement", "expected expression after this \"return\" keyword")
-            ),
-            |(return_span, (_, expr, maybe_semi))| {
-                RStatement::Return {
-                    return_span,
-                    expr,
-                    semi: match maybe_semi { Some(v) => Some(v.1), None => None }
-                }
+    file.parse(ParseContext::new(&AnyMemTable::new(file_text), PPos::new()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    use std::rc::Rc;
+
+    use crate::parser::parser::{RCrate, RFn, RItem, RStatement, Execute};
+    use crate::symex::{SymVar, IntTy, SummaryCache, Budget, BudgetKind, Scheduler, Strategy, SymModel, SolverConfig, SolverKind};
+
+    use super::{parse_file, ExecuteArgs, SymexRes, ExOk, ExErr, PanicKind, BinOp, RComment, RSymexQuery, RExpr, QueryOutcome, MacroKind};
+    use super::super::ParseResult;
+    use ParseResult::*;
+    use super::PPos;
+    use super::super::{ParseNode, ParseContext, AnyMemTable, SpanOf, Span};
+
+    #[test]
+    fn test_if_stmt() {
+        match parse_file("
+            fn a(x:i32, y:i32) -> u128 { 
+                if x == 2 { }
             }
-        ),
-    );
+            ") {
+            Okay(value, advance) => {
+                println!("{:?} {:?}", value, advance)
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_stmt_semi_end_fn() {
+        match parse_file("
+            fn a(x:i32, y:i32) -> u128 { 
+                let u = 6; 
+                8 / 4; 
+                5 - 2;
+            }
+            ") {
+            Okay(value, advance) => {
+                println!("{:?} {:?}", value, advance)
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_stmt_fn() {
+        match parse_file(" fn hello() -> u8 {  (10 + 3) * 3 / 4 ^ (10); 10; 23; 0 } ") {
+            Okay(value, advance) => {
+                println!("{:?} {:?}", value, advance)
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_expr_fn() {
+        match parse_file(" fn hello() -> u8 {  (10 + 3) * 3 / 4 ^ (10) } ") {
+            Okay(value, advance) => {
+                println!("{:?} {:?}", value, advance)
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_add_fn() {
+        match parse_file(" fn hello() -> u8 {10 + 3}") {
+            Okay(value, advance) => {
+                println!("{:?} {:?}", value, advance)
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_empty_fn() {
+        match parse_file(" fn hello() -> u8 {0}") {
+            Okay(value, advance) => {
+                println!("{:?} {:?}", value, advance)
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        match parse_file("") {
+            Okay(value, advance) => {
+                println!("{}: {:?}", advance, value);
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_bv_lisp() {
+        let s = "fn f(x: u8, y: i64) { x / 2; y < -3; (x as i64) >> y }";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let sigma = vec![SymVar::new("x".to_string(), "u8".to_string()), SymVar::new("y".to_string(), "i64".to_string())];
+        let RItem::Fn { val, .. } = &krate.items[0] else { panic!("Expected a fn") };
+        let lisp: Vec<String> = val.body.statements.iter().map(|stmt| match stmt {
+            RStatement::Expr { expr, .. } => expr.to_bv_lisp(s, &sigma, IntTy::I32),
+            _ => panic!("expected expression statement"),
+        }).collect();
+
+        assert_eq!(lisp[0], "(bvudiv x (_ bv2 8))");
+        assert_eq!(lisp[1], "(bvslt y (bvneg (_ bv3 64)))");
+        assert_eq!(lisp[2], "(bvashr ((_ zero_extend 56) x) y)");
+    }
+
+    #[test]
+    fn test_span_of_tuple() {
+        let s = "ab cd!";
+        let node: &dyn ParseNode<Span<PPos>, String, AnyMemTable<&str, PPos, char>, PPos, char> = &SpanOf(("ab", ' ', "cd"));
+        match node.parse(ParseContext::new(&AnyMemTable::new(s), PPos::new())) {
+            Okay(span, advance) => {
+                assert_eq!(span.start.index, 0);
+                assert_eq!(span.end.index, 5);
+                assert_eq!(advance.index, 5);
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_parse_symex_query() {
+        let s = "
+fn f(w: i32) -> i32 {
+    //symex assert w != 0
+    //symex reach
+    //symex - what is w
+    //symex assert w
+    -w
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let RItem::Fn { val, .. } = &krate.items[0] else { panic!("Expected a fn") };
+        let queries: Vec<_> = val.body.statements.iter().filter_map(|stmt| match stmt {
+            RStatement::Comment { comment: RComment::Symex { query, .. } } => Some(query.clone()),
+            _ => None,
+        }).collect();
+
+        assert_eq!(queries.len(), 4);
+        assert!(matches!(&queries[0], Some(RSymexQuery::Assert { expr, .. }) if expr.span().into_string(s) == "w != 0"));
+        assert!(matches!(&queries[1], Some(RSymexQuery::Reach { .. })));
+        assert_eq!(queries[2], None);
+        // `-w` on the next line is not part of the query
+        assert!(matches!(&queries[3], Some(RSymexQuery::Assert { expr, .. }) if expr.span().into_string(s) == "w"));
+        assert!(matches!(val.body.statements.last(), Some(RStatement::Expr { expr: RExpr::Negate { .. }, .. })));
+    }
+
+    #[test]
+    fn test_symex_query() {
+        let s = "
+fn f(x: i32) -> i32 {
+    let w = x + 1;
+    if x > 5 {
+        //symex assert w > 6
+        return w;
+    }
+    //symex assert w != 0
+    if x > 10 {
+        //symex reach
+    }
+    w
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let mut engine = Vec::new();
+        let ok = krate.execute(&mut engine, ExecuteArgs::new(s)).unwrap();
+
+        let outcomes: Vec<(&str, &QueryOutcome)> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Query { query, outcome, .. } => Some((query.as_str(), outcome)),
+            _ => None,
+        }).collect();
+
+        assert!(outcomes.contains(&("assert w > 6", &QueryOutcome::Proved)));
+        assert!(outcomes.iter().any(|(query, outcome)| *query == "assert w != 0" && matches!(outcome, QueryOutcome::Counterexample(model) if model.get("x") == Some("-1"))));
+        assert!(outcomes.contains(&("reach", &QueryOutcome::Unreachable)));
+    }
+
+    #[test]
+    fn test_if_values() {
+        let s = "
+fn f(x: i32) -> i32 {
+    let y = if x > 0 { x } else { 0 - x };
+    let z = if x > 5 { 1 } else { 2 };
+    //symex assert y >= 0
+    //symex assert z <= 2
+    //symex assert y != z
+    y + z
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let mut engine = Vec::new();
+        let ok = krate.execute(&mut engine, ExecuteArgs::new(s)).unwrap();
+
+        // each if has a value of its own, taken from the block that ran
+        let outcomes: Vec<(&str, &QueryOutcome)> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Query { query, outcome, .. } => Some((query.as_str(), outcome)),
+            _ => None,
+        }).collect();
+        assert!(outcomes.iter().filter(|(query, _)| *query != "assert y != z").all(|(_, outcome)| **outcome == QueryOutcome::Proved), "{:?}", outcomes);
+        assert!(outcomes.iter().any(|(query, outcome)| *query == "assert y != z" && matches!(outcome, QueryOutcome::Counterexample(model) if ["1", "2", "-2"].contains(&model.get("x").unwrap()))));
+        assert!(!outcomes.iter().any(|(_, outcome)| **outcome == QueryOutcome::Unknown));
+    }
+
+    #[test]
+    fn test_query_reachability() {
+        let s = "
+fn g(a: i32) -> i32 {
+    if a > 0 {
+        //symex reach
+    }
+    //symex reach
+    a
+}
+
+fn f(x: i32) -> i32 {
+    g(-1) + g(x) + g(-2)
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let RItem::Fn { val: f, .. } = &krate.items[1] else { panic!("Expected a fn") };
+        let mut engine = Vec::new();
+        let ok = f.execute(&mut engine, ExecuteArgs::new(s).with_fns(Rc::new(krate.fn_map(s)))).unwrap();
+
+        // `g(-1)` and `g(-2)` never reach the first query but `g(x)` does
+        let queries: Vec<&QueryOutcome> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Query { outcome, .. } => Some(outcome),
+            _ => None,
+        }).collect();
+        assert!(!queries.contains(&&QueryOutcome::Unreachable));
+        assert!(queries.iter().all(|outcome| matches!(outcome, QueryOutcome::Reached(_))));
+
+        // with only those calls, it is reported unreachable once
+        let s = "
+fn g(a: i32) -> i32 {
+    if a > 0 {
+        //symex reach
+    }
+    a
+}
+
+fn f(x: i32) -> i32 {
+    g(-1) + g(-2)
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let RItem::Fn { val: f, .. } = &krate.items[1] else { panic!("Expected a fn") };
+        let mut engine = Vec::new();
+        let ok = f.execute(&mut engine, ExecuteArgs::new(s).with_fns(Rc::new(krate.fn_map(s)))).unwrap();
+        assert_eq!(ok.res.iter().filter(|res| matches!(res, SymexRes::Query { outcome: QueryOutcome::Unreachable, .. })).count(), 1);
+    }
 
-    let_statement_rule.set(
-        MapV(
-            Leader(
-                "let",
-                (w, Maybe(("mut", w)), ident, Maybe((w, ':', w, ty)), w, '=', w, expr, Maybe((w, ';'))),
-                |_, let_span, _| panic(let_span, "statement", "expected_variable assignment after this let statement")
-            ),
-            |(let_span, (_, mutable, ident, maybe_type, _, eq_span, _, expr, maybe_semi))| {
-                RStatement::Assign {
-                    let_: let_span,
-                    mutable: mutable.map(|(s, _)|s),
-                    ident,
-                    ty: match maybe_type {
-                        Some((_, _colon_span, _, ty)) => Some(ty),
-                        None => None,
-                    },
-                    equal_sign: eq_span,
-                    equal_value: expr,
-                    semicolon: match maybe_semi {
-                        Some((_, span)) => Some(span),
-                        None => None,
-                    },
-                }
-            }
-        ),
-    );
+    #[test]
+    fn test_unknown_outcomes() {
+        let s = "
+fn f(x: i32, y: i32) -> i32 {
+    //symex assert x * y != 1000003
+    100 / (x * y - 1000003)
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let solver = SolverConfig::new().with_kind(Some(SolverKind::Builtin)).with_timeout_ms(Some(1));
+        let mut engine = Vec::new();
+        let ok = krate.execute(&mut engine, ExecuteArgs::new(s).with_solver(Rc::new(solver))).unwrap();
+
+        // running out of time proves nothing and drops no panic
+        assert!(ok.res.iter().any(|res| matches!(res, SymexRes::Query { outcome: QueryOutcome::Unknown, .. })));
+        assert!(!ok.res.iter().any(|res| matches!(res, SymexRes::Query { outcome: QueryOutcome::Proved, .. })));
+        assert!(ok.res.iter().any(|res| matches!(res, SymexRes::UnknownPanic { kind: PanicKind::DivByZero, .. })));
+    }
 
-    expr_semi_statement_rule.set(MapV(
-        (expr, Maybe((w, ';'))),
-        |(expr, maybe_semi)| {
-            RStatement::Expr { expr, semi: maybe_semi.map(|(_, s)|s) }
-        }
-    ));
+    #[test]
+    fn test_non_bool_assert() {
+        let s = "
+fn f(x: i32) -> i32 {
+    //symex assert x + 1
+    //symex assert x > 0
+    x
+}
 
-    semi_statement_rule.set(MapV(
-        ";",
-        |semi| RStatement::SColon { semi }
-        ),
-    );
+fn g(x: i32) -> i32 {
+    //symex assert x < 10
+    x
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let mut engine = Vec::new();
+        let ok = krate.execute(&mut engine, ExecuteArgs::new(s)).unwrap();
+        let outcomes: Vec<(&str, &QueryOutcome)> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Query { query, outcome, .. } => Some((query.as_str(), outcome)),
+            _ => None,
+        }).collect();
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0], ("assert x + 1", &QueryOutcome::NonBoolAssert));
+        assert!(matches!(outcomes[1], ("assert x > 0", QueryOutcome::Counterexample(_))));
+        assert!(matches!(outcomes[2], ("assert x < 10", QueryOutcome::Counterexample(_))));
+    }
 
-//     Spanned(Leader(
-//            "if", (w, expr, w, block,
-//                Maybe((w, Leader("else", (w, OneOf2(block, if_statement)),
-//                    |_, pos, _| panic(pos, "if_statement", "expected code block after this \"else\"")
-//                )))
-//            )
+    #[test]
+    fn test_parse_macros() {
+        let s = "
+fn f(x: i32) {
+    assert!(x > 0);
+    debug_assert!(x != 3, \"x is {}\", x,);
+    if x > 10 {
+        panic!();
+    }
+    unreachable!(\"no {} here\", x)
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let RItem::Fn { val, .. } = &krate.items[0] else { panic!("Expected a fn") };
+        let stmts = &val.body.statements;
+
+        let macro_of = |stmt: &RStatement| match stmt {
+            RStatement::Expr { expr: mac @ RExpr::Macro { kind, args, .. }, .. } => Some((*kind, args.len(), mac.macro_panic_kind(s))),
+            _ => None,
+        };
+        assert_eq!(macro_of(&stmts[0]), Some((MacroKind::Assert, 1, PanicKind::AssertFailed("assertion failed: x > 0".to_string()))));
+        assert_eq!(macro_of(&stmts[1]), Some((MacroKind::DebugAssert, 3, PanicKind::AssertFailed("x is ".to_string()))));
+        assert_eq!(macro_of(&stmts[3]), Some((MacroKind::Unreachable, 2, PanicKind::Unreachable("internal error: entered unreachable code: no ".to_string()))));
+        assert_eq!(PanicKind::Explicit("explicit panic".to_string()).to_string(), "explicit panic");
+    }
 
-    if_statement_rule.set(MapV(Spanned((
-            OJoin(
-                MapV(
-                    Leader("if", (w, expr, w, block), |_, pos, _| panic(pos, "if_statement", "expected expression and body of the if statement after this \"if\" keyword")),
-                    |(_, (_, expr, _, block))| (expr, block) 
-                ),
-                (w, "else", w)
-            ),
-            Maybe(MapV((w, "else", w, block), |(_, _, _, block)| block))
-        )),
-        |(span, (ifs, else_stmt))| {
-            RIf { span, ifs, else_stmt }
-        }
-    ));
+    #[test]
+    fn test_macro_panics() {
+        let s = "
+fn f(x: i32) -> i32 {
+    assert!(x < 100);
+    if x > 10 {
+        panic!(\"too big\");
+    }
+    if x > 50 {
+        unreachable!();
+    }
+    x
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let mut engine = Vec::new();
+        let ok = krate.execute(&mut engine, ExecuteArgs::new(s)).unwrap();
+
+        let panics: Vec<(&PanicKind, i32)> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Panic { kind, model, .. } => Some((kind, model.get("x").unwrap().parse().unwrap())),
+            _ => None,
+        }).collect();
+
+        // the failing assertion and the panic come with witnesses, and the
+        // unreachable! cannot be reached past them
+        assert_eq!(panics.len(), 2);
+        assert!(panics.iter().any(|(kind, x)| **kind == PanicKind::AssertFailed("assertion failed: x < 100".to_string()) && *x >= 100));
+        assert!(panics.iter().any(|(kind, x)| **kind == PanicKind::Explicit("too big".to_string()) && *x > 10 && *x < 100));
+    }
 
-    loop_statement_rule.set(Funnel3(
-        infinite_loop,
-        while_loop,
-        for_loop,
-    ));
+    #[test]
+    fn test_local_from_local() {
+        let s = "
+fn f(x: i32) {
+    let a = x + 1;
+    let b = a * 2;
+    if b > 10 {
+        //symex
+    }
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let mut engine = Vec::new();
+        krate.execute(&mut engine, ExecuteArgs::new(s)).unwrap();
 
-    infinite_loop_rule.set(MapV(
-        Spanned(("loop", w, block)),
-        |(span, (_, _, block))| RLoop::Infinite { span, block }
-    ));
+        let b = engine[0].sigma.iter().find(|v| v.name == "b").unwrap();
+        assert_eq!(b.var0.to_lisp(), "(bvmul (bvadd x (_ bv1 32)) (_ bv2 32))");
+    }
 
-    while_loop_rule.set(MapV(
-        Spanned(("while", w, expr, w, block)),
-        |(span, (_, _, expr, _, block))| RLoop::While { span, expr, block }
-    ));
+    #[test]
+    fn test_panic_conditions() {
+        let s = "fn f(x: u8, y: i8) { x + 1; y / 2; y << x }";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let sigma = vec![SymVar::new("x".to_string(), "u8".to_string()), SymVar::new("y".to_string(), "i8".to_string())];
+        let RItem::Fn { val, .. } = &krate.items[0] else { panic!("Expected a fn") };
+        let conds: Vec<Vec<(PanicKind, String)>> = val.body.statements.iter().map(|stmt| match stmt {
+            RStatement::Expr { expr, .. } => {
+                expr.panic_conditions(s, &sigma, IntTy::I32).into_iter().map(|(kind, cond)| (kind, cond.to_lisp())).collect()
+            },
+            _ => panic!("expected expression statement"),
+        }).collect();
+
+        assert_eq!(conds[0], vec![(
+            PanicKind::Overflow(BinOp::Add),
+            "(not (= ((_ zero_extend 8) (bvadd x (_ bv1 8))) (bvadd ((_ zero_extend 8) x) ((_ zero_extend 8) (_ bv1 8)))))".to_string()
+        )]);
+        assert_eq!(conds[1], vec![
+            (PanicKind::DivByZero, "(= (_ bv2 8) (_ bv0 8))".to_string()),
+            (PanicKind::Overflow(BinOp::Div), "(and (= y (_ bv128 8)) (= (_ bv2 8) (_ bv255 8)))".to_string()),
+        ]);
+        assert_eq!(conds[2], vec![(PanicKind::Overflow(BinOp::LSh), "(bvuge x (_ bv8 8))".to_string())]);
+
+        let s = "fn g(y: i8) { -128i8; -2147483648; -y }";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let RItem::Fn { val, .. } = &krate.items[0] else { panic!("Expected a fn") };
+        let conds: Vec<Vec<(PanicKind, String)>> = val.body.statements.iter().map(|stmt| match stmt {
+            RStatement::Expr { expr, .. } => {
+                expr.panic_conditions(s, &sigma, IntTy::I32).into_iter().map(|(kind, cond)| (kind, cond.to_lisp())).collect()
+            },
+            _ => panic!("expected expression statement"),
+        }).collect();
 
-    for_loop_rule.set(MapV(
-        Spanned(("for", w, ident, w, "in", w, expr, w, block)),
-        |(span, (_, _, var, _, _, _, expr, _, block))| RLoop::For { span, var, expr, block }
-    ));
+        assert_eq!(conds[0], vec![]);
+        assert_eq!(conds[1], vec![]);
+        assert_eq!(conds[2], vec![(PanicKind::NegOverflow, "(= y (_ bv128 8))".to_string())]);
 
-    // --- Function ---
+        assert_eq!(PanicKind::Overflow(BinOp::Add).to_string(), "attempt to add with overflow");
+        assert_eq!(PanicKind::DivByZero.to_string(), "attempt to divide by zero");
+    }
 
-    // the rule to parse a function
-    func_rule.set(
-        MapV(
-            Spanned(Leader(
-                "fn",
-                (w, Leader(ident,
-                        (w,
-                            Surround(
-                                '(', (w, params, w), ')',
-                                |_, oparen_span, _| panic(oparen_span, "func", "expected parameters in this function argument scope"),
-                                |_, oparen_span, _, _| panic(oparen_span, "func", "expected closing parenthesis to match this open parenthesis")
-                            ),
-                            Maybe((w, return_type)),
-                            Req((w, block), |_, p, _| panic(Span::new(p.clone(), p), "func", "function requires a function body"))
-                        ),
-                        |_, ident_span, _| panic(ident_span, "func", "expected function parameters and body after function identifier")
-                    )
-                ),
-                |_, fn_span, _| panic(fn_span, "func", "expected correct function syntax after 'fn' keyword"))),
-            |(span, (fn_span, (_, (id_span, (_, (_oparen, (_, params, _), _cparen), ret_type, (_, body))))))| {
-                RFn {
-                    span,
-                    fn_span,
-                    id: id_span,
-                    args: params,
-                    ret_type: ret_type.map(|(_, t)| t),
-                    body,
-                }
-            }
-        )
-    );
+    #[test]
+    fn test_div_by_zero_panic() {
+        let s = "
+fn b_algebra(mut x:i32, mut y:i32) -> i32 {
+    x = x + 4;
+    y = 2 * y;
+    let w = x / y;
+    return w;
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let mut engine = Vec::new();
+        let ok = krate.execute(&mut engine, ExecuteArgs::new(s)).unwrap();
+
+        let model = ok.res.iter().find_map(|res| match res {
+            SymexRes::Panic { kind: PanicKind::DivByZero, model, .. } => Some(model),
+            _ => None,
+        }).expect("expected a division by zero");
+        assert_eq!(model.get("y"), Some("0"));
+    }
 
-    // a vector of function parameters
-    params_rule.set(MapV(
-        (Join(param, (w, ',', w)), Maybe((w, ','))),
-        |(params, _)| params
-    ));
+    #[test]
+    fn test_truncated_paths() {
+        let s = "
+fn f(n: i32) -> i32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let mut engine = Vec::new();
+        let ok = krate.execute(&mut engine, ExecuteArgs::new(s).with_max_loop_iter(3)).unwrap();
+
+        let truncated: Vec<bool> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Path { truncated, .. } => Some(*truncated),
+            _ => None,
+        }).collect();
+        assert_eq!(truncated.len(), 4);
+        assert_eq!(truncated.iter().filter(|t| **t).count(), 1);
+    }
 
-    // function parameter
-    param_rule.set(MapV(
-        (
-            Maybe(("mut", w)),
-            Leader(
-                ident,
-                (w, Leader(
-                        ':', (w, ty),
-                        |_, colon_span, _| panic(colon_span, "param", "missing type after this colon"),
-                    )
-                ),
-                |_, id_span, _| panic(id_span, "param", "missing arg's type"),
-            )
-        ),
-        |(mutable, (id, (_, (_colon, (_, ty)))))| RParam { mutable: mutable.map(|(s, _)|s), id, ty, }
-    ));
+    #[test]
+    fn test_budget_frontier() {
+        let s = "
+fn f(x: i32) -> i32 {
+    let mut y = 0;
+    if x > 0 {
+        y += 1;
+    }
+    if x > 10 {
+        y += 1;
+    }
+    if x > 20 {
+        y += 1;
+    }
+    y
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let count = |ok: &ExOk| (
+            ok.res.iter().filter(|res| matches!(res, SymexRes::Path { .. })).count(),
+            ok.res.iter().filter(|res| matches!(res, SymexRes::Frontier { .. })).count(),
+        );
 
-    vis_rule.set(
-        Funnel4(
-            MapV(SpanOf(("pub", w, '(', w, "crate", w, ')')), |span| RVis::VisCrate { span }),
-            MapV(SpanOf(("pub", w, '(', w, "self" , w, ')')), |span| RVis::VisSelf  { span }),
-            MapV(SpanOf(("pub", w, '(', w, "super", w, ')')), |span| RVis::VisSuper { span }),
-            MapV("pub", |span| RVis::VisPub { span }),
-            //MapV(("pub", w, '(', w, "in", w, simple_path, w, ')'), |(_, _, _, _, _, _, path, _, _)| RVis::VisPath { span, path })
-        ),
-    );
+        let ok = krate.execute(&mut Vec::new(), ExecuteArgs::new(s)).unwrap();
+        assert_eq!(count(&ok), (4, 0));
 
-    item_rule.set(
-        MapV(Spanned((Maybe(vis), w, func)), |(span, (vis, _, val))| RItem::Fn { span, vis, val })
-    );
+        // the paths still pending once two were forked are reported instead
+        for strategy in [Strategy::Dfs, Strategy::Bfs, Strategy::RandomPath, Strategy::Coverage] {
+            let scheduler = Rc::new(RefCell::new(Scheduler::new(strategy, Budget::new().with_max_paths(Some(2)))));
+            let ok = krate.execute(&mut Vec::new(), ExecuteArgs::new(s).with_scheduler(scheduler.clone())).unwrap();
+            let (paths, frontier) = count(&ok);
+            assert!(paths < 4 && frontier > 0, "{:?}: {} paths, {} left", strategy, paths, frontier);
+            assert_eq!(scheduler.borrow().exhausted, Some(BudgetKind::Paths));
+        }
 
-    // the rule to parse a `File`
+        // each strategy reaches the end of the paths in its own order, told
+        // apart by how many of the bounds `x` is above
+        let s = "
+fn f(x: i32) -> i32 {
+    let mut y = 0;
+    if x > 0 {
+        y += 1;
+    } else if x > -10 {
+        y += 2;
+    } else {
+        y += 3;
+    }
+    if x > 20 {
+        y += 10;
+    }
+    //symex reach
+    y
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let order = |strategy: Strategy| {
+            let scheduler = Rc::new(RefCell::new(Scheduler::new(strategy, Budget::new()).with_seed(3)));
+            let ok = krate.execute(&mut Vec::new(), ExecuteArgs::new(s).with_scheduler(scheduler)).unwrap();
+            ok.res.iter().filter_map(|res| match res {
+                SymexRes::Query { outcome: QueryOutcome::Reached(model), .. } => {
+                    let x: i32 = model.get("x").unwrap().parse().unwrap();
+                    Some([-10, 0, 20].iter().filter(|bound| x > **bound).count())
+                },
+                _ => None,
+            }).collect::<Vec<usize>>()
+        };
+        // depth first finishes a path before the one forked before it
+        assert_eq!(order(Strategy::Dfs), vec![1, 3, 2, 0]);
+        // the path that took one branch goes before those that took two
+        assert_eq!(order(Strategy::Bfs), vec![2, 3, 0, 1]);
+        assert_eq!(order(Strategy::RandomPath), vec![0, 1, 3, 2]);
+        // the middle arm of the first if is visited twice, so its path is last
+        assert_eq!(order(Strategy::Coverage), vec![0, 2, 3, 1]);
+    }
 
-    file_rule.set(MapV((
-            w,
-            Maybe(("\\uFEFF", w)),
-            Maybe((SpanOf(("#!", OneOrMore((Not('\n'), AnyV())))), w)),
-            //ZeroOrMore((inner_attribute, w)),
-            ZeroOrMore((item, w)),
-            Req(End(), |_, pos: PPos, _| panic(Span::new(pos.clone(), pos.clone()), "file", "parser failed to reach the end of the file (from this pos)"))
-        ),
-        |(_, utf8bom, shebang, items, _)| {
-            RCrate {
-                utf8bom: utf8bom.map(|(s, _)|s),
-                shebang: shebang.map(|(s, _)|s),
-                items: items.into_iter().map(|(i, _)|i).collect()
-            }
+    #[test]
+    fn test_prune_infeasible() {
+        let s = "
+fn f(x: i32) -> i32 {
+    let mut y = 0;
+    if x > 5 {
+        y = 1;
+    } else if x > 2 {
+        y = 2;
+    } else if x > 10 {
+        y = 3 / 0;
+    } else {
+        y = 4;
+    }
+    if x < 3 {
+        while x > 5 {
+            y = 5;
         }
-    ));
+    }
+    if x > 5 {
+        if x < 3 {
+            //symex reach
+        }
+    }
+    y
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let scheduler = Rc::new(RefCell::new(Scheduler::default()));
+        let ok = krate.execute(&mut Vec::new(), ExecuteArgs::new(s).with_scheduler(scheduler.clone())).unwrap();
+
+        let rets: HashSet<String> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Path { ret: Some(ret), .. } => Some(ret.clone()),
+            _ => None,
+        }).collect();
+        assert_eq!(rets, HashSet::from(["1".to_string(), "2".to_string(), "4".to_string()]));
+        assert!(!ok.res.iter().any(|res| matches!(res, SymexRes::Panic { .. })));
+        assert!(ok.res.iter().any(|res| matches!(res, SymexRes::Query { outcome: QueryOutcome::Unreachable, .. })));
+
+        // the third arm of the chain, an arm of both later ifs on each of the
+        // three paths, the loop on the path with x < 3 and the inner if
+        assert_eq!(scheduler.borrow().pruned(), 9);
+    }
+
+    #[test]
+    fn test_pruned_query_reached_elsewhere() {
+        let s = "
+fn f(x: i32) -> i32 {
+    let mut y = 0;
+    if x < -5 {
+        y = 1;
+    }
+    if x > 0 {
+        //symex reach
+        y += 2;
+    }
+    y
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let ok = krate.execute(&mut Vec::new(), ExecuteArgs::new(s)).unwrap();
+
+        // the path with x < -5 prunes the arm the other path reaches it through
+        let outcomes: Vec<&QueryOutcome> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Query { outcome, .. } => Some(outcome),
+            _ => None,
+        }).collect();
+        assert!(!outcomes.is_empty());
+        assert!(outcomes.iter().all(|outcome| matches!(outcome, QueryOutcome::Reached(_))));
+    }
+
+    #[test]
+    fn test_merge_paths() {
+        let s = "
+fn f(x: i32) -> i32 {
+    let mut y = 0;
+    if x > 0 {
+        y += 1;
+    }
+    if x > 10 {
+        y += 1;
+    } else {
+        y += 4;
+    }
+    if x > 20 {
+        return y;
+    }
+    y
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let paths = |ok: &ExOk| ok.res.iter().filter(|res| matches!(res, SymexRes::Path { .. })).count();
+
+        let ok = krate.execute(&mut Vec::new(), ExecuteArgs::new(s).with_returns(Some(5))).unwrap();
+        assert_eq!(paths(&ok), 4);
+
+        // the first two ifs each end on one path, the return keeps the third
+        // from merging
+        let ok = krate.execute(&mut Vec::new(), ExecuteArgs::new(s).with_returns(Some(5)).with_merge(true)).unwrap();
+        assert_eq!(paths(&ok), 2);
+        let returns: Vec<&str> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Returns { model, .. } => model.get("x"),
+            _ => None,
+        }).collect();
+        assert_eq!(returns.len(), 1);
+        let x: i32 = returns[0].parse().unwrap();
+        assert!((1..=10).contains(&x), "{}", x);
+    }
 
-    file.parse(ParseContext::new(&AnyMemTable::new(file_text), PPos::new()))
+    #[test]
+    fn test_loop_summaries() {
+        let s = "
+fn f(n: i64) -> i64 {
+    let mut i: i64 = 0;
+    let mut j: i64 = 1;
+    while i < n {
+        j = j - 1;
+        i = i + 1;
+    }
+    //symex assert i >= n
+    i
 }
 
+fn g(n: i64) -> i64 {
+    let mut i: i64 = 0;
+    let mut j: i64 = 1;
+    while i < n {
+        j = j * 2;
+        i = i + 1;
+    }
+    i
+}
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashSet;
+fn h(n: u8) -> u8 {
+    let mut i: u8 = 0;
+    while i < n {
+        let q = 1000 / (i as i32 - 50);
+        i = i + 2;
+    }
+    i
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let args = ExecuteArgs::new(s).with_max_loop_iter(3).with_returns(Some(50));
+        let proved = |ok: &ExOk| ok.res.iter().all(|res| !matches!(res, SymexRes::Query { outcome, .. } if *outcome != QueryOutcome::Proved));
+        let returns = |ok: &ExOk| ok.res.iter().filter_map(|res| match res {
+            SymexRes::Returns { model, .. } => model.get("n").map(str::to_string),
+            _ => None,
+        }).collect::<Vec<_>>();
+
+        // cut off after three iterations, i is 3 whatever n is
+        let ok = krate.execute(&mut Vec::new(), args.clone()).unwrap();
+        assert!(!proved(&ok));
+        assert!(returns(&ok).is_empty());
+        assert!(ok.res.contains(&SymexRes::WhileLoopMaxIterHit));
+
+        let ok = krate.execute(&mut Vec::new(), args.with_loop_summaries(true)).unwrap();
+        assert!(proved(&ok));
+        // f and h return 50, for n = 50 and n = 49 or 50
+        assert!(returns(&ok).contains(&"50".to_string()));
+
+        // j in g doubles, and may overflow after any number of iterations,
+        // so its loop alone is still cut off
+        assert_eq!(ok.res.iter().filter(|res| **res == SymexRes::WhileLoopMaxIterHit).count(), 1);
+
+        // h divides by zero once i is 50, and i overflows from 254
+        let panics: Vec<(&PanicKind, u8)> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Panic { kind, model, .. } => Some((kind, model.get("n").unwrap().parse().unwrap())),
+            _ => None,
+        }).collect();
+        assert!(panics.iter().any(|(kind, n)| **kind == PanicKind::DivByZero && *n > 50));
+        assert!(panics.iter().any(|(kind, n)| matches!(kind, PanicKind::Overflow(_)) && *n == 255));
+        assert!(panics.iter().all(|(kind, n)| !matches!(kind, PanicKind::Overflow(_)) || *n == 255));
+    }
 
-    use crate::parser::parser::{RCrate, RFn, Execute};
+    #[test]
+    fn test_for_ranges() {
+        let s = "
+fn f(n: u8) -> u8 {
+    let i: u8 = 7;
+    let mut sum: u8 = 0;
+    for i in 0..n {
+        sum = sum + i;
+    }
+    //symex assert i == 7
+    sum
+}
 
-    use super::{parse_file, ExecuteArgs, SymexRes, ExOk};
-    use super::super::ParseResult;
-    use ParseResult::*;
-    use super::PPos;
+fn g(n: u8) -> u8 {
+    let mut c: u8 = 0;
+    for k in 3..=n {
+        c = c + 1;
+    }
+    c
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let args = ExecuteArgs::new(s).with_max_loop_iter(5);
+        let ok = krate.execute(&mut Vec::new(), args.clone()).unwrap();
+
+        // the outer i is shadowed only inside the loop
+        assert!(ok.res.iter().all(|res| !matches!(res, SymexRes::Query { outcome, .. } if *outcome != QueryOutcome::Proved)));
+        assert!(ok.res.iter().all(|res| !matches!(res, SymexRes::Panic { .. })));
+        // f loops more than five times from n = 5 and g from n = 8
+        assert!(ok.res.contains(&SymexRes::ForLoopMaxIterHit));
+
+        // 0 + 1 + 2 + 3 = 6 for n = 4, and 3..=4 runs twice
+        let ok = krate.execute(&mut Vec::new(), args.clone().with_returns(Some(6))).unwrap();
+        let returns = |ok: &ExOk| ok.res.iter().filter_map(|res| match res {
+            SymexRes::Returns { model, .. } => model.get("n").map(str::to_string),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert_eq!(returns(&ok), vec!["4".to_string()]);
+        let ok = krate.execute(&mut Vec::new(), args.with_returns(Some(2))).unwrap();
+        assert_eq!(returns(&ok), vec!["4".to_string()]);
+    }
 
     #[test]
-    fn test_if_stmt() {
-        match parse_file("
-            fn a(x:i32, y:i32) -> u128 { 
-                if x == 2 { }
+    fn test_break_continue() {
+        let s = "
+fn f(n: u8) -> u8 {
+    let mut i: u8 = 0;
+    let x: u8 = loop {
+        if i == n {
+            break i + 1;
+        }
+        if i == 3 {
+            break 0;
+        }
+        i = i + 1;
+    };
+    x
+}
+
+fn g(n: u8) -> u8 {
+    let mut c: u8 = 0;
+    'outer: for i in 0..n {
+        for j in 0..n {
+            if j == 2 {
+                continue 'outer;
             }
-            ") {
-            Okay(value, advance) => {
-                println!("{:?} {:?}", value, advance)
-            },
+            if i == 3 {
+                break 'outer;
+            }
+            c = c + 1;
+        }
+    }
+    c
+}
+
+fn h(n: u8) -> u8 {
+    let mut i: u8 = 0;
+    let mut odd: u8 = 0;
+    while i < n {
+        i = i + 1;
+        if i % 2 == 0 {
+            continue;
+        }
+        odd = odd + 1;
+    }
+    odd
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
-        }
+        };
+        let args = ExecuteArgs::new(s).with_max_loop_iter(5);
+        let returns = |value: i128| {
+            let ok = krate.execute(&mut Vec::new(), args.clone().with_returns(Some(value))).unwrap();
+            let mut ns = ok.res.iter().filter_map(|res| match res {
+                SymexRes::Returns { model, .. } => model.get("n").map(str::to_string),
+                _ => None,
+            }).collect::<Vec<_>>();
+            ns.sort();
+            (ns, ok)
+        };
+
+        // f breaks out of its loop with n + 1 (or 0 past 3), g leaves both
+        // loops by the third iteration of either, and h continues on the
+        // even i
+        let (ns, ok) = returns(4);
+        assert_eq!(ns, vec!["2".to_string(), "3".to_string()]);
+        assert!(!ok.res.contains(&SymexRes::ForLoopMaxIterHit));
+        assert!(ok.res.iter().all(|res| !matches!(res, SymexRes::Panic { .. })));
+        let (ns, _) = returns(2);
+        assert_eq!(ns, vec!["1".to_string(), "3".to_string(), "4".to_string()]);
     }
 
     #[test]
-    fn test_stmt_semi_end_fn() {
-        match parse_file("
-            fn a(x:i32, y:i32) -> u128 { 
-                let u = 6; 
-                8 / 4; 
-                5 - 2;
-            }
-            ") {
-            Okay(value, advance) => {
-                println!("{:?} {:?}", value, advance)
-            },
+    fn test_match() {
+        let s = "
+fn classify(n: i32) -> i32 {
+    match n {
+        0 => 10,
+        1 | 2 => 20,
+        3..=9 => 30,
+        x if x < 0 => 40,
+        5 => 50,
+        _ => 60,
+    }
+}
+
+fn pair(a: u8, b: u8) -> u8 {
+    let v: u8 = match (a, b) {
+        (0, y) => y,
+        (x, 0) => x - 1,
+        (_, _) => 1,
+    };
+    v
+}
+
+fn partial(n: u8) -> u8 {
+    match n {
+        0..=9 => 1,
+        10..=99 => { 2 }
+    }
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
-        }
+        };
+        let args = ExecuteArgs::new(s);
+        let returns = |name: &str, value: i128| {
+            let ok = krate.execute(&mut Vec::new(), args.clone().with_returns(Some(value))).unwrap();
+            let found = ok.res.iter().filter_map(|res| match res {
+                SymexRes::Returns { fn_name, model, .. } if fn_name == name => Some(model.to_string()),
+                _ => None,
+            }).collect::<Vec<_>>();
+            (found, ok)
+        };
+
+        // 1 | 2 is a single path
+        let (found, ok) = returns("classify", 20);
+        assert_eq!(found.len(), 1);
+        assert!(found[0] == "n = 1" || found[0] == "n = 2");
+
+        // 5 is taken by 3..=9 first
+        let unreachable = ok.res.iter().filter_map(|res| match res {
+            SymexRes::UnreachableArm { span } => Some(span.into_string(s)),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert_eq!(unreachable, vec!["5 => 50".to_string()]);
+        assert_eq!(returns("classify", 50).0, Vec::<String>::new());
+        assert_eq!(returns("classify", 40).0.len(), 1);
+
+        // x - 1 cannot overflow as x is not 0 past the first arm
+        let (mut found, _) = returns("pair", 6);
+        found.sort();
+        assert_eq!(found, vec!["a = 0, b = 6".to_string(), "a = 7, b = 0".to_string()]);
+
+        let partial = ok.res.iter().filter_map(|res| match res {
+            SymexRes::MatchNotExhaustive { model, .. } => model.get("n").map(|n| n.parse::<u8>().unwrap()),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert_eq!(partial.len(), 1);
+        assert!(partial[0] >= 100);
+        assert!(ok.res.iter().all(|res| !matches!(res, SymexRes::Panic { .. })));
     }
 
     #[test]
-    fn test_stmt_fn() {
-        match parse_file(" fn hello() -> u8 {  (10 + 3) * 3 / 4 ^ (10); 10; 23; 0 } ") {
-            Okay(value, advance) => {
-                println!("{:?} {:?}", value, advance)
-            },
+    fn test_bools() {
+        let s = "
+fn flags(a: bool, b: bool, n: u8) -> u8 {
+    let both = a && b;
+    let one = a ^ b;
+    if both {
+        return 1;
+    }
+    if one & !a {
+        return 2;
+    }
+    if !(a | b) {
+        if n > 3 {
+            return 3;
+        }
+        return 4;
+    }
+    5
+}
+
+fn pick(x: i32) -> i32 {
+    let neg: bool = x < 0;
+    let big = x > 100;
+    if is_set(neg || big) {
+        return big as i32 + 10;
+    }
+    0
+}
+
+fn is_set(flag: bool) -> bool {
+    flag == true
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
-        }
+        };
+        let args = ExecuteArgs::new(s);
+        let returns = |name: &str, value: i128| {
+            let ok = krate.execute(&mut Vec::new(), args.clone().with_returns(Some(value))).unwrap();
+            ok.res.iter().filter_map(|res| match res {
+                SymexRes::Returns { fn_name, model, .. } if fn_name == name => Some(model.clone()),
+                _ => None,
+            }).collect::<Vec<_>>()
+        };
+        let flags = |value: i128| {
+            let found = returns("flags", value);
+            assert_eq!(found.len(), 1);
+            (found[0].get("a").unwrap().to_string(), found[0].get("b").unwrap().to_string())
+        };
+
+        assert_eq!(flags(1), ("true".to_string(), "true".to_string()));
+        assert_eq!(flags(2), ("false".to_string(), "true".to_string()));
+        assert_eq!(flags(5), ("true".to_string(), "false".to_string()));
+        let n = returns("flags", 3)[0].get("n").unwrap().parse::<u8>().unwrap();
+        assert!(n > 3);
+
+        let x = |value: i128| returns("pick", value)[0].get("x").unwrap().parse::<i32>().unwrap();
+        assert!(x(10) < 0);
+        assert!(x(11) > 100);
+        assert!((0..=100).contains(&x(0)));
+
+        // a bool function returns no int
+        assert!(returns("is_set", 1).is_empty());
     }
 
     #[test]
-    fn test_expr_fn() {
-        match parse_file(" fn hello() -> u8 {  (10 + 3) * 3 / 4 ^ (10) } ") {
-            Okay(value, advance) => {
-                println!("{:?} {:?}", value, advance)
-            },
+    fn test_arrays() {
+        let s = "
+fn get(a: [i32; 4], i: usize) -> i32 {
+    a[i]
+}
+
+fn sum(a: [u8; 3]) -> u8 {
+    let mut s: u8 = 0;
+    for i in 0..3 {
+        s += a[i];
+    }
+    s
+}
+
+fn write(i: usize, v: i32) -> i32 {
+    let mut b = [0; 3];
+    b[i] = v;
+    b[0] = 4;
+    return b[2];
+}
+
+fn fill(n: usize) -> u32 {
+    let mut c: [u32; 2] = [7; 2];
+    c[n] += 1;
+    c[0]
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
-        }
+        };
+        let args = ExecuteArgs::new(s);
+        let ok = krate.execute(&mut Vec::new(), args.clone()).unwrap();
+        let mut out_of_bounds = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Panic { kind: PanicKind::IndexOutOfBounds { len, index }, .. } => Some((*len, index.clone())),
+            _ => None,
+        }).collect::<Vec<_>>();
+        out_of_bounds.sort();
+        assert_eq!(out_of_bounds.len(), 3);
+        assert_eq!((out_of_bounds[0].0, out_of_bounds[1].0, out_of_bounds[2].0), (2, 3, 4));
+        assert!(out_of_bounds.iter().all(|(len, index)| str::parse::<usize>(index.as_ref().unwrap()).unwrap() >= *len));
+        assert!(ok.res.iter().any(|res| matches!(res, SymexRes::Panic { kind: PanicKind::Overflow(_), .. })));
+
+        let returns = |name: &str, value: i128| {
+            let ok = krate.execute(&mut Vec::new(), args.clone().with_returns(Some(value))).unwrap();
+            ok.res.iter().filter_map(|res| match res {
+                SymexRes::Returns { fn_name, model, .. } if fn_name == name => Some(model.clone()),
+                _ => None,
+            }).collect::<Vec<_>>()
+        };
+        let get = |model: &SymModel, name: &str| model.get(name).unwrap().parse::<i128>().unwrap();
+
+        let found = returns("get", 3);
+        let i = get(&found[0], "i");
+        assert!(i < 4);
+        assert_eq!(get(&found[0], &format!("a[{}]", i)), 3);
+
+        let found = returns("sum", 6);
+        assert_eq!(get(&found[0], "a[0]") + get(&found[0], "a[1]") + get(&found[0], "a[2]"), 6);
+
+        let found = returns("write", 5);
+        assert_eq!((get(&found[0], "i"), get(&found[0], "v")), (2, 5));
+        // the write to b[0] leaves b[2] alone
+        assert_eq!(get(&returns("write", 4)[0], "i"), 2);
+
+        assert_eq!(get(&returns("fill", 8)[0], "n"), 0);
+        assert_eq!(get(&returns("fill", 7)[0], "n"), 1);
     }
 
     #[test]
-    fn test_add_fn() {
-        match parse_file(" fn hello() -> u8 {10 + 3}") {
-            Okay(value, advance) => {
-                println!("{:?} {:?}", value, advance)
-            },
+    fn test_tuples() {
+        let s = "
+fn swap(p: (i32, u8)) -> i32 {
+    let (a, b) = p;
+    let q = (b as i32, a);
+    if q.1 > 10 {
+        return q.0;
+    }
+    -1
+}
+
+fn pair((x, y): (i32, i32), z: i32) -> i32 {
+    let mut t = (x + 1, (y, z));
+    t.0 = t.0 * 2;
+    t.1.1 += 3;
+    let (m, (n, o)) = t;
+    return m + n + o;
+}
+
+fn shadow(v: i32) -> i32 {
+    let t = (v, v, v);
+    let t = (1, 2);
+    let r = match t {
+        (1, w) => w + v,
+        _ => 0,
+    };
+    return r;
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
-        }
+        };
+        let args = ExecuteArgs::new(s);
+        let ok = krate.execute(&mut Vec::new(), args.clone()).unwrap();
+        // t is (1, 2) whatever v is
+        assert_eq!(ok.res.iter().filter(|res| matches!(res, SymexRes::UnreachableArm { .. })).count(), 1);
+
+        let returns = |name: &str, value: i128| {
+            let ok = krate.execute(&mut Vec::new(), args.clone().with_returns(Some(value))).unwrap();
+            ok.res.iter().filter_map(|res| match res {
+                SymexRes::Returns { fn_name, model, .. } if fn_name == name => Some(model.clone()),
+                _ => None,
+            }).collect::<Vec<_>>()
+        };
+        let get = |model: &SymModel, name: &str| model.get(name).unwrap().parse::<i128>().unwrap();
+
+        let found = returns("swap", 5);
+        assert_eq!(found.len(), 1);
+        assert!(get(&found[0], "p.0") > 10);
+        assert_eq!(get(&found[0], "p.1"), 5);
+
+        let found = returns("pair", 100);
+        let (x, y, z) = (get(&found[0], "x"), get(&found[0], "y"), get(&found[0], "z"));
+        assert_eq!((x + 1) * 2 + y + z + 3, 100);
+
+        assert_eq!(get(&returns("shadow", 5)[0], "v"), 3);
     }
 
     #[test]
-    fn test_empty_fn() {
-        match parse_file(" fn hello() -> u8 {0}") {
-            Okay(value, advance) => {
-                println!("{:?} {:?}", value, advance)
-            },
+    fn test_structs() {
+        let s = "
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Wrap(u8, bool);
+
+struct Line {
+    from: Point,
+    to: Point,
+    weights: [u8; 2],
+}
+
+fn shift(l: Line, k: usize) -> i32 {
+    let mut m = Line { to: Point { x: 1, y: 2 }, ..l };
+    m.from.x = m.to.y + 10;
+    m.to = Point { x: 5, y: m.from.y };
+    let w = m.weights[k];
+    return m.from.x + m.to.y + w as i32;
+}
+
+fn wrap(w: Wrap) -> u8 {
+    let v = Wrap(w.0 + 1, !w.1);
+    if v.1 {
+        return v.0;
+    }
+    0
+}
+
+fn make(x: i32) -> i32 {
+    let y = 3;
+    let p = Point { x, y };
+    let q = p;
+    return q.x + q.y;
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
-        }
+        };
+        let args = ExecuteArgs::new(s);
+        let ok = krate.execute(&mut Vec::new(), args.clone()).unwrap();
+        // m.weights[k] with k past the end
+        assert!(ok.res.iter().any(|res| matches!(res, SymexRes::Panic { .. } if res.to_string().contains("index out of bounds"))));
+
+        let returns = |name: &str, value: i128| {
+            let ok = krate.execute(&mut Vec::new(), args.clone().with_returns(Some(value))).unwrap();
+            ok.res.iter().filter_map(|res| match res {
+                SymexRes::Returns { fn_name, model, .. } if fn_name == name => Some(model.clone()),
+                _ => None,
+            }).collect::<Vec<_>>()
+        };
+        let get = |model: &SymModel, name: &str| model.get(name).unwrap().parse::<i128>().unwrap();
+
+        let found = returns("shift", 300);
+        assert_eq!(found.len(), 1);
+        let k = get(&found[0], "k");
+        let w = get(&found[0], &format!("l.weights[{}]", k));
+        assert_eq!(12 + get(&found[0], "l.from.y") + w, 300);
+
+        let found = returns("wrap", 5);
+        assert_eq!(get(&found[0], "w.0"), 4);
+        assert_eq!(found[0].get("w.1").unwrap(), "false");
+
+        assert_eq!(get(&returns("make", 5)[0], "x"), 2);
     }
 
     #[test]
-    fn test_empty() {
-        match parse_file("") {
-            Okay(value, advance) => {
-                println!("{}: {:?}", advance, value);
-            },
+    fn test_enums() {
+        let s = "
+enum Shape {
+    Circle(u8),
+    Rect { w: u8, h: u8 },
+    Empty,
+}
+
+fn area(s: Shape) -> u32 {
+    let a = match s {
+        Shape::Circle(r) => 3 * (r as u32),
+        Shape::Rect { w, h } => (w as u32) + (h as u32),
+        Shape::Empty => 0,
+    };
+    return a;
+}
+
+fn first(o: Option<u8>) -> u8 {
+    let v = o.unwrap();
+    return v;
+}
+
+fn either(r: Result<i32, bool>, d: i32) -> i32 {
+    if let Ok(v) = r {
+        return v + 1;
+    }
+    return d;
+}
+
+fn find(x: i32) -> Option<i32> {
+    if x > 0 {
+        return Some(x);
+    }
+    return None;
+}
+
+fn make(n: i32) -> i32 {
+    let mut o = None;
+    if n > 5 {
+        o = Some(n);
+    }
+    let r = match find(n) {
+        Some(_) => 1,
+        None => 0,
+    };
+    return o.unwrap_or(2) + r;
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
-        }
+        };
+        let args = ExecuteArgs::new(s);
+        let ok = krate.execute(&mut Vec::new(), args.clone()).unwrap();
+        // o.unwrap() with o = None, and every arm of the match on find(n)
+        // reachable
+        assert!(ok.res.iter().any(|res| matches!(res, SymexRes::Panic { .. } if res.to_string().contains("called `Option::unwrap()` on a `None` value"))));
+        assert!(!ok.res.iter().any(|res| matches!(res, SymexRes::MatchNotExhaustive { .. } | SymexRes::UnreachableArm { .. })));
+
+        let returns = |name: &str, value: i128| {
+            let ok = krate.execute(&mut Vec::new(), args.clone().with_returns(Some(value))).unwrap();
+            ok.res.iter().filter_map(|res| match res {
+                SymexRes::Returns { fn_name, model, .. } if fn_name == name => Some(model.clone()),
+                _ => None,
+            }).collect::<Vec<_>>()
+        };
+        let get = |model: &SymModel, name: &str| model.get(name).unwrap().parse::<i128>().unwrap();
+
+        // a circle of radius 5 (or a rectangle) has an area of 15, but
+        // nothing empty does
+        let found = returns("area", 15);
+        assert!(found.iter().any(|model| model.get("s") == Some("Circle") && get(model, "s.Circle.0") == 5));
+        assert!(found.iter().all(|model| model.get("s") != Some("Empty")));
+        assert!(returns("area", 0).iter().any(|model| model.get("s") == Some("Empty")));
+
+        let found = returns("first", 9);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].get("o"), Some("Some"));
+        assert_eq!(get(&found[0], "o.Some.0"), 9);
+
+        let found = returns("either", 8);
+        assert!(found.iter().any(|model| model.get("r") == Some("Ok") && get(model, "r.Ok.0") == 7));
+        assert!(found.iter().any(|model| model.get("r") == Some("Err") && get(model, "d") == 8));
+
+        assert!(returns("make", 3).iter().all(|model| get(model, "n") <= 5));
     }
 
-    fn print_res(res: Result<ExOk, ()>) {
+    fn print_res(res: Result<ExOk, ExErr>) {
         match res {
             Ok(ok) => {
                 for res in ok.res.iter() {
@@ -2281,7 +6863,7 @@ mod tests {
                 }
                 println!("Total Results: {}", ok.res.len());
             },
-            Err(_) => println!("error!"),
+            Err(err) => println!("error: {}", err),
         }
     }
 
@@ -2434,7 +7016,7 @@ mod tests {
                 //println!("{}: {:?}", advance, value);
                 let mut engine = Vec::new();
                 //println!("{:?}", value);
-                print_res(value.execute(&mut engine, ExecuteArgs { store: test, ids: HashSet::from([0]), max_loop_iter: 10 }));
+                print_res(value.execute(&mut engine, ExecuteArgs::new(test).with_max_loop_iter(10)));
             },
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
@@ -2460,13 +7042,218 @@ fn b(mut x:i32, mut y:i32) -> i32 {
         match parse_file(s) {
             Okay(value, _) => {
                 let mut engine = Vec::new();
-                print_res(value.execute(&mut engine, ExecuteArgs { store: s, ids: HashSet::from([0]), max_loop_iter: 100 }))
+                print_res(value.execute(&mut engine, ExecuteArgs::new(s)))
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+
+    #[test]
+    fn test_returns() {
+        let s = "
+fn f(x: i32) -> i32 {
+    if x > 10 {
+        return 0;
+    }
+    x - 3
+}
+";
+        match parse_file(s) {
+            Okay(value, _) => {
+                let mut engine = Vec::new();
+                let res = value.execute(&mut engine, ExecuteArgs::new(s).with_returns(Some(0))).unwrap();
+
+                // the return value of each path is in terms of the parameters
+                let rets: Vec<Option<String>> = res.res.iter().filter_map(|r| match r {
+                    SymexRes::Path { ret, .. } => Some(ret.clone()),
+                    _ => None,
+                }).collect();
+                assert_eq!(rets.len(), 2);
+                assert!(rets.contains(&Some("0".to_string())));
+
+                // both the early return and the tail expression can return 0
+                let models: Vec<_> = res.res.iter().filter_map(|r| match r {
+                    SymexRes::Returns { model, .. } => Some(model.get("x").unwrap().parse::<i32>().unwrap()),
+                    _ => None,
+                }).collect();
+                assert_eq!(models.len(), 2);
+                assert!(models.contains(&3));
+                assert!(models.iter().any(|x| *x > 10));
+                print_res(Ok(res));
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+
+        // 300 is no u8, so it must not match the 44 it wraps to
+        let s = "
+fn g(x: u8) -> u8 {
+    x
+}
+";
+        match parse_file(s) {
+            Okay(value, _) => {
+                let mut engine = Vec::new();
+                let res = value.execute(&mut engine, ExecuteArgs::new(s).with_returns(Some(300))).unwrap();
+                assert!(!res.res.iter().any(|r| matches!(r, SymexRes::Returns { .. })));
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+
+        // an if ending the body returns the value of whichever block ran
+        let s = "
+fn h(x: i32) -> i32 {
+    if x > 10 {
+        x - 10
+    } else {
+        let y = x * 2;
+        y + 1
+    }
+}
+";
+        match parse_file(s) {
+            Okay(value, _) => {
+                let mut engine = Vec::new();
+                let res = value.execute(&mut engine, ExecuteArgs::new(s).with_returns(Some(5))).unwrap();
+                let rets: Vec<Option<String>> = res.res.iter().filter_map(|r| match r {
+                    SymexRes::Path { ret, .. } => Some(ret.clone()),
+                    _ => None,
+                }).collect();
+                assert_eq!(rets.len(), 2);
+                assert!(rets.iter().all(Option::is_some));
+
+                let mut models: Vec<_> = res.res.iter().filter_map(|r| match r {
+                    SymexRes::Returns { model, .. } => Some(model.get("x").unwrap().parse::<i32>().unwrap()),
+                    _ => None,
+                }).collect();
+                models.sort();
+                assert_eq!(models, vec![2, 15]);
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_call() {
+        let s = "
+fn half(a: i32) -> i32 {
+    if a < 0 {
+        return 0;
+    }
+    a / 2
+}
+
+fn b(x: i32) -> i32 {
+    half(x) + 1
+}
+";
+        match parse_file(s) {
+            Okay(value, _) => {
+                let mut engine = Vec::new();
+                let res = value.execute(&mut engine, ExecuteArgs::new(s)).unwrap();
+
+                // both paths through `half` come back to `b`
+                let b_paths = engine.iter().filter(|e| e.fn_name == "b").count();
+                assert_eq!(b_paths, 2);
+                assert!(engine.iter().filter(|e| e.fn_name == "b").all(|e| e.ret.is_some()));
+                print_res(Ok(res));
             },
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
         }
     }
 
+    #[test]
+    fn test_call_in_if_condition() {
+        let s = "
+fn sign(a: i32) -> i32 {
+    if a > 0 {
+        return 1;
+    }
+    if a < 0 {
+        return -1;
+    }
+    0
+}
+
+fn f(x: i32) -> i32 {
+    if sign(x) == 1 {
+        //symex reach
+        return 10 / (x - 5);
+    }
+    //symex assert x <= 0
+    x
+}
+";
+        let krate = match parse_file(s) {
+            Okay(value, _) => value,
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        };
+        let RItem::Fn { val: f, .. } = &krate.items[1] else { panic!("Expected a fn") };
+        let mut engine = Vec::new();
+        let ok = f.execute(&mut engine, ExecuteArgs::new(s).with_fns(Rc::new(krate.fn_map(s)))).unwrap();
+
+        // each path back from `sign` takes the arm or goes past it on its own
+        let queries: Vec<&QueryOutcome> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Query { outcome, .. } => Some(outcome),
+            _ => None,
+        }).collect();
+        assert!(queries.iter().any(|outcome| matches!(outcome, QueryOutcome::Reached(_))));
+        assert!(queries.iter().all(|outcome| matches!(outcome, QueryOutcome::Reached(_) | QueryOutcome::Proved)), "{:?}", queries);
+        let div_by_zero: Vec<&SymModel> = ok.res.iter().filter_map(|res| match res {
+            SymexRes::Panic { kind: PanicKind::DivByZero, model, .. } => Some(model),
+            _ => None,
+        }).collect();
+        assert_eq!(div_by_zero.len(), 1);
+        assert_eq!(div_by_zero[0].get("x"), Some("5"));
+    }
+
+    #[test]
+    fn test_call_summary() {
+        let s = "
+fn half(a: i32) -> i32 {
+    if a < 0 {
+        return 0;
+    }
+    100 / a
+}
+
+fn b(x: i32) -> i32 {
+    half(x) + half(x + 1)
+}
+";
+        match parse_file(s) {
+            Okay(value, _) => {
+                let summaries = SummaryCache::default();
+                let mut engine = Vec::new();
+                let res = value.execute(&mut engine, ExecuteArgs::new(s).with_summaries(Some(summaries.clone()))).unwrap();
+
+                // `half` is summarised once and both calls instantiate it
+                let half = summaries.borrow().get("half").cloned().flatten().unwrap();
+                assert_eq!(half.paths.len(), 2);
+                assert_eq!(half.panics.len(), 1);
+
+                // the division by zero is reached through both calls in `b`,
+                // for x = 0 and x = -1, besides from `half` run on its own
+                let div_by_zero: Vec<Option<&str>> = res.res.iter().filter_map(|r| match r {
+                    SymexRes::Panic { kind: PanicKind::DivByZero, model, .. } => Some(model.get("x")),
+                    _ => None,
+                }).collect();
+                let mut from_b: Vec<&str> = div_by_zero.iter().copied().flatten().collect();
+                from_b.sort();
+                assert_eq!(from_b, vec!["-1", "0"]);
+                assert_eq!(div_by_zero.len(), 3);
+                print_res(Ok(res));
+            },
+            Error(error) => panic!("Error: {}", error),
+            Panic(error) => panic!("Panic: {}", error),
+        }
+    }
 
     #[test]
     fn test_small_while_loop() {
@@ -2488,7 +7275,7 @@ fn b(mut x:i32, mut y:i32) -> i32 {
         match parse_file(s) {
             Okay(value, _) => {
                 let mut engine = Vec::new();
-                print_res(value.execute(&mut engine, ExecuteArgs { store: s, ids: HashSet::from([0]), max_loop_iter: 100 }))
+                print_res(value.execute(&mut engine, ExecuteArgs::new(s)))
             },
             Error(error) => panic!("Error: {}", error),
             Panic(error) => panic!("Panic: {}", error),
@@ -0,0 +1,1001 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use rsmt2::SmtRes;
+
+use crate::symex::SymSolver;
+
+/// The most boxes a single check splits before giving up on it.
+static MAX_BOXES: usize = 200_000;
+
+///
+/// A small in-process stand-in for an SMT solver, deciding the bit-vector
+/// terms the engine asserts without an external binary.
+///
+/// Each check searches the box of possible values of the constants, splitting
+/// it in two while some assertion can be neither proved nor refuted over it.
+/// Additions, subtractions and multiplications by constants are tracked
+/// exactly as linear forms, so linear conditions over bounded ints are
+/// decided after a few dozen splits; anything else is bounded by intervals
+/// and, at worst, decided once the box is down to single values. A check that
+/// runs out of splits (or time) is reported as unknown.
+///
+pub struct BvSolver {
+    sorts: HashMap<String, Sort>,
+    asserts: Vec<Term>,
+    /// How many assertions there were at each `push`.
+    scopes: Vec<usize>,
+    model: Option<HashMap<String, Value>>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    Bool,
+    Bv(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Bool(bool),
+    Bv(u128, u32),
+    Var(String),
+    /// `(op args..)`, or `((_ op indices..) args..)` for indexed operators.
+    App { op: String, indices: Vec<u32>, args: Vec<Term> },
+}
+
+/// A concrete value: a bit-vector (in its low `w` bits) or a boolean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Bool(bool),
+    Bv(u128, u32),
+}
+
+///
+/// What is known about a term over a box. A bit-vector is described by some
+/// integer whose low `w` bits are its value, which addition, subtraction,
+/// negation and multiplication preserve without wrapping; `range` bounds that
+/// integer and `lin` gives it exactly as a linear form, when known.
+///
+#[derive(Debug, Clone)]
+enum Abs {
+    Bool(Option<bool>),
+    Bv { w: u32, lin: Option<Linear>, range: Option<(i128, i128)> },
+}
+
+/// `c + sum(coeff * var)` over the integers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Linear {
+    coeffs: BTreeMap<String, i128>,
+    c: i128,
+}
+
+/// The values each constant can still take, as integers (`0..=1` for
+/// booleans, `-2^(w-1)..=2^(w-1)-1` for bit-vectors).
+type Boxes = HashMap<String, (i128, i128)>;
+
+/// A linear form, if known, and the range of the integer it describes.
+type View = (Option<Linear>, (i128, i128));
+
+/// What abstract evaluation works with and finds out over one box.
+struct Scope<'a> {
+    /// The box, along with the atoms met so far and the comparisons assumed
+    /// true (`1..=1`) or false (`0..=0`).
+    boxes: Boxes,
+    sorts: &'a HashMap<String, Sort>,
+    /// Whether any comparison is assumed.
+    assuming: bool,
+    /// Whether an assumed comparison decided the assertion being evaluated.
+    relied: bool,
+    /// The comparisons that are neither decided nor assumed, once per use.
+    undecided: Vec<String>,
+    /// Whether some comparison is decided against its assumption, which
+    /// leaves nothing in the box.
+    conflict: bool,
+}
+
+enum Outcome {
+    Sat(HashMap<String, Value>),
+    Unsat,
+    Unknown,
+}
+
+impl BvSolver {
+    pub fn new(timeout: Option<Duration>) -> Self {
+        BvSolver { sorts: HashMap::new(), asserts: Vec::new(), scopes: Vec::new(), model: None, timeout }
+    }
+
+    /// Searches `boxes` for values that satisfy every assertion.
+    fn search(&self, boxes: Boxes, budget: &mut usize, deadline: Option<Instant>) -> Outcome {
+        if *budget == 0 || deadline.is_some_and(|d| Instant::now() > d) {
+            return Outcome::Unknown;
+        }
+        *budget -= 1;
+
+        // the values nearest zero often work already
+        let point = self.point(&boxes);
+        if self.asserts.iter().all(|a| eval(a, &point) == Ok(Value::Bool(true))) {
+            return Outcome::Sat(point);
+        }
+
+        // once something is assumed, the assertions it decides may still
+        // need the constants narrowed down to agree with it
+        let assuming = boxes.len() > self.sorts.len();
+        let mut open: HashMap<String, usize> = HashMap::new();
+        let mut scope = Scope { boxes: boxes.clone(), sorts: &self.sorts, assuming, relied: false, undecided: Vec::new(), conflict: false };
+        for assert in self.asserts.iter() {
+            scope.relied = false;
+            match abs_eval(assert, &mut scope) {
+                Ok(Abs::Bool(Some(false))) => return Outcome::Unsat,
+                Ok(Abs::Bool(Some(true))) if !scope.relied => {},
+                Ok(_) => {
+                    let mut names = Vec::new();
+                    vars(assert, &mut names);
+                    names.sort();
+                    names.dedup();
+                    for name in names {
+                        *open.entry(name).or_default() += 1;
+                    }
+                },
+                Err(_) => return Outcome::Unknown,
+            }
+        }
+        if scope.conflict {
+            return Outcome::Unsat;
+        }
+
+        // a comparison several assertions hinge on is cheaper to decide
+        // either way than to narrow down the constants it compares
+        let mut undecided = scope.undecided;
+        undecided.sort();
+        if let Some(pair) = undecided.windows(2).find(|pair| pair[0] == pair[1]) {
+            let mut unknown = false;
+            for b in [1, 0] {
+                let mut boxes = boxes.clone();
+                boxes.insert(pair[0].clone(), (b, b));
+                match self.search(boxes, budget, deadline) {
+                    Outcome::Sat(model) => return Outcome::Sat(model),
+                    Outcome::Unsat => {},
+                    Outcome::Unknown => unknown = true,
+                }
+            }
+            return if unknown { Outcome::Unknown } else { Outcome::Unsat };
+        }
+
+        // split the constant the most undecided assertions use, and the
+        // widest of those; splitting a constant only one of them compares
+        // against the others leaves them all as undecided as before
+        let split = open.iter()
+            .filter_map(|(name, uses)| boxes.get(name).map(|range| (name, *uses, *range)))
+            .filter(|(_, _, (lo, hi))| lo < hi)
+            .max_by_key(|(name, uses, (lo, hi))| (*uses, hi.wrapping_sub(*lo) as u128, *name));
+        let Some((name, _, (lo, hi))) = split else {
+            return Outcome::Unknown;
+        };
+
+        let halves = if lo < 0 && hi >= 0 {
+            [(0, hi), (lo, -1)]
+        } else {
+            let mid = lo.wrapping_add((hi.wrapping_sub(lo) as u128 / 2) as i128);
+            if lo >= 0 { [(lo, mid), (mid + 1, hi)] } else { [(mid + 1, hi), (lo, mid)] }
+        };
+
+        let mut unknown = false;
+        for half in halves {
+            let mut boxes = boxes.clone();
+            boxes.insert(name.clone(), half);
+            match self.search(boxes, budget, deadline) {
+                Outcome::Sat(model) => return Outcome::Sat(model),
+                Outcome::Unsat => {},
+                Outcome::Unknown => unknown = true,
+            }
+        }
+        if unknown { Outcome::Unknown } else { Outcome::Unsat }
+    }
+
+    /// The values in `boxes` nearest zero.
+    fn point(&self, boxes: &Boxes) -> HashMap<String, Value> {
+        let mut point = HashMap::new();
+        for (name, sort) in self.sorts.iter() {
+            let (lo, hi) = boxes[name];
+            let v = if lo > 0 { lo } else if hi < 0 { hi } else { 0 };
+            point.insert(name.clone(), match sort {
+                Sort::Bool => Value::Bool(v == 1),
+                Sort::Bv(w) => Value::Bv(v as u128 & mask(*w), *w),
+            });
+        }
+        point
+    }
+}
+
+impl SymSolver for BvSolver {
+    fn declare_const(&mut self, name: &str, sort: &str) -> SmtRes<()> {
+        let sort = parse_sort(sort).ok_or_else(|| format!("unsupported sort {}", sort))?;
+        self.sorts.insert(name.to_string(), sort);
+        Ok(())
+    }
+
+    fn push(&mut self, n: u8) -> SmtRes<()> {
+        for _ in 0..n {
+            self.scopes.push(self.asserts.len());
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self, n: u8) -> SmtRes<()> {
+        for _ in 0..n {
+            let len = self.scopes.pop().ok_or("pop without a matching push")?;
+            self.asserts.truncate(len);
+        }
+        Ok(())
+    }
+
+    fn assert(&mut self, term: &str) -> SmtRes<()> {
+        conjuncts(fold(parse_term(term)?), &mut self.asserts);
+        Ok(())
+    }
+
+    fn check_sat_or_unk(&mut self) -> SmtRes<Option<bool>> {
+        let mut boxes = Boxes::new();
+        for (name, sort) in self.sorts.iter() {
+            boxes.insert(name.clone(), match sort {
+                Sort::Bool => (0, 1),
+                Sort::Bv(w) if *w <= 127 => (-(1 << (w - 1)), (1 << (w - 1)) - 1),
+                Sort::Bv(_) => (i128::MIN, i128::MAX),
+            });
+        }
+
+        let mut budget = MAX_BOXES;
+        let deadline = self.timeout.map(|t| Instant::now() + t);
+        self.model = None;
+        Ok(match self.search(boxes, &mut budget, deadline) {
+            Outcome::Sat(model) => {
+                self.model = Some(model);
+                Some(true)
+            },
+            Outcome::Unsat => Some(false),
+            Outcome::Unknown => None,
+        })
+    }
+
+    fn get_values(&mut self, terms: &[String]) -> SmtRes<Vec<(String, String)>> {
+        let model = self.model.as_ref().ok_or("no model to read values from")?;
+        let mut values = Vec::new();
+        for term in terms {
+            let value = match eval(&parse_term(term)?, model)? {
+                Value::Bool(b) => b.to_string(),
+                Value::Bv(v, w) => format!("(_ bv{} {})", v, w),
+            };
+            values.push((term.clone(), value));
+        }
+        Ok(values)
+    }
+}
+
+fn parse_sort(sort: &str) -> Option<Sort> {
+    if sort == "Bool" {
+        return Some(Sort::Bool);
+    }
+    let width = sort.strip_prefix("(_ BitVec ")?.strip_suffix(')')?;
+    str::parse(width.trim()).ok().filter(|w| *w > 0 && *w <= 128).map(Sort::Bv)
+}
+
+/// Splits SMT-LIB text into parentheses and atoms, keeping `|quoted|`
+/// symbols whole.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut atom = String::new();
+    let mut quoted = false;
+    for ch in text.chars() {
+        if ch == '|' {
+            quoted = !quoted;
+            atom.push(ch);
+        } else if !quoted && (ch == '(' || ch == ')' || ch.is_whitespace()) {
+            if !atom.is_empty() {
+                tokens.push(std::mem::take(&mut atom));
+            }
+            if !ch.is_whitespace() {
+                tokens.push(ch.to_string());
+            }
+        } else {
+            atom.push(ch);
+        }
+    }
+    if !atom.is_empty() {
+        tokens.push(atom);
+    }
+    tokens
+}
+
+fn parse_term(text: &str) -> Result<Term, String> {
+    let tokens = tokenize(text);
+    let mut pos = 0;
+    let term = parse_tokens(&tokens, &mut pos)?;
+    if pos != tokens.len() {
+        return Err(format!("unexpected {} in {}", tokens[pos], text));
+    }
+    Ok(term)
+}
+
+fn parse_tokens(tokens: &[String], pos: &mut usize) -> Result<Term, String> {
+    let token = tokens.get(*pos).ok_or("unexpected end of term")?;
+    *pos += 1;
+    if token != "(" {
+        return Ok(parse_atom(token));
+    }
+
+    // `(_ bvN W)` literals and `((_ op i..) args..)` indexed operators
+    let (op, indices) = if tokens.get(*pos).map(String::as_str) == Some("_") {
+        let (name, indices) = parse_indexed(tokens, pos)?;
+        if let Some(value) = name.strip_prefix("bv") {
+            let value = str::parse(value).map_err(|_| format!("bad literal {}", name))?;
+            return Ok(Term::Bv(value, *indices.first().ok_or("literal without a width")?));
+        }
+        (name, indices)
+    } else if tokens.get(*pos).map(String::as_str) == Some("(") {
+        *pos += 1;
+        if tokens.get(*pos).map(String::as_str) != Some("_") {
+            return Err("only indexed operators can be applied".to_string());
+        }
+        parse_indexed(tokens, pos)?
+    } else {
+        let op = tokens.get(*pos).ok_or("unexpected end of term")?.clone();
+        *pos += 1;
+        (op, Vec::new())
+    };
+
+    let mut args = Vec::new();
+    while tokens.get(*pos).map(String::as_str) != Some(")") {
+        args.push(parse_tokens(tokens, pos)?);
+    }
+    *pos += 1;
+    Ok(Term::App { op, indices, args })
+}
+
+/// Parses `_ name i..)` after an opening parenthesis.
+fn parse_indexed(tokens: &[String], pos: &mut usize) -> Result<(String, Vec<u32>), String> {
+    let name = tokens.get(*pos + 1).ok_or("unexpected end of term")?.clone();
+    *pos += 2;
+    let mut indices = Vec::new();
+    while let Some(token) = tokens.get(*pos).filter(|t| *t != ")") {
+        indices.push(str::parse(token).map_err(|_| format!("bad index {}", token))?);
+        *pos += 1;
+    }
+    *pos += 1;
+    Ok((name, indices))
+}
+
+fn parse_atom(token: &str) -> Term {
+    match token {
+        "true" => Term::Bool(true),
+        "false" => Term::Bool(false),
+        _ => {
+            let lit = token.strip_prefix("#x").map(|d| (d, 16, 4)).or_else(|| token.strip_prefix("#b").map(|d| (d, 2, 1)));
+            match lit.and_then(|(digits, radix, bits)| Some((u128::from_str_radix(digits, radix).ok()?, digits.len() as u32 * bits))) {
+                Some((value, w)) => Term::Bv(value, w),
+                None => Term::Var(token.to_string()),
+            }
+        },
+    }
+}
+
+/// Evaluates the subterms of `term` that do not use any constant.
+fn fold(term: Term) -> Term {
+    let Term::App { op, indices, args } = term else {
+        return term;
+    };
+    let args: Vec<Term> = args.into_iter().map(fold).collect();
+    let values = args.iter().map(|arg| match arg {
+        Term::Bool(b) => Some(Value::Bool(*b)),
+        Term::Bv(v, w) => Some(Value::Bv(v & mask(*w), *w)),
+        _ => None,
+    }).collect::<Option<Vec<_>>>();
+    match values.and_then(|values| apply(&op, &indices, &values).ok()) {
+        Some(Value::Bool(b)) => Term::Bool(b),
+        Some(Value::Bv(v, w)) => Term::Bv(v, w),
+        None => normalize(op, indices, args),
+    }
+}
+
+///
+/// Writes every ordering comparison as `bvslt` or `bvult`, negated if need
+/// be, and drops double negations, so that a comparison and its negation are
+/// the same atom.
+///
+fn normalize(op: String, indices: Vec<u32>, mut args: Vec<Term>) -> Term {
+    let app = |op: &str, args: Vec<Term>| Term::App { op: op.to_string(), indices: Vec::new(), args };
+    match op.as_str() {
+        "bvsgt" | "bvsge" | "bvsle" | "bvugt" | "bvuge" | "bvule" if args.len() == 2 => {
+            let lt = format!("{}lt", &op[..3]);
+            let (b, a) = (args.pop().unwrap_or(Term::Bool(false)), args.pop().unwrap_or(Term::Bool(false)));
+            match &op[3..] {
+                "gt" => app(&lt, vec![b, a]),
+                "ge" => app("not", vec![app(&lt, vec![a, b])]),
+                _ => app("not", vec![app(&lt, vec![b, a])]),
+            }
+        },
+        "not" => match &mut args[..] {
+            [Term::App { op: inner, args: inner_args, .. }] if inner == "not" && inner_args.len() == 1 => {
+                inner_args.pop().unwrap_or(Term::Bool(false))
+            },
+            _ => Term::App { op, indices, args },
+        },
+        _ => Term::App { op, indices, args },
+    }
+}
+
+///
+/// Adds each conjunct of `term` to `out` as an assertion of its own, so that
+/// the search can tell which of them are still undecided.
+///
+fn conjuncts(term: Term, out: &mut Vec<Term>) {
+    match term {
+        Term::App { op, args, .. } if op == "and" => args.into_iter().for_each(|arg| conjuncts(arg, out)),
+        term => out.push(term),
+    }
+}
+
+/// Adds the constants `term` uses to `out`.
+fn vars(term: &Term, out: &mut Vec<String>) {
+    match term {
+        Term::Var(name) => out.push(name.clone()),
+        Term::App { args, .. } => args.iter().for_each(|arg| vars(arg, out)),
+        _ => {},
+    }
+}
+
+fn mask(w: u32) -> u128 {
+    if w >= 128 { u128::MAX } else { (1 << w) - 1 }
+}
+
+/// Reads the low `w` bits of `v` as a signed int.
+fn signed(v: u128, w: u32) -> i128 {
+    ((v << (128 - w)) as i128) >> (128 - w)
+}
+
+/// Evaluates `term` with the constants set to `model`.
+fn eval(term: &Term, model: &HashMap<String, Value>) -> Result<Value, String> {
+    match term {
+        Term::Bool(b) => Ok(Value::Bool(*b)),
+        Term::Bv(v, w) => Ok(Value::Bv(v & mask(*w), *w)),
+        Term::Var(name) => model.get(name).copied().ok_or_else(|| format!("unknown constant {}", name)),
+        Term::App { op, indices, args } => {
+            let args = args.iter().map(|arg| eval(arg, model)).collect::<Result<Vec<_>, _>>()?;
+            apply(op, indices, &args)
+        },
+    }
+}
+
+/// Applies the operator `op` to concrete arguments.
+fn apply(op: &str, indices: &[u32], args: &[Value]) -> Result<Value, String> {
+    let bools = || args.iter().map(|arg| match arg {
+        Value::Bool(b) => Ok(*b),
+        _ => Err(format!("{} expects booleans", op)),
+    }).collect::<Result<Vec<bool>, String>>();
+    let bvs = || args.iter().map(|arg| match arg {
+        Value::Bv(v, w) => Ok((*v, *w)),
+        _ => Err(format!("{} expects bit-vectors", op)),
+    }).collect::<Result<Vec<(u128, u32)>, String>>();
+
+    let value = match op {
+        "not" => Value::Bool(!*bools()?.first().ok_or("not expects an argument")?),
+        "and" => Value::Bool(bools()?.iter().all(|b| *b)),
+        "or" => Value::Bool(bools()?.iter().any(|b| *b)),
+        "xor" => Value::Bool(bools()?.iter().fold(false, |a, b| a ^ b)),
+        "=>" => {
+            let b = bools()?;
+            Value::Bool(!b[0] || b[1])
+        },
+        "=" => Value::Bool(args.windows(2).all(|pair| pair[0] == pair[1])),
+        "distinct" => Value::Bool(args.len() == 2 && args[0] != args[1]),
+        "ite" => match args {
+            [Value::Bool(c), a, b] => if *c { *a } else { *b },
+            _ => return Err("ite expects a condition and two values".to_string()),
+        },
+        _ => {
+            let bvs = bvs()?;
+            let (a, w) = *bvs.first().ok_or_else(|| format!("{} expects an argument", op))?;
+            let b = bvs.get(1).map(|(b, _)| *b).unwrap_or(0);
+            let m = mask(w);
+            let (sa, sb) = (signed(a, w), signed(b, w));
+            let bv = |v: u128| Value::Bv(v & m, w);
+            match op {
+                "bvadd" => bv(bvs.iter().fold(0u128, |acc, (v, _)| acc.wrapping_add(*v))),
+                "bvmul" => bv(bvs.iter().fold(1u128, |acc, (v, _)| acc.wrapping_mul(*v))),
+                "bvsub" => bv(a.wrapping_sub(b)),
+                "bvneg" => bv(a.wrapping_neg()),
+                "bvnot" => bv(!a),
+                "bvand" => bv(a & b),
+                "bvor" => bv(a | b),
+                "bvxor" => bv(a ^ b),
+                "bvshl" => bv(if b >= w as u128 { 0 } else { a << b }),
+                "bvlshr" => bv(if b >= w as u128 { 0 } else { a >> b }),
+                "bvashr" => bv(if b >= w as u128 { (sa >> 127) as u128 } else { (sa >> b) as u128 }),
+                "bvudiv" => bv(a.checked_div(b).unwrap_or(m)),
+                "bvurem" => bv(if b == 0 { a } else { a % b }),
+                "bvsdiv" => {
+                    let (ua, ub) = (sa.unsigned_abs() & m, sb.unsigned_abs() & m);
+                    let q = ua.checked_div(ub).unwrap_or(m);
+                    bv(if (sa < 0) != (sb < 0) { q.wrapping_neg() } else { q })
+                },
+                "bvsrem" => {
+                    let (ua, ub) = (sa.unsigned_abs() & m, sb.unsigned_abs() & m);
+                    let r = if ub == 0 { ua } else { ua % ub };
+                    bv(if sa < 0 { r.wrapping_neg() } else { r })
+                },
+                "bvult" => Value::Bool(a < b),
+                "bvule" => Value::Bool(a <= b),
+                "bvugt" => Value::Bool(a > b),
+                "bvuge" => Value::Bool(a >= b),
+                "bvslt" => Value::Bool(sa < sb),
+                "bvsle" => Value::Bool(sa <= sb),
+                "bvsgt" => Value::Bool(sa > sb),
+                "bvsge" => Value::Bool(sa >= sb),
+                "zero_extend" | "sign_extend" => {
+                    let to = w + indices.first().ok_or("extension without a width")?;
+                    if to > 128 {
+                        return Err(format!("{}-bit values are not supported", to));
+                    }
+                    let v = if op == "sign_extend" { sa as u128 } else { a };
+                    Value::Bv(v & mask(to), to)
+                },
+                "extract" => match indices {
+                    [hi, lo] if hi >= lo && *hi < w => Value::Bv((a >> lo) & mask(hi - lo + 1), hi - lo + 1),
+                    _ => return Err("bad extract".to_string()),
+                },
+                _ => return Err(format!("unsupported operator {}", op)),
+            }
+        },
+    };
+    Ok(value)
+}
+
+impl Linear {
+    fn constant(c: i128) -> Self {
+        Linear { coeffs: BTreeMap::new(), c }
+    }
+
+    fn var(name: &str) -> Self {
+        Linear { coeffs: BTreeMap::from([(name.to_string(), 1)]), c: 0 }
+    }
+
+    fn add(&self, other: &Linear, sign: i128) -> Option<Linear> {
+        let mut sum = self.clone();
+        sum.c = sum.c.checked_add(other.c.checked_mul(sign)?)?;
+        for (name, coeff) in other.coeffs.iter() {
+            let entry = sum.coeffs.entry(name.clone()).or_insert(0);
+            *entry = entry.checked_add(coeff.checked_mul(sign)?)?;
+        }
+        sum.coeffs.retain(|_, coeff| *coeff != 0);
+        Some(sum)
+    }
+
+    fn scale(&self, k: i128) -> Option<Linear> {
+        let mut scaled = Linear::constant(self.c.checked_mul(k)?);
+        for (name, coeff) in self.coeffs.iter() {
+            scaled.coeffs.insert(name.clone(), coeff.checked_mul(k)?);
+        }
+        scaled.coeffs.retain(|_, coeff| *coeff != 0);
+        Some(scaled)
+    }
+
+    /// The least and greatest value of this form over `boxes`.
+    fn range(&self, boxes: &Boxes) -> Option<(i128, i128)> {
+        let (mut lo, mut hi) = (self.c, self.c);
+        for (name, coeff) in self.coeffs.iter() {
+            let (a, b) = *boxes.get(name)?;
+            let (a, b) = (a.checked_mul(*coeff)?, b.checked_mul(*coeff)?);
+            lo = lo.checked_add(a.min(b))?;
+            hi = hi.checked_add(a.max(b))?;
+        }
+        Some((lo, hi))
+    }
+}
+
+impl Abs {
+    fn bv(w: u32, lin: Option<Linear>, range: Option<(i128, i128)>, boxes: &Boxes) -> Abs {
+        match lin.as_ref().and_then(|lin| lin.range(boxes)) {
+            // both bound the same integer
+            Some((lo, hi)) => {
+                let range = range.map_or((lo, hi), |(a, b)| (a.max(lo), b.min(hi)));
+                Abs::Bv { w, lin, range: Some(range) }
+            },
+            None => Abs::Bv { w, lin: None, range },
+        }
+    }
+
+    /// The concrete value, if there is only one.
+    fn value(&self) -> Option<Value> {
+        match self {
+            Abs::Bool(b) => b.map(Value::Bool),
+            Abs::Bv { w, range: Some((lo, hi)), .. } if lo == hi => Some(Value::Bv(*lo as u128 & mask(*w), *w)),
+            _ => None,
+        }
+    }
+
+    fn from_value(value: Value) -> Abs {
+        match value {
+            Value::Bool(b) => Abs::Bool(Some(b)),
+            Value::Bv(v, w) => {
+                let v = signed(v, w);
+                Abs::Bv { w, lin: Some(Linear::constant(v)), range: Some((v, v)) }
+            },
+        }
+    }
+
+    fn tri(&self) -> Option<bool> {
+        match self {
+            Abs::Bool(b) => *b,
+            _ => None,
+        }
+    }
+
+    fn width(&self) -> Option<u32> {
+        match self {
+            Abs::Bv { w, .. } => Some(*w),
+            _ => None,
+        }
+    }
+
+    fn lin(&self) -> Option<&Linear> {
+        match self {
+            Abs::Bv { lin, .. } => lin.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn range(&self) -> Option<(i128, i128)> {
+        match self {
+            Abs::Bv { range, .. } => *range,
+            _ => None,
+        }
+    }
+
+    ///
+    /// Rewrites this bit-vector so that its integer is its value read as
+    /// signed (or unsigned), which is only possible when every value in its
+    /// range reads the same way once wrapped. Returns the shifted linear form
+    /// (if known) and range.
+    ///
+    fn view(&self, signed: bool) -> Option<View> {
+        let (w, (lo, hi)) = (self.width()?, self.range()?);
+        let shift = if w >= 127 {
+            // only the identity fits in an i128
+            let fits = if signed { w == 128 || (lo >= -(1 << 125) * 2 && hi < (1 << 126)) } else { lo >= 0 };
+            if !fits {
+                return None;
+            }
+            0
+        } else {
+            let m = 1i128 << w;
+            let offset = if signed { 1i128 << (w - 1) } else { 0 };
+            let k = (lo + offset).div_euclid(m);
+            if (hi + offset).div_euclid(m) != k {
+                return None;
+            }
+            k * m
+        };
+        let lin = match self.lin() {
+            Some(lin) => Some(lin.add(&Linear::constant(shift), -1)?),
+            None => None,
+        };
+        Some((lin, (lo - shift, hi - shift)))
+    }
+}
+
+/// Whether some multiple of `2^w` lies in `lo..=hi`.
+fn has_multiple(lo: i128, hi: i128, w: u32) -> bool {
+    if w >= 127 {
+        return lo <= 0 && 0 <= hi || lo == i128::MIN;
+    }
+    let m = 1i128 << w;
+    let first = lo.div_euclid(m) + if lo.rem_euclid(m) == 0 { 0 } else { 1 };
+    first.checked_mul(m).is_some_and(|first| first <= hi)
+}
+
+/// `a - b` as a linear form or, failing that, as a range.
+fn difference(a: Option<View>, b: Option<View>, boxes: &Boxes) -> Option<View> {
+    let ((a_lin, (al, ah)), (b_lin, (bl, bh))) = (a?, b?);
+    let d = a_lin.zip(b_lin).and_then(|(a, b)| a.add(&b, -1));
+    match d.as_ref().and_then(|d| d.range(boxes)) {
+        Some(range) => Some((d, range)),
+        None => Some((None, (al.checked_sub(bh)?, ah.checked_sub(bl)?))),
+    }
+}
+
+/// Writes `term` back out as SMT-LIB, which names it as an atom.
+fn write_term(term: &Term, out: &mut String) {
+    match term {
+        Term::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Term::Bv(v, w) => out.push_str(&format!("(_ bv{} {})", v, w)),
+        Term::Var(name) => out.push_str(name),
+        Term::App { op, indices, args } => {
+            out.push('(');
+            if indices.is_empty() {
+                out.push_str(op);
+            } else {
+                out.push_str(&format!("(_ {}", op));
+                indices.iter().for_each(|i| out.push_str(&format!(" {}", i)));
+                out.push(')');
+            }
+            for arg in args {
+                out.push(' ');
+                write_term(arg, out);
+            }
+            out.push(')');
+        },
+    }
+}
+
+///
+/// Evaluates `term` over every value in `scope.boxes` at once. A bit-vector
+/// that is not linear in the constants is added to the boxes as an atom of
+/// its own, so that the same subterm cancels out wherever it is compared.
+///
+fn abs_eval(term: &Term, scope: &mut Scope) -> Result<Abs, String> {
+    let comparison = matches!(term, Term::App { op, .. }
+        if matches!(op.as_str(), "=" | "distinct" | "bvult" | "bvule" | "bvugt" | "bvuge" | "bvslt" | "bvsle" | "bvsgt" | "bvsge"));
+
+    let abs = abs_eval_op(term, scope)?;
+    match abs {
+        // only undecided or assumed comparisons need naming
+        Abs::Bool(b) if comparison && (b.is_none() || scope.assuming) => {
+            let mut atom = String::from("#");
+            write_term(term, &mut atom);
+            let assumed = scope.boxes.get(&atom).filter(|(lo, hi)| lo == hi).map(|(b, _)| *b == 1);
+            match (b, assumed) {
+                (Some(b), Some(assumed)) if b != assumed => scope.conflict = true,
+                (None, None) => scope.undecided.push(atom),
+                (None, Some(_)) => scope.relied = true,
+                _ => {},
+            }
+            Ok(Abs::Bool(b.or(assumed)))
+        },
+        Abs::Bv { w, lin: None, range } if w <= 127 && matches!(term, Term::App { .. }) => {
+            let range = range.unwrap_or((-(1 << (w - 1)), (1 << (w - 1)) - 1));
+            let mut atom = String::from("#");
+            write_term(term, &mut atom);
+            let lin = Linear::var(&atom);
+            scope.boxes.insert(atom, range);
+            Ok(Abs::Bv { w, lin: Some(lin), range: Some(range) })
+        },
+        abs => Ok(abs),
+    }
+}
+
+fn abs_eval_op(term: &Term, scope: &mut Scope) -> Result<Abs, String> {
+    let (op, indices, args) = match term {
+        Term::Bool(b) => return Ok(Abs::Bool(Some(*b))),
+        Term::Bv(v, w) => return Ok(Abs::from_value(Value::Bv(v & mask(*w), *w))),
+        Term::Var(name) => {
+            let (lo, hi) = *scope.boxes.get(name).ok_or_else(|| format!("unknown constant {}", name))?;
+            return Ok(match scope.sorts.get(name) {
+                Some(Sort::Bool) => Abs::Bool((lo == hi).then_some(lo == 1)),
+                Some(Sort::Bv(w)) => Abs::Bv { w: *w, lin: Some(Linear::var(name)), range: Some((lo, hi)) },
+                None => return Err(format!("unknown constant {}", name)),
+            });
+        },
+        Term::App { op, indices, args } => (op.as_str(), indices, args),
+    };
+
+    let args = args.iter().map(|arg| abs_eval(arg, scope)).collect::<Result<Vec<_>, _>>()?;
+    let boxes = &scope.boxes;
+    if let Some(values) = args.iter().map(Abs::value).collect::<Option<Vec<_>>>() {
+        return apply(op, indices, &values).map(Abs::from_value);
+    }
+
+    let tri = |b: Option<bool>| Ok(Abs::Bool(b));
+    let w = args.first().and_then(Abs::width).unwrap_or(1);
+    let unknown = Abs::Bv { w, lin: None, range: None };
+    match op {
+        "not" => tri(args[0].tri().map(|b| !b)),
+        "and" => {
+            let bs: Vec<Option<bool>> = args.iter().map(Abs::tri).collect();
+            tri(if bs.contains(&Some(false)) { Some(false) } else if bs.iter().all(|b| *b == Some(true)) { Some(true) } else { None })
+        },
+        "or" => {
+            let bs: Vec<Option<bool>> = args.iter().map(Abs::tri).collect();
+            tri(if bs.contains(&Some(true)) { Some(true) } else if bs.iter().all(|b| *b == Some(false)) { Some(false) } else { None })
+        },
+        "=>" => tri(match (args[0].tri(), args[1].tri()) {
+            (Some(false), _) | (_, Some(true)) => Some(true),
+            (Some(true), Some(false)) => Some(false),
+            _ => None,
+        }),
+        "xor" => tri(None),
+        "=" | "distinct" if args.len() == 2 => {
+            let eq = match (&args[0], &args[1]) {
+                (Abs::Bool(a), Abs::Bool(b)) => a.zip(*b).map(|(a, b)| a == b),
+                (a, b) => {
+                    let a_view = a.range().map(|range| (a.lin().cloned(), range));
+                    let b_view = b.range().map(|range| (b.lin().cloned(), range));
+                    match difference(a_view, b_view, boxes) {
+                        // `c + sum(k * x)` is never a multiple of 2^w when
+                        // the largest power of two dividing every k does not
+                        // divide c
+                        Some((Some(d), _)) if !d.coeffs.is_empty() && {
+                            let twos = d.coeffs.values().map(|k| k.trailing_zeros()).min().unwrap_or(0).min(w);
+                            twos < 127 && d.c.rem_euclid(1 << twos) != 0
+                        } => Some(false),
+                        Some((_, (lo, hi))) if !has_multiple(lo, hi, w) => Some(false),
+                        Some((_, (lo, hi))) if lo == hi => Some(true),
+                        _ => None,
+                    }
+                },
+            };
+            tri(if op == "=" { eq } else { eq.map(|b| !b) })
+        },
+        "ite" => Ok(match args[0].tri() {
+            Some(true) => args[1].clone(),
+            Some(false) => args[2].clone(),
+            None => match (&args[1], &args[2]) {
+                (Abs::Bool(a), Abs::Bool(b)) => Abs::Bool(if a == b { *a } else { None }),
+                (a, b) => {
+                    let range = a.range().zip(b.range()).map(|((al, ah), (bl, bh))| (al.min(bl), ah.max(bh)));
+                    Abs::Bv { w: a.width().unwrap_or(w), lin: None, range }
+                },
+            },
+        }),
+        "bvadd" | "bvsub" => {
+            let sign = if op == "bvsub" { -1 } else { 1 };
+            let mut lin = args[0].lin().cloned();
+            let mut range = args[0].range();
+            for arg in args[1..].iter() {
+                lin = lin.zip(arg.lin()).and_then(|(a, b)| a.add(b, sign));
+                range = range.zip(arg.range()).and_then(|((al, ah), (bl, bh))| {
+                    if sign == 1 {
+                        Some((al.checked_add(bl)?, ah.checked_add(bh)?))
+                    } else {
+                        Some((al.checked_sub(bh)?, ah.checked_sub(bl)?))
+                    }
+                });
+            }
+            Ok(Abs::bv(w, lin, range, boxes))
+        },
+        "bvneg" | "bvnot" => {
+            // !x is -x - 1
+            let k = if op == "bvnot" { -1 } else { 0 };
+            let lin = args[0].lin().and_then(|lin| lin.scale(-1)?.add(&Linear::constant(k), 1));
+            let range = args[0].range().and_then(|(lo, hi)| Some((hi.checked_neg()? + k, lo.checked_neg()? + k)));
+            Ok(Abs::bv(w, lin, range, boxes))
+        },
+        "bvmul" if args.len() == 2 => {
+            let (a, b) = (&args[0], &args[1]);
+            let constant = |x: &Abs| x.range().filter(|(lo, hi)| lo == hi).map(|(lo, _)| lo);
+            let lin = match (constant(a), constant(b)) {
+                (Some(k), _) => b.lin().and_then(|lin| lin.scale(k)),
+                (_, Some(k)) => a.lin().and_then(|lin| lin.scale(k)),
+                _ => None,
+            };
+            let range = a.range().zip(b.range()).and_then(|((al, ah), (bl, bh))| {
+                let corners = [al.checked_mul(bl)?, al.checked_mul(bh)?, ah.checked_mul(bl)?, ah.checked_mul(bh)?];
+                Some((*corners.iter().min()?, *corners.iter().max()?))
+            });
+            Ok(Abs::bv(w, lin, range, boxes))
+        },
+        "bvshl" => match args[1].value() {
+            Some(Value::Bv(k, _)) if k < w as u128 && k < 126 => {
+                let k = 1i128 << k;
+                let lin = args[0].lin().and_then(|lin| lin.scale(k));
+                let range = args[0].range().and_then(|(lo, hi)| Some((lo.checked_mul(k)?, hi.checked_mul(k)?)));
+                Ok(Abs::bv(w, lin, range, boxes))
+            },
+            _ => Ok(unknown),
+        },
+        "bvult" | "bvule" | "bvugt" | "bvuge" | "bvslt" | "bvsle" | "bvsgt" | "bvsge" => {
+            let signed = op.starts_with("bvs");
+            let d = difference(args[0].view(signed), args[1].view(signed), boxes);
+            tri(d.and_then(|(_, (lo, hi))| match &op[3..] {
+                "lt" => if hi < 0 { Some(true) } else if lo >= 0 { Some(false) } else { None },
+                "le" => if hi <= 0 { Some(true) } else if lo > 0 { Some(false) } else { None },
+                "gt" => if lo > 0 { Some(true) } else if hi <= 0 { Some(false) } else { None },
+                _ => if lo >= 0 { Some(true) } else if hi < 0 { Some(false) } else { None },
+            }))
+        },
+        "sign_extend" | "zero_extend" => {
+            let to = w + indices.first().copied().unwrap_or(0);
+            let signed = op == "sign_extend";
+            match args[0].view(signed) {
+                Some((lin, range)) => Ok(Abs::bv(to, lin, Some(range), boxes)),
+                // every value of the narrower type still fits
+                None if w <= 126 => {
+                    let range = if signed { (-(1 << (w - 1)), (1 << (w - 1)) - 1) } else { (0, (1 << w) - 1) };
+                    Ok(Abs::Bv { w: to, lin: None, range: Some(range) })
+                },
+                None => Ok(Abs::Bv { w: to, lin: None, range: None }),
+            }
+        },
+        // the low bits of the integer are the low bits of the value
+        "extract" if indices.get(1) == Some(&0) => {
+            let to = indices[0] + 1;
+            Ok(Abs::bv(to, args[0].lin().cloned(), args[0].range(), boxes))
+        },
+        "bvudiv" | "bvurem" | "bvand" | "bvlshr" => {
+            let (a, b) = (args[0].view(false), args[1].view(false));
+            let range = a.zip(b).and_then(|((_, (al, ah)), (_, (bl, bh)))| match op {
+                "bvudiv" if bl > 0 => Some((al / bh, ah / bl)),
+                "bvurem" if bl > 0 => Some((0, ah.min(bh - 1))),
+                "bvand" => Some((0, ah.min(bh))),
+                "bvlshr" if bl == bh && bl < 127 => Some((al >> bl, ah >> bl)),
+                _ => None,
+            });
+            Ok(Abs::Bv { w, lin: None, range })
+        },
+        "bvsdiv" | "bvsrem" | "bvashr" => {
+            let (a, b) = (args[0].view(true), args[1].view(true));
+            let range = a.zip(b).and_then(|((_, (al, ah)), (_, (bl, bh)))| {
+                let most = al.checked_abs()?.max(ah.checked_abs()?);
+                match op {
+                    "bvsdiv" if bl > 0 || bh < 0 => Some((-most, most)),
+                    "bvsrem" if bl > 0 || bh < 0 => {
+                        let most = most.min(bl.checked_abs()?.max(bh.checked_abs()?) - 1);
+                        Some((if al >= 0 { 0 } else { -most }, if ah <= 0 { 0 } else { most }))
+                    },
+                    "bvashr" if bl == bh && (0..127).contains(&bl) => Some((al >> bl, ah >> bl)),
+                    _ => None,
+                }
+            });
+            Ok(Abs::Bv { w, lin: None, range })
+        },
+        _ => match args.first() {
+            Some(Abs::Bool(_)) => tri(None),
+            _ => Ok(unknown),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symex::SymSolver;
+
+    use super::BvSolver;
+
+    fn check(decls: &[(&str, &str)], asserts: &[&str]) -> Option<bool> {
+        let mut s = BvSolver::new(None);
+        for (name, sort) in decls {
+            s.declare_const(name, sort).unwrap();
+        }
+        for assert in asserts {
+            s.assert(assert).unwrap();
+        }
+        s.check_sat_or_unk().unwrap()
+    }
+
+    #[test]
+    fn test_bv_solver() {
+        let x = [("x", "(_ BitVec 32)")];
+        let xy = [("x", "(_ BitVec 32)"), ("y", "(_ BitVec 32)")];
+
+        assert_eq!(check(&x, &["(bvsgt x (_ bv10 32))", "(bvslt x (_ bv20 32))"]), Some(true));
+        assert_eq!(check(&x, &["(bvsgt x (_ bv10 32))", "(bvslt x (_ bv5 32))"]), Some(false));
+        // 2y + 1 is odd, so never -1 + 1
+        assert_eq!(check(&xy, &["(= (bvmul (_ bv2 32) y) (_ bv4294967295 32))"]), Some(false));
+        // x + 4 overflows only for the last four values of x
+        let overflow = "(not (= ((_ sign_extend 32) (bvadd x (_ bv4 32))) (bvadd ((_ sign_extend 32) x) ((_ sign_extend 32) (_ bv4 32)))))";
+        assert_eq!(check(&x, &[overflow]), Some(true));
+        assert_eq!(check(&x, &[overflow, "(bvslt x (_ bv2147483644 32))"]), Some(false));
+        // b_if_stmt's else branch: y + 4 is neither <= 4 nor > 4
+        assert_eq!(check(&xy, &["(not (bvsle (bvadd y (_ bv4 32)) (_ bv4 32)))", "(not (bvsgt (bvadd y (_ bv4 32)) (_ bv4 32)))"]), Some(false));
+        // a comparison and its negation are one atom, however they are written
+        assert_eq!(check(&xy, &["(not (bvslt (bvadd x y) y))", "(not (bvsge (bvadd x y) y))"]), Some(false));
+        // x + 3 is below y, so x + 4 does not overflow, which takes narrowing
+        // x down rather than y, even once x + 3 < y is assumed
+        let xy64 = [("x", "(_ BitVec 64)"), ("y", "(_ BitVec 64)")];
+        let below = "(bvslt (bvadd x (_ bv3 64)) y)";
+        let overflow = "(not (= ((_ sign_extend 64) (bvadd (bvadd x (_ bv3 64)) (_ bv1 64))) (bvadd ((_ sign_extend 64) (bvadd x (_ bv3 64))) ((_ sign_extend 64) (_ bv1 64)))))";
+        let and = format!("(and (bvsge x (_ bv0 64)) {} {})", below, overflow);
+        assert_eq!(check(&xy64, &[&and]), Some(false));
+        assert_eq!(check(&xy64, &["(bvslt (_ bv3 64) y)", below, &and]), Some(false));
+
+        let mut s = BvSolver::new(None);
+        s.declare_const("x", "(_ BitVec 8)").unwrap();
+        s.assert("(= (bvmul x (_ bv2 8)) (_ bv6 8))").unwrap();
+        s.push(1).unwrap();
+        s.assert("(bvslt x (_ bv0 8))").unwrap();
+        assert_eq!(s.check_sat_or_unk().unwrap(), Some(true));
+        assert_eq!(s.get_values(&["x".to_string()]).unwrap(), vec![("x".to_string(), "(_ bv131 8)".to_string())]);
+        s.pop(1).unwrap();
+        assert_eq!(s.check_sat_or_unk().unwrap(), Some(true));
+        assert_eq!(s.get_values(&["(bvadd x (_ bv1 8))".to_string()]).unwrap()[0].1, "(_ bv4 8)");
+    }
+}
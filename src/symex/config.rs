@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rsmt2::{SmtConf, Logic};
+use thiserror::Error;
+
+use crate::symex::{SymSolver, BvSolver};
+
+/// The environment variable naming the solver binary to run when no path is
+/// given on the command line.
+pub static SOLVER_ENV: &str = "SYMEX_SOLVER";
+
+/// Where the solver was expected before it could be configured, relative to
+/// the working directory.
+static BUNDLED_Z3: &str = "z3\\bin\\z3.exe";
+
+///
+/// The SMT solvers that can be run as the backend: solver processes run
+/// through `rsmt2`, or the built-in `BvSolver`.
+///
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SolverKind {
+    Z3,
+    Cvc5,
+    Yices,
+    Builtin,
+}
+
+impl SolverKind {
+    /// Reads a solver name as given to `--solver`.
+    pub fn from_name(name: &str) -> Option<SolverKind> {
+        match name.to_ascii_lowercase().as_str() {
+            "z3" => Some(SolverKind::Z3),
+            "cvc5" => Some(SolverKind::Cvc5),
+            "yices" | "yices2" | "yices-smt2" => Some(SolverKind::Yices),
+            "builtin" => Some(SolverKind::Builtin),
+            _ => None,
+        }
+    }
+
+    /// Guesses the solver a binary runs from its file name.
+    pub fn from_path(path: &str) -> Option<SolverKind> {
+        let stem = Path::new(&path.replace('\\', "/")).file_stem()?.to_str()?.to_string();
+        SolverKind::from_name(&stem)
+    }
+
+    /// The name of the solver's binary.
+    pub fn binary(&self) -> &'static str {
+        match self {
+            SolverKind::Z3 => "z3",
+            SolverKind::Cvc5 => "cvc5",
+            SolverKind::Yices => "yices-smt2",
+            SolverKind::Builtin => "builtin",
+        }
+    }
+}
+
+///
+/// A solver process could not be started, or failed on an assertion.
+///
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SolverError {
+    #[error("{} not found: pass --solver-path, set {} or put it on the PATH", .0.binary(), SOLVER_ENV)]
+    NotFound(SolverKind),
+    #[error("could not start the solver `{path}`: {reason}")]
+    Spawn { path: String, reason: String },
+    #[error("the solver failed on `{assert}`: {reason}")]
+    Assert { assert: String, reason: String },
+}
+
+///
+/// Which solver to run and how. Unless a path is given, the binary is taken
+/// from `SYMEX_SOLVER` or else looked up on the `PATH`. If no solver is
+/// chosen and none is found, the built-in one is used.
+///
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SolverConfig {
+    /// The solver to run, or `None` to pick whichever is found.
+    pub kind: Option<SolverKind>,
+    pub path: Option<String>,
+    /// How long a single `check-sat` may take, in milliseconds. A path whose
+    /// condition times out is kept, and a check that times out is reported
+    /// as unknown.
+    pub timeout_ms: Option<u64>,
+}
+
+impl SolverConfig {
+    pub fn new() -> Self {
+        SolverConfig::default()
+    }
+
+    pub fn with_kind(mut self, kind: Option<SolverKind>) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_path(mut self, path: Option<String>) -> Self {
+        self.path = path;
+        self
+    }
+
+    pub fn with_timeout_ms(mut self, timeout_ms: Option<u64>) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Returns the solver to run and the binary to run it from (empty for
+    /// the built-in solver).
+    pub fn resolve(&self) -> Result<(SolverKind, String), SolverError> {
+        if self.kind == Some(SolverKind::Builtin) {
+            return Ok((SolverKind::Builtin, String::new()));
+        }
+
+        let path = self.path.clone().or_else(|| std::env::var(SOLVER_ENV).ok().filter(|p| !p.is_empty()));
+        if let Some(path) = path {
+            let kind = self.kind.or_else(|| SolverKind::from_path(&path)).unwrap_or(SolverKind::Z3);
+            return Ok((kind, path));
+        }
+
+        let kinds = match self.kind {
+            Some(kind) => vec![kind],
+            None => vec![SolverKind::Z3, SolverKind::Cvc5, SolverKind::Yices],
+        };
+        for kind in kinds.iter().copied() {
+            if let Some(path) = find_on_path(kind.binary()) {
+                return Ok((kind, path.to_string_lossy().into_owned()));
+            }
+            if kind == SolverKind::Z3 && Path::new(BUNDLED_Z3).is_file() {
+                return Ok((kind, BUNDLED_Z3.to_string()));
+            }
+        }
+
+        match self.kind {
+            Some(kind) => Err(SolverError::NotFound(kind)),
+            None => Ok((SolverKind::Builtin, String::new())),
+        }
+    }
+
+    /// Starts the solver.
+    pub fn spawn(&self) -> Result<Box<dyn SymSolver>, SolverError> {
+        let (kind, conf) = match self.smt_conf()? {
+            Some(conf) => conf,
+            None => return Ok(Box::new(BvSolver::new(self.timeout_ms.map(Duration::from_millis)))),
+        };
+        let path = conf.get_cmd().to_string();
+        let spawn_err = |err: rsmt2::errors::Error| SolverError::Spawn { path: path.clone(), reason: err.to_string() };
+
+        let mut s = conf.spawn(()).map_err(spawn_err)?;
+        // yices needs a logic before anything is declared
+        if kind == SolverKind::Yices {
+            s.set_logic(Logic::QF_BV).map_err(spawn_err)?;
+        }
+        Ok(Box::new(s))
+    }
+
+    /// Returns the `rsmt2` configuration for the solver, set up for the
+    /// incremental queries and models the engine relies on, or `None` for
+    /// the built-in solver.
+    pub fn smt_conf(&self) -> Result<Option<(SolverKind, SmtConf)>, SolverError> {
+        let (kind, path) = self.resolve()?;
+        let mut conf = match kind {
+            SolverKind::Z3 => SmtConf::z3(path),
+            SolverKind::Cvc5 => SmtConf::cvc4(path),
+            SolverKind::Yices => SmtConf::yices_2(path),
+            SolverKind::Builtin => return Ok(None),
+        };
+        conf.models();
+        conf.incremental();
+
+        if let Some(ms) = self.timeout_ms {
+            match kind {
+                SolverKind::Z3 => conf.option(format!("-t:{}", ms)),
+                SolverKind::Cvc5 => conf.option(format!("--tlimit-per={}", ms)),
+                // yices only takes whole seconds
+                SolverKind::Yices => conf.option(format!("--timeout={}", ms.div_ceil(1000))),
+                SolverKind::Builtin => &mut conf,
+            };
+        }
+
+        Ok(Some((kind, conf)))
+    }
+}
+
+/// Looks `binary` up in the directories of the `PATH`.
+fn find_on_path(binary: &str) -> Option<PathBuf> {
+    let dirs = std::env::var_os("PATH")?;
+    std::env::split_paths(&dirs)
+        .flat_map(|dir| [dir.join(binary), dir.join(format!("{}.exe", binary))])
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symex::{SolverConfig, SolverKind};
+
+    #[test]
+    fn test_solver_config() {
+        assert_eq!(SolverKind::from_name("CVC5"), Some(SolverKind::Cvc5));
+        assert_eq!(SolverKind::from_path("/usr/bin/yices-smt2"), Some(SolverKind::Yices));
+        assert_eq!(SolverKind::from_path("z3\\bin\\z3.exe"), Some(SolverKind::Z3));
+        assert_eq!(SolverKind::from_path("/opt/solver"), None);
+
+        let config = SolverConfig::new().with_path(Some("/opt/cvc5/bin/cvc5".to_string()));
+        assert_eq!(config.resolve().unwrap(), (SolverKind::Cvc5, "/opt/cvc5/bin/cvc5".to_string()));
+
+        let config = SolverConfig::new().with_kind(Some(SolverKind::Yices)).with_path(Some("/opt/solver".to_string()));
+        assert_eq!(config.resolve().unwrap().0, SolverKind::Yices);
+
+        let config = SolverConfig::new().with_kind(Some(SolverKind::Builtin)).with_path(Some("/opt/solver".to_string()));
+        assert_eq!(config.resolve().unwrap(), (SolverKind::Builtin, String::new()));
+        assert!(config.smt_conf().unwrap().is_none());
+
+        let (_, conf) = SolverConfig::new()
+            .with_path(Some("z3".to_string()))
+            .with_timeout_ms(Some(500))
+            .smt_conf()
+            .unwrap()
+            .unwrap();
+        assert!(conf.get_options().iter().any(|o| o == "-t:500"));
+    }
+}
@@ -1,38 +1,155 @@
-use equation_solver::*;
-use crate::symex::{SymVar, SymSolver};
+use crate::parser::Span;
+use crate::parser::parser::{BinOp, PPos, PanicKind};
+use crate::symex::{SymVar, PathCondition, SymModel, SymTerm, IntTy, SummaryPanic, CheckResult, SolverError, local_decls};
 
 pub struct SymExEngine {
     pub fn_name: String,
-    pub pi: SymSolver,
+    pub pi: PathCondition,
     pub sigma: Vec<SymVar>,
+    /// The parameters of the function, whose values make up a `SymModel`.
+    pub params: Vec<(String, IntTy)>,
     pub path: u32,
     pub reached_symex: bool,
+    /// Whether this path was cut off by the loop iteration limit rather than
+    /// leaving the loop, so what it does afterwards is not what the program
+    /// does.
+    pub truncated: bool,
+    /// The type the function being executed returns, if it returns an int.
+    pub ret_ty: Option<IntTy>,
+    /// Whether this path has returned from the function being executed.
+    pub returned: bool,
+    /// The value this path returned, in terms of the parameters.
+    pub ret: Option<SymTerm>,
+    /// The panics found on this path (and not on the path it was cloned
+    /// from), for summarising the function.
+    pub panics: Vec<SummaryPanic>,
+    /// How many branches this path took.
+    pub depth: u32,
+    /// The last branch this path took, by position and arm.
+    pub branch: Option<(usize, usize)>,
+    /// Whether the budget of the run ran out before this path did, leaving
+    /// the rest of it unexplored.
+    pub suspended: bool,
+    /// The `break` or `continue` this path is leaving the body of a loop by,
+    /// skipping the rest of it until the loop it is meant for takes it.
+    pub exit: Option<LoopExit>,
+    /// The tag cell of every enum parameter and the names of its variants,
+    /// for printing the variant a `SymModel` picks.
+    pub variants: Vec<(String, Vec<String>)>,
 }
 
-pub fn eval(stmt_rs: String) -> String {
-    let stmt_clean = stmt_rs.replace(";", "");
-    //println!("eval {}", stmt_clean);
-    let n = Equation::new(stmt_clean.clone());
+/// How a path leaves the body of a loop early.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoopExit {
+    /// `break`, out of the loop labelled `label` (or the innermost one),
+    /// with the value (in terms of the parameters) and type of
+    /// `break value`.
+    Break { label: Option<String>, value: Option<(SymTerm, IntTy)> },
+    /// `continue`, with the next iteration of the loop labelled `label` (or
+    /// the innermost one).
+    Continue { label: Option<String> },
+}
+
+impl LoopExit {
+    /// Whether this exit is meant for the loop labelled `label`, being the
+    /// innermost loop it is in.
+    pub fn targets(&self, label: Option<&str>) -> bool {
+        match self {
+            LoopExit::Break { label: to, .. } | LoopExit::Continue { label: to } => to.is_none() || to.as_deref() == label,
+        }
+    }
+}
 
-    let e = match n {
-            Ok(_) => {
-    let eq = n.unwrap();
+/// The state of a caller put aside while a call it made executes.
+#[derive(Clone)]
+pub struct CallFrame {
+    fn_name: String,
+    sigma: Vec<SymVar>,
+    ret_ty: Option<IntTy>,
+    returned: bool,
+    ret: Option<SymTerm>,
+}
 
-    let wrap_result = eq.evaluate();
-    
-    let eval = match wrap_result {
-        Ok(_) => wrap_result.unwrap().to_string(), // f64
-        Err(_) => stmt_clean.clone(), // EquationError
-    };
-        return eval;
+/// Replaces every whole symbol in the SMT-LIB term `stmt` for which `value`
+/// returns a replacement. Quoted symbols (`|..|`) are matched whole.
+pub fn substitute_lisp(stmt: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut symbol = String::new();
+    let mut quoted = false;
+    for ch in stmt.chars().chain(std::iter::once(' ')) {
+        if ch == '|' {
+            quoted = !quoted;
+            symbol.push(ch);
+        } else if !quoted && (ch == '(' || ch == ')' || ch.is_whitespace()) {
+            if !symbol.is_empty() {
+                match value(&symbol) {
+                    Some(v) => out.push_str(&v),
+                    None => out.push_str(&symbol),
+                }
+                symbol.clear();
+            }
+            out.push(ch);
+        } else {
+            symbol.push(ch);
+        }
     }
-    Err(_) => {return stmt_clean.clone();}
-    };
+    out.pop();
+    out
+}
 
+/// The variable (and, for parameters, the constant) holding item `i` of
+/// the array `name`: `|name[i]|`.
+pub fn array_cell(name: &str, i: usize) -> String {
+    format!("|{}[{}]|", name, i)
+}
 
+/// The items of the array variable `name` in `sigma` in order, or nothing
+/// if there is no such array.
+pub fn array_cells<'a>(sigma: &'a [SymVar], name: &str) -> Vec<&'a SymVar> {
+    (0..).map_while(|i| {
+        let cell = array_cell(name, i);
+        sigma.iter().find(|v| v.name == cell)
+    }).collect()
 }
 
+/// The variable (and, for parameters, the constant) holding item `i` of
+/// the tuple `name`: `|name.i|`. The items of a tuple in a tuple are named
+/// after the path to them (`|name.1.0|`).
+pub fn tuple_cell(name: &str, i: usize) -> String {
+    field_cell(name, &i.to_string())
+}
 
+/// The variable holding the field `field` of the struct `name`:
+/// `|name.field|`, named like the items of a tuple.
+pub fn field_cell(name: &str, field: &str) -> String {
+    format!("|{}.{}|", name, field)
+}
+
+/// 
+/// The variable (and, for parameters, the constant) holding the tag of the
+/// enum variable `name` of the enum `enum_name`: `|name:enum_name|`. The
+/// fields of its variants are held like those of a struct named after the
+/// variant (`|name.Some.0|`).
+/// 
+pub fn tag_cell(name: &str, enum_name: &str) -> String {
+    format!("|{}:{}|", name, enum_name)
+}
+
+/// The tag cell of the enum variable `name` in `sigma` and the name of its
+/// enum, if there is such an enum.
+pub fn tag_of<'a>(sigma: &'a [SymVar], name: &str) -> Option<(&'a SymVar, &'a str)> {
+    let prefix = format!("|{}:", name);
+    sigma.iter().find_map(|v| {
+        let enum_name = v.name.strip_prefix(&prefix)?.strip_suffix('|')?;
+        Some((v, enum_name)).filter(|_| !enum_name.contains(['.', '[', ':']))
+    })
+}
+
+/// The index of the item of the array `name` that the variable `cell` holds,
+/// if it holds one.
+fn array_index(cell: &str, name: &str) -> Option<usize> {
+    cell.strip_prefix('|')?.strip_prefix(name)?.strip_prefix('[')?.strip_suffix("]|")?.parse().ok()
+}
 
 impl SymExEngine {
     pub fn to_string(&self) -> String {
@@ -50,108 +167,277 @@ impl SymExEngine {
     pub fn new_variable(&mut self, var_name: String, var_type: String) {
         let v = SymVar::new(var_name.clone(), var_type.clone());
         //println!("created {} of type {}", var_name.clone(), var_type.clone());
+        self.pi.add_int(var_name.clone(), v.ty);
+        self.params.push((var_name, v.ty));
         self.sigma.push(v);
-        self.pi.add_int(var_name.clone());
     }
 
     //creates symvar from initialization
     //ie let var_name: var_type = assign;
-    pub fn new_variable_assign(&mut self, var_name: String, var_type: String, assign: String, l_assign: String) {
-        let a = assign.replace(";", "");
-        let stmt = self.display_as_var0(a);
-        let v = SymVar::new_assign(var_name.clone(), var_type.clone(), eval(stmt.clone()), l_assign);
-        //println!("created {} of type {} with value {}", var_name.clone(), var_type.clone(), assign.clone());
-        self.sigma.push(v);
-        self.pi.add_int(var_name.clone())
+    //locals are always replaced by their value, so unlike params they are
+    //not declared in the solver. A local shadowing another takes its place.
+    pub fn new_variable_assign(&mut self, var_name: String, var_type: String, assign: SymTerm) {
+        let v = SymVar::new_assign(var_name, var_type, self.term_as_var0(&assign));
+        match self.sigma.iter_mut().find(|old| old.name == v.name) {
+            Some(old) => *old = v,
+            None => self.sigma.push(v),
+        }
     }
 
-    pub fn display_as_var0(&mut self, st: String) -> String {
-        let mut stmt = st.replace(";", "");
-        let mut i = 0;
-        while i < self.sigma.len() {
-            let f = &self.sigma[i].name.eq(&self.sigma[i].var0);
-            if stmt.contains(&self.sigma[i].name) && !f {
-                let s = format!("({})", self.sigma[i].var0);
-                stmt = stmt.replace(&self.sigma[i].name, &s);
-            }
-            else if *f {
-                let s = format!("{}", self.sigma[i].var0);
-                //println!("{}", stmt);
-                stmt = stmt.replace(&self.sigma[i].name, &s);
-            }
-            i = i + 1;
+    /// Declares the parameter `name` as an array of `len` items of type
+    /// `ty`, each a parameter of its own named by `array_cell`.
+    pub fn new_array(&mut self, name: &str, ty: IntTy, len: usize) {
+        for i in 0..len {
+            self.new_variable(array_cell(name, i), ty.to_string());
         }
-        
-        return stmt;
     }
 
-    pub fn lisp_as_var0(&mut self, mut stmt: String) -> String {
-        let mut i = 0;
-        while i < self.sigma.len() {
-            let f = &self.sigma[i].name.eq(&self.sigma[i].var0);
-            if stmt.contains(&self.sigma[i].name) && !f {
-                let s = format!("{}", self.sigma[i].lisp_var0);
-                stmt = stmt.replace(&self.sigma[i].name, &s);
-            }
-            else if *f {
-                let s = format!("{}", self.sigma[i].lisp_var0);
-                
-                stmt = stmt.replace(&self.sigma[i].name, &s);
+    /// 
+    /// Declares the parameter `name` as a value of the enum `enum_name` with
+    /// the variants `variants`: its tag cell (`tag_cell`), which is assumed
+    /// to be the index of one of them. The fields of the variants are
+    /// declared separately.
+    /// 
+    pub fn new_enum(&mut self, name: &str, enum_name: &str, variants: Vec<String>) -> Result<(), SolverError> {
+        let tag = tag_cell(name, enum_name);
+        self.new_variable(tag.clone(), IntTy::TAG.to_string());
+        let count = SymTerm::Int(variants.len() as i128, IntTy::TAG);
+        self.variants.push((tag.clone(), variants));
+        self.new_assertion(&SymTerm::bin(BinOp::LessThan, false, SymTerm::Var(tag), count))
+    }
+
+    /// Binds the array variable `name` to `items` (in terms of the current
+    /// variables), replacing any array or cells of the same name.
+    pub fn new_array_assign(&mut self, name: &str, ty: IntTy, items: Vec<SymTerm>) {
+        let items: Vec<SymTerm> = items.iter().map(|item| self.term_as_var0(item)).collect();
+        let len = items.len();
+        self.sigma.retain(|v| array_index(&v.name, name).is_none_or(|i| i < len));
+        for (i, item) in items.into_iter().enumerate() {
+            let v = SymVar::new_assign(array_cell(name, i), ty.to_string(), item);
+            match self.sigma.iter_mut().find(|old| old.name == v.name) {
+                Some(old) => *old = v,
+                None => self.sigma.push(v),
             }
-            i = i + 1;
         }
-       // println!("{}", stmt);
-        return stmt;
-    }
-
-    pub fn assign_symvar_value(&mut self, mut stmt_rs: String, stmt_ls: String, mut lisp_rs: String) {
-        //println!("{} = {}", stmt_ls.clone(), stmt_rs.clone());
-        stmt_rs = self.display_as_var0(stmt_rs);
-        lisp_rs = self.lisp_as_var0(lisp_rs);
-        let mut j = 0;
-        let mut found = false;
-        while j < self.sigma.len() {
-            if stmt_ls.contains(&self.sigma[j].name) {
-                found = true;
-                self.sigma[j].prev = self.sigma[j].var0.clone();
-                self.sigma[j].var0 = eval(stmt_rs.clone());
-                self.sigma[j].lisp_var0 = lisp_rs.clone();
-                self.sort_symvar(j);
+    }
+
+    /// Sets the items of the array variable `name` to `items`, which are in
+    /// terms of the parameters.
+    pub fn assign_array(&mut self, name: &str, items: Vec<SymTerm>) {
+        for (i, item) in items.into_iter().enumerate() {
+            let cell = array_cell(name, i);
+            if let Some(v) = self.sigma.iter_mut().find(|v| v.name == cell) {
+                v.prev = std::mem::replace(&mut v.var0, item);
             }
-            j = j + 1;
         }
-        if !found {
-            panic!("assignment to uninitialized variable attempted");
+    }
+
+    /// Drops the items of the tuple, array or enum variable `name`, and of
+    /// any in it, before a new variable of that name is bound.
+    pub fn drop_cells(&mut self, name: &str) {
+        let (field, item, tag) = (format!("|{}.", name), format!("|{}[", name), format!("|{}:", name));
+        self.sigma.retain(|v| !v.name.starts_with(&field) && !v.name.starts_with(&item) && !v.name.starts_with(&tag));
+    }
+
+    /// Binds the variable `name` to `value` (in terms of the parameters),
+    /// shadowing any variable of the same name. Returns the variable it
+    /// shadows, for `unbind_variable`.
+    pub fn bind_variable(&mut self, name: &str, ty: IntTy, value: SymTerm) -> Option<SymVar> {
+        let v = SymVar::new_assign(name.to_string(), ty.to_string(), value);
+        match self.sigma.iter_mut().find(|old| old.name == name) {
+            Some(old) => Some(std::mem::replace(old, v)),
+            None => {
+                self.sigma.push(v);
+                None
+            },
+        }
+    }
+
+    /// Drops the variable `name` bound by `bind_variable`, bringing back the
+    /// variable `shadowed` it returned.
+    pub fn unbind_variable(&mut self, name: &str, shadowed: Option<SymVar>) {
+        let at = self.sigma.iter().position(|v| v.name == name);
+        match (at, shadowed) {
+            (Some(at), Some(old)) => self.sigma[at] = old,
+            (Some(at), None) => { self.sigma.remove(at); },
+            (None, old) => self.sigma.extend(old),
+        }
+    }
+
+    /// Rewrites every variable in `term` to its current value, which is in
+    /// terms of the parameters.
+    pub fn term_as_var0(&self, term: &SymTerm) -> SymTerm {
+        term.substitute(&|name| {
+            self.sigma.iter().find(|v| v.name == name).map(|v| v.var0.clone())
+        })
+    }
+
+    /// Assigns `value` (in terms of the current variables) to the variable
+    /// `name`.
+    pub fn assign_symvar_value(&mut self, name: &str, value: SymTerm) {
+        let value = self.term_as_var0(&value);
+        match self.sigma.iter_mut().find(|v| v.name == name) {
+            Some(v) => v.prev = std::mem::replace(&mut v.var0, value),
+            None => panic!("assignment to uninitialized variable attempted"),
         }
     }
 
-    pub fn new_assertion(&mut self, a: String, lisp: String) {
-        let assert = a.replace(";", "");
-        let var0_assert = self.display_as_var0(assert.clone());
-        let lisp_assert =  self.lisp_as_var0(lisp.clone());
-        let and_assert = " && ".to_owned() + &var0_assert;
-        self.pi.add_assertion_to_pi_str(&and_assert);
-        self.pi.add_assertion_to_solver(&lisp_assert);
+    /// Asserts the condition `cond` (in terms of the current variables) for
+    /// the rest of this path.
+    pub fn new_assertion(&mut self, cond: &SymTerm) -> Result<(), SolverError> {
+        let cond = self.term_as_var0(cond);
+        self.pi.add_assertion_to_pi_str(&format!(" && {}", cond));
+        self.pi.add_assertion_to_solver(&cond.to_lisp())
+    }
+
+    /// Asserts `lisp`, which is already in terms of the parameters, shown in
+    /// `pi` as `a`.
+    pub fn new_assertion_var0(&mut self, a: String, lisp: String) -> Result<(), SolverError> {
+        self.pi.add_assertion_to_pi_str(&format!(" && {}", a));
+        self.pi.add_assertion_to_solver(&lisp)
     }
 
 
-    pub fn sort_symvar(&mut self, i: usize) {
-        let mut temp= i;
+    /// Checks whether the condition `cond` can hold on this path. Returns the
+    /// values of the parameters that make it hold.
+    pub fn check_condition(&mut self, cond: &SymTerm) -> CheckResult<SymModel> {
+        let lisp = self.term_as_var0(cond).to_lisp();
+        self.check_condition_var0(lisp)
+    }
+
+    /// Like `check_condition` for a condition already in terms of the
+    /// parameters.
+    pub fn check_condition_var0(&mut self, lisp: String) -> CheckResult<SymModel> {
+        let names: Vec<String> = self.params.iter().map(|(name, _)| name.clone()).collect();
+        self.pi.check_with(&lisp, &names).map(|values| SymModel::new(&self.params, values).with_variants(&self.variants))
+    }
+
+    /// Records that this path can panic with `kind` at `span` when `lisp` (in
+    /// terms of the parameters) holds, so that the panic ends up in the
+    /// summary of the function.
+    pub fn record_panic(&mut self, span: Span<PPos>, kind: PanicKind, lisp: String) {
+        let mut asserts = self.pi.assertions();
+        asserts.push(lisp);
+        let decls = local_decls(&self.pi, &self.params);
+        self.panics.push(SummaryPanic { span, kind, asserts, decls });
+    }
+
+    /// Returns values of the parameters that drive the function down this
+    /// path, or `None` if the path is infeasible (or the solver could not
+    /// tell).
+    pub fn model(&mut self) -> Option<SymModel> {
+        self.check_condition(&SymTerm::Bool(true)).sat()
+    }
+
+    /// Like `model`, but also returns the value this path returns as a
+    /// decimal Rust literal if it returns an int that only depends on the
+    /// parameters (and not on what unknown calls return).
+    pub fn model_with_ret(&mut self) -> Option<(SymModel, Option<String>)> {
+        let params = &self.params;
+        let ret = self.ret_ty.zip(self.ret.clone().filter(|ret| ret.only_vars(&|name| params.iter().any(|(param, _)| param == name))));
+        self.check_value_var0("true".to_string(), ret).sat()
+    }
 
-        while temp > 0 {
-            self.sigma.swap(temp, temp - 1);
-            temp = temp - 1;
+    /// Returns values of the parameters that drive the function down this
+    /// path and make it return `value`, or `None` if none do or the
+    /// function does not return an int. Nothing returns a value out of the
+    /// range of the return type.
+    pub fn check_return(&mut self, value: i128) -> Option<SymModel> {
+        let (ty, ret) = (self.ret_ty.filter(|ty| !ty.is_bool())?, self.ret.clone()?);
+        if !ty.contains(value) {
+            return None;
+        }
+        let (model, _) = self.check_value_var0(SymTerm::eq(ret.clone(), SymTerm::Int(value, ty)).to_lisp(), Some((ty, ret))).sat()?;
+        Some(model)
+    }
+
+    /// Like `check_condition`, also returning the value `term` (in terms of
+    /// the current variables, of type `ty`) takes for the values found.
+    pub fn check_condition_value(&mut self, cond: &SymTerm, term: &SymTerm, ty: IntTy) -> CheckResult<(SymModel, Option<String>)> {
+        let lisp = self.term_as_var0(cond).to_lisp();
+        let term = self.term_as_var0(term);
+        self.check_value_var0(lisp, Some((ty, term)))
+    }
+
+    /// Checks whether the condition `lisp` (in terms of the parameters) can
+    /// hold on this path. Returns the values of the parameters that make it
+    /// hold and the value `value` (of its type, in terms of the parameters)
+    /// takes for them, as a Rust literal.
+    fn check_value_var0(&mut self, lisp: String, value: Option<(IntTy, SymTerm)>) -> CheckResult<(SymModel, Option<String>)> {
+        let mut names: Vec<String> = self.params.iter().map(|(name, _)| name.clone()).collect();
+        if let Some((_, term)) = &value {
+            names.push(term.to_lisp());
+        }
+
+        // the solver may print the term differently, but the values come
+        // back in the order they were asked for
+        self.pi.check_with(&lisp, &names).map(|mut values| {
+            let value = match value {
+                Some((ty, _)) => values.pop().and_then(|(_, v)| ty.parse_value(&v)),
+                None => None,
+            };
+            (SymModel::new(&self.params, values).with_variants(&self.variants), value)
+        })
+    }
+
+    /// Enters a call to `fn_name`. The caller's variables are put aside and
+    /// the callee's `params` (name, type and argument value) become the only
+    /// variables. Returns the caller's state for `leave_call`.
+    pub fn enter_call(&mut self, fn_name: String, ret_ty: Option<IntTy>, params: Vec<(String, IntTy, SymTerm)>) -> CallFrame {
+        let mut sigma = Vec::new();
+        for (name, ty, value) in params {
+            sigma.push(SymVar::new_assign(name, ty.to_string(), self.term_as_var0(&value)));
         }
 
-        //println!("{}", self.to_string());
+        CallFrame {
+            fn_name: std::mem::replace(&mut self.fn_name, fn_name),
+            sigma: std::mem::replace(&mut self.sigma, sigma),
+            ret_ty: std::mem::replace(&mut self.ret_ty, ret_ty),
+            returned: std::mem::replace(&mut self.returned, false),
+            ret: self.ret.take(),
+        }
+    }
+
+    /// Returns from a call entered with `enter_call`, restoring the caller's
+    /// state from `frame`. Returns the value the callee returned.
+    pub fn leave_call(&mut self, frame: CallFrame) -> Option<SymTerm> {
+        self.fn_name = frame.fn_name;
+        self.sigma = frame.sigma;
+        self.ret_ty = frame.ret_ty;
+        self.returned = frame.returned;
+        std::mem::replace(&mut self.ret, frame.ret)
+    }
+
+    /// Binds `symbol` (the quoted source text of a call) to the value the call
+    /// returned (in terms of the parameters) so that later uses of the call
+    /// read it.
+    pub fn bind_call(&mut self, symbol: String, ty: IntTy, value: SymTerm) {
+        match self.sigma.iter_mut().find(|v| v.name == symbol) {
+            Some(v) => v.prev = std::mem::replace(&mut v.var0, value),
+            None => self.sigma.insert(0, SymVar::new_assign(symbol, ty.to_string(), value)),
+        }
+    }
+
+    /// Marks this path as returned with the value `ret` (in terms of the
+    /// current variables).
+    pub fn set_ret(&mut self, ret: Option<SymTerm>) {
+        self.ret = ret.map(|r| self.term_as_var0(&r));
+        self.returned = true;
+    }
 
+    /// Assumes that the condition `cond` does not hold for the rest of this
+    /// path without forking it. It is shown in `pi` as `!name(a)`.
+    pub fn assume_not(&mut self, name: &str, a: &SymTerm, cond: &SymTerm) -> Result<(), SolverError> {
+        let a = self.term_as_var0(a);
+        let not = SymTerm::not(self.term_as_var0(cond));
+        self.pi.add_assertion_to_pi_str(&format!(" && !{}({})", name, a));
+        self.pi.add_assertion_to_solver(&not.to_lisp())
     }
 }
 
 //#[cfg(test)]
 //mod tests {
 //    use crate::symex::*;
-//    use equation_solver::*;
 //    static PATH_TO_SOLVER:&str = "z3\\bin\\z3";
 //
 //
@@ -0,0 +1,301 @@
+use std::{cell::Cell, collections::HashMap};
+
+use crate::parser::parser::{BinOp, SymexRes};
+use crate::symex::{substitute_lisp, CheckResult, IntTy, SolverError, SummaryPanic, SymExEngine, SymTerm, SymVar};
+
+/// How one iteration of a loop changes a variable.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LoopStep {
+    /// The variable keeps its value.
+    Same,
+    /// The variable goes up (`op` is `+`) or down (`-`) by a constant.
+    By { op: BinOp, step: SymTerm },
+    /// The variable changes in some other way.
+    Unknown,
+}
+
+///
+/// What one iteration of a while loop does to the variables of the path
+/// entering it, read off executing its body once from unknown values. Loops
+/// whose variables only ever stay the same or move by a constant can then
+/// be run any number of times at once.
+///
+#[derive(Debug, Clone)]
+pub struct LoopSummary {
+    /// Each variable by name, with its type and how an iteration changes it.
+    pub steps: Vec<(String, IntTy, LoopStep)>,
+    /// Each variable by name, with the constant it held before the
+    /// iteration.
+    pub pre: Vec<(String, String)>,
+    /// The panics of the iteration, in terms of the constants in `pre`.
+    pub panics: Vec<SummaryPanic>,
+}
+
+impl LoopSummary {
+    ///
+    /// Reads how an iteration changes each variable of `pre`, given as its
+    /// name and the constant it held before the iteration, from the
+    /// variables `post` it ended with. Variables declared by the body are
+    /// left out, as they do not outlive it.
+    ///
+    pub fn infer(pre: &[(String, String)], post: &[SymVar]) -> LoopSummary {
+        let mut steps = Vec::new();
+        for (name, before) in pre {
+            let Some(var) = post.iter().find(|var| &var.name == name) else {
+                continue;
+            };
+            let step = match &var.var0 {
+                SymTerm::Var(v) if v == before => LoopStep::Same,
+                SymTerm::BinOp { op: op @ (BinOp::Add | BinOp::Sub), left, right, .. } => match (&**left, &**right) {
+                    (SymTerm::Var(v), SymTerm::Int(..)) if v == before => LoopStep::By { op: op.clone(), step: (**right).clone() },
+                    _ => LoopStep::Unknown,
+                },
+                _ => LoopStep::Unknown,
+            };
+            steps.push((name.clone(), var.ty, step));
+        }
+        LoopSummary { steps, pre: pre.to_vec(), panics: Vec::new() }
+    }
+
+    ///
+    /// Whether the condition of every panic of the iteration is known once
+    /// the variables that move by a constant are, so that `apply` can tell
+    /// in which of the iterations left it happens.
+    ///
+    pub fn panics_known(&self) -> bool {
+        let unknown: Vec<&str> = self.pre.iter()
+            .filter(|(name, _)| matches!(self.step(name), Some(LoopStep::Unknown)))
+            .map(|(_, before)| before.as_str())
+            .collect();
+        let mentioned = Cell::new(false);
+        for assert in self.panics.iter().flat_map(|panic| &panic.asserts) {
+            substitute_lisp(assert, |symbol| {
+                mentioned.set(mentioned.get() || unknown.contains(&symbol));
+                None
+            });
+        }
+        !mentioned.get()
+    }
+
+    fn step(&self, name: &str) -> Option<&LoopStep> {
+        self.steps.iter().find(|(n, ..)| n == name).map(|(_, _, step)| step)
+    }
+
+    ///
+    /// Whether `cond` (in terms of the variables) is a comparison of sums of
+    /// variables and constants that no iteration leaves unknown, so that
+    /// once it fails it fails for good.
+    ///
+    fn monotone(&self, cond: &SymTerm) -> bool {
+        match cond {
+            SymTerm::BinOp { op: BinOp::LessThan | BinOp::LessThanEq | BinOp::MoreThan | BinOp::MoreThanEq, left, right, .. } => {
+                self.linear(left) && self.linear(right)
+            },
+            _ => false,
+        }
+    }
+
+    fn linear(&self, term: &SymTerm) -> bool {
+        match term {
+            // the value of a call is only read, never recomputed, here
+            SymTerm::Var(name) if name.starts_with('|') => false,
+            SymTerm::Var(name) => matches!(self.step(name), Some(LoopStep::Same | LoopStep::By { .. })),
+            SymTerm::Int(..) => true,
+            SymTerm::BinOp { op: BinOp::Add | BinOp::Sub, left, right, .. } => self.linear(left) && self.linear(right),
+            SymTerm::Cast { from, to, term } => to.bits > from.bits && self.linear(term),
+            _ => false,
+        }
+    }
+
+    ///
+    /// Whether the rest of a loop with condition `cond` (in terms of the
+    /// variables) can be run at once: its condition is monotone, and the
+    /// variables that move all have the same type.
+    ///
+    pub fn applies(&self, cond: &SymTerm) -> bool {
+        self.counter_ty().is_some() && self.monotone(cond)
+    }
+
+    /// The type of the variables that move by a constant, which the number
+    /// of iterations is counted in.
+    fn counter_ty(&self) -> Option<IntTy> {
+        let mut tys = self.steps.iter()
+            .filter(|(_, _, step)| matches!(step, LoopStep::By { .. }))
+            .map(|(_, ty, _)| *ty);
+        let ty = tys.next()?;
+        tys.all(|t| t == ty).then_some(ty)
+    }
+
+    ///
+    /// Runs the rest of the loop with condition `cond` (in terms of the
+    /// variables) on `engine` at once, given that `cond` holds now (see
+    /// `applies`). The number of iterations left less one becomes a new
+    /// unknown `k`: the path assumes `cond` still holds after `k` of them
+    /// and fails after one more, and every variable takes the value it has
+    /// then.
+    ///
+    /// Returns the panics of the iterations left, each found after a new
+    /// unknown number `m` of them, including a variable overflowing as it
+    /// moves. The path then assumes the loop ends without overflowing, but
+    /// not that none of the other panics happened along the way.
+    ///
+    pub fn apply(&self, engine: &mut SymExEngine, cond: &SymTerm) -> Result<Vec<SymexRes>, SolverError> {
+        let mut res = Vec::new();
+        let Some(ty) = self.counter_ty() else {
+            return Ok(res);
+        };
+        let k = SymTerm::Var(engine.pi.fresh_int("k", ty));
+        let zero = SymTerm::Int(0, ty);
+        let k_plus_one = SymTerm::bin(BinOp::Add, ty.signed, k.clone(), SymTerm::Int(1, ty));
+
+        // how far `n` iterations move a variable, kept linear for the usual
+        // step of one
+        let moved = |n: &SymTerm, step: &SymTerm| match step {
+            SymTerm::Int(1, _) => n.clone(),
+            _ => SymTerm::bin(BinOp::Mul, ty.signed, n.clone(), step.clone()),
+        };
+
+        // the values of the variables after `n` more iterations
+        let current: Vec<(String, SymTerm)> = engine.sigma.iter().map(|var| (var.name.clone(), var.var0.clone())).collect();
+        let after = |n: &SymTerm, name: &str| -> Option<SymTerm> {
+            let value = current.iter().find(|(var, _)| var == name)?.1.clone();
+            match self.step(name)? {
+                LoopStep::Same => Some(value),
+                LoopStep::By { op, step } => Some(SymTerm::bin(op.clone(), ty.signed, value, moved(n, step))),
+                LoopStep::Unknown => None,
+            }
+        };
+
+        // that `n` more iterations move no variable past its type
+        let no_overflow = |n: &SymTerm| {
+            let mut asserts = Vec::new();
+            for (name, _, step) in &self.steps {
+                if let LoopStep::By { op, step } = step {
+                    let value = current.iter().find(|(var, _)| var == name).map(|(_, v)| v.clone()).unwrap_or(SymTerm::var(name.as_str()));
+                    if !matches!(step, SymTerm::Int(1, _)) {
+                        asserts.push(SymTerm::not(SymTerm::overflow(BinOp::Mul, ty, n.clone(), step.clone())));
+                    }
+                    asserts.push(SymTerm::not(SymTerm::overflow(op.clone(), ty, value, moved(n, step))));
+                }
+            }
+            asserts
+        };
+
+        // the panics of the iteration after `m` more, where the loop still
+        // runs and nothing has overflowed yet; an overflow of a variable
+        // that moves is one of them
+        for panic in &self.panics {
+            let m = SymTerm::Var(engine.pi.fresh_int("m", ty));
+            let mut names: HashMap<&str, String> = HashMap::new();
+            for (name, before) in &self.pre {
+                if let Some(value) = after(&m, name) {
+                    names.insert(before, value.to_lisp());
+                }
+            }
+            for (name, sort) in &panic.decls {
+                let hint = name.trim_matches('|').split('!').next().unwrap_or("v");
+                names.insert(name, engine.pi.fresh_const(hint, sort));
+            }
+
+            let mut asserts: Vec<String> = [SymTerm::bin(BinOp::MoreThanEq, ty.signed, m.clone(), zero.clone()), cond.substitute(&|name| after(&m, name))]
+                .into_iter()
+                .chain(no_overflow(&m))
+                .map(|assert| assert.to_lisp())
+                .collect();
+            asserts.extend(panic.asserts.iter().map(|a| substitute_lisp(a, |symbol| names.get(symbol).cloned())));
+            let lisp = format!("(and {})", asserts.join(" "));
+            match engine.check_condition_var0(lisp.clone()) {
+                CheckResult::Sat(model) => res.push(SymexRes::Panic { span: panic.span.clone(), kind: panic.kind.clone(), model }),
+                CheckResult::Unknown => res.push(SymexRes::UnknownPanic { span: panic.span.clone(), kind: panic.kind.clone() }),
+                CheckResult::Unsat => continue,
+            }
+            engine.record_panic(panic.span.clone(), panic.kind.clone(), lisp);
+        }
+
+        let mut asserts = vec![
+            SymTerm::bin(BinOp::MoreThanEq, ty.signed, k.clone(), zero.clone()),
+            SymTerm::not(SymTerm::overflow(BinOp::Add, ty, k.clone(), SymTerm::Int(1, ty))),
+            cond.substitute(&|name| after(&k, name)),
+            SymTerm::not(cond.substitute(&|name| after(&k_plus_one, name))),
+        ];
+        asserts.extend(no_overflow(&k_plus_one));
+
+        for assert in asserts {
+            engine.new_assertion_var0(assert.to_string(), assert.to_lisp())?;
+        }
+
+        // variables the loop changes unpredictably are unknown after it
+        let mut values = Vec::new();
+        for (name, ty, step) in &self.steps {
+            match step {
+                LoopStep::Same => {},
+                LoopStep::By { .. } => values.extend(after(&k_plus_one, name).map(|value| (name.clone(), value))),
+                LoopStep::Unknown => values.push((name.clone(), SymTerm::Var(engine.pi.fresh_int(name.trim_matches('|'), *ty)))),
+            }
+        }
+        for var in engine.sigma.iter_mut() {
+            if let Some((_, value)) = values.iter().find(|(name, _)| *name == var.name) {
+                var.prev = std::mem::replace(&mut var.var0, value.clone());
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Span;
+    use crate::parser::parser::{BinOp, PPos, PanicKind};
+    use crate::symex::{IntTy, LoopStep, LoopSummary, SummaryPanic, SymTerm, SymVar};
+
+    #[test]
+    fn test_infer_steps() {
+        let i64_ty = IntTy::from_type_str("i64").unwrap();
+        let pre = vec![
+            ("i".to_string(), "|i!1|".to_string()),
+            ("n".to_string(), "|n!2|".to_string()),
+            ("j".to_string(), "|j!3|".to_string()),
+        ];
+        let post = vec![
+            SymVar::new_assign("i".to_string(), "i64".to_string(), SymTerm::bin(BinOp::Add, true, SymTerm::var("|i!1|"), SymTerm::Int(1, i64_ty))),
+            SymVar::new_assign("n".to_string(), "i64".to_string(), SymTerm::var("|n!2|")),
+            SymVar::new_assign("j".to_string(), "i64".to_string(), SymTerm::bin(BinOp::Mul, true, SymTerm::var("|j!3|"), SymTerm::Int(2, i64_ty))),
+        ];
+
+        let summary = LoopSummary::infer(&pre, &post);
+        let steps: Vec<&LoopStep> = summary.steps.iter().map(|(_, _, step)| step).collect();
+        assert_eq!(steps, vec![
+            &LoopStep::By { op: BinOp::Add, step: SymTerm::Int(1, i64_ty) },
+            &LoopStep::Same,
+            &LoopStep::Unknown,
+        ]);
+
+        let lt = |l: &str, r: &str| SymTerm::bin(BinOp::LessThan, true, SymTerm::var(l), SymTerm::var(r));
+        assert!(summary.applies(&lt("i", "n")));
+        assert!(!summary.applies(&lt("j", "n")));
+        assert!(!summary.applies(&SymTerm::bin(BinOp::NotEq, true, SymTerm::var("i"), SymTerm::var("n"))));
+    }
+
+    #[test]
+    fn test_panics_known() {
+        let i32_ty = IntTy::from_type_str("i32").unwrap();
+        let pre = vec![("x".to_string(), "x".to_string()), ("xy".to_string(), "xy".to_string())];
+        let post = vec![
+            SymVar::new_assign("x".to_string(), "i32".to_string(), SymTerm::bin(BinOp::Mul, true, SymTerm::var("x"), SymTerm::Int(2, i32_ty))),
+            SymVar::new_assign("xy".to_string(), "i32".to_string(), SymTerm::var("xy")),
+        ];
+        let mut summary = LoopSummary::infer(&pre, &post);
+        let panic = |assert: &str| SummaryPanic {
+            span: Span::new(PPos::new(), PPos::new()),
+            kind: PanicKind::DivByZero,
+            asserts: vec![assert.to_string()],
+            decls: Vec::new(),
+        };
+
+        // `xy` is a symbol of its own, not `x` followed by `y`
+        summary.panics = vec![panic("(= xy (_ bv0 32))")];
+        assert!(summary.panics_known());
+        summary.panics = vec![panic("(= (bvadd x xy) (_ bv0 32))")];
+        assert!(!summary.panics_known());
+    }
+}
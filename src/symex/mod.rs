@@ -1,11 +1,32 @@
+mod config;
+pub use config::*;
+
 mod symsolver;
 pub use symsolver::*;
 
+mod bvsolver;
+pub use bvsolver::*;
+
 mod engine;
 pub use engine::*;
 
 mod symvar;
 pub use symvar::*;
 
+mod term;
+pub use term::*;
+
 mod pather;
-pub use pather::*;
\ No newline at end of file
+pub use pather::*;
+
+mod model;
+pub use model::*;
+
+mod summary;
+pub use summary::*;
+
+mod scheduler;
+pub use scheduler::*;
+
+mod loops;
+pub use loops::*;
\ No newline at end of file
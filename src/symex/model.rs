@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+use crate::symex::IntTy;
+
+/// 
+/// Concrete values for the parameters of a function that drive it down a
+/// path, read back from the solver after a satisfiable `check-sat`.
+/// 
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SymModel {
+    /// Each parameter (in declaration order) and its value as a decimal Rust
+    /// literal.
+    pub values: Vec<(String, String)>,
+}
+
+impl SymModel {
+    /// Builds a model from the `(name, value)` pairs the solver returned for
+    /// `params`, converting each bit-vector value to a literal of its type.
+    /// Quoted names (the items of arrays, `|a[0]|`) lose their quotes.
+    pub fn new(params: &[(String, IntTy)], values: Vec<(String, String)>) -> Self {
+        let mut model = SymModel::default();
+        for (name, ty) in params {
+            let value = values.iter()
+                .find(|(n, _)| n == name)
+                .and_then(|(_, v)| ty.parse_value(v));
+
+            if let Some(value) = value {
+                model.values.push((name.trim_matches('|').to_string(), value));
+            }
+        }
+        model
+    }
+
+    /// 
+    /// Prints the tag of every enum parameter in `variants` (its tag cell
+    /// and the names of its variants) as the variant it picks, under the
+    /// name of the parameter, and drops the fields of the other variants.
+    /// 
+    pub fn with_variants(mut self, variants: &[(String, Vec<String>)]) -> Self {
+        for (tag, names) in variants {
+            let Some((name, _)) = tag.trim_matches('|').rsplit_once(':') else {
+                continue;
+            };
+            let Some(at) = self.values.iter().position(|(n, _)| n == tag.trim_matches('|')) else {
+                continue;
+            };
+            let Some(variant) = self.values[at].1.parse::<usize>().ok().and_then(|i| names.get(i)) else {
+                continue;
+            };
+            self.values[at] = (name.to_string(), variant.clone());
+            self.values.retain(|(n, _)| {
+                let field = n.strip_prefix(name).and_then(|rest| rest.strip_prefix('.'));
+                field.is_none_or(|field| field.starts_with(variant.as_str()) && field[variant.len()..].starts_with(['.', '[', ':']))
+            });
+        }
+        self
+    }
+
+    /// Returns the value of the parameter `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+impl Display for SymModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, value)) in self.values.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} = {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symex::{SymModel, IntTy};
+
+    #[test]
+    fn test_model() {
+        let params = vec![("x".to_string(), IntTy::I32), ("y".to_string(), IntTy::from_type_str("u8").unwrap())];
+        let values = vec![("y".to_string(), "#xff".to_string()), ("x".to_string(), "#xfffffffd".to_string())];
+        let model = SymModel::new(&params, values);
+
+        assert_eq!(model.get("x"), Some("-3"));
+        assert_eq!(model.get("y"), Some("255"));
+        assert_eq!(model.to_string(), "x = -3, y = 255");
+
+        let params = vec![("|a[0]|".to_string(), IntTy::I32)];
+        let model = SymModel::new(&params, vec![("|a[0]|".to_string(), "#x00000007".to_string())]);
+        assert_eq!(model.to_string(), "a[0] = 7");
+
+        // o is Ok(5), so the field of Err is dropped
+        let params = vec![("|o:Result|".to_string(), IntTy::TAG), ("|o.Ok.0|".to_string(), IntTy::I32), ("|o.Err.0|".to_string(), IntTy::I32)];
+        let values = vec![("|o:Result|".to_string(), "#x00000000".to_string()), ("|o.Ok.0|".to_string(), "#x00000005".to_string()), ("|o.Err.0|".to_string(), "#x00000001".to_string())];
+        let model = SymModel::new(&params, values).with_variants(&[("|o:Result|".to_string(), vec!["Ok".to_string(), "Err".to_string()])]);
+        assert_eq!(model.to_string(), "o = Ok, o.Ok.0 = 5");
+    }
+}
@@ -1,25 +1,28 @@
-use crate::symex::{SymSolver, SymExEngine};
+use crate::symex::{PathCondition, SymExEngine, SymTerm, SolverConfig, SolverError};
 
-pub fn new_engine(engines: &mut Vec<SymExEngine>, fn_name: &String) -> usize {
+/// Adds the engine of a new execution of `fn_name`, with its own solver
+/// started from `config`, and returns its id.
+pub fn new_engine(engines: &mut Vec<SymExEngine>, fn_name: &str, config: &SolverConfig) -> Result<usize, SolverError> {
     let id = engines.len();
-
-    let mut init_engine = || -> Result<(), Box<dyn std::error::Error>> {
-        let e = SymExEngine {
-            fn_name: fn_name.clone(),
-            pi: SymSolver::new(),
-            sigma: Vec::new(),
-            path: engines.len() as u32,
-            reached_symex: false,
-        };
-        engines.push(e);
-        Ok(())
-    };
-
-    if let Err(_err) = init_engine() {
-        println!("Failed to initialize symbolic execution engine.");
-    }
-
-    id
+    engines.push(SymExEngine {
+        fn_name: fn_name.to_string(),
+        pi: PathCondition::new(config)?,
+        sigma: Vec::new(),
+        params: Vec::new(),
+        path: engines.len() as u32,
+        reached_symex: false,
+        truncated: false,
+        ret_ty: None,
+        returned: false,
+        ret: None,
+        panics: Vec::new(),
+        depth: 0,
+        branch: None,
+        suspended: false,
+        exit: None,
+        variants: Vec::new(),
+    });
+    Ok(id)
 }
 
 pub fn clone_engine(engines: &mut Vec<SymExEngine>, path: usize) -> usize {
@@ -28,10 +31,21 @@ pub fn clone_engine(engines: &mut Vec<SymExEngine>, path: usize) -> usize {
     let mut init_engine = || -> Result<(), Box<dyn std::error::Error>> {
         let e = SymExEngine {
             fn_name: engines[path].fn_name.clone(),
-            pi: SymSolver::copy_solver(&engines[path].pi),
+            pi: PathCondition::copy_solver(&engines[path].pi),
             sigma: (*engines[path].sigma).to_vec(),
+            params: engines[path].params.clone(),
             path: engines.len() as u32,
-            reached_symex: engines[path].reached_symex.clone(),
+            reached_symex: engines[path].reached_symex,
+            truncated: engines[path].truncated,
+            ret_ty: engines[path].ret_ty,
+            returned: engines[path].returned,
+            ret: engines[path].ret.clone(),
+            panics: Vec::new(),
+            depth: engines[path].depth,
+            branch: engines[path].branch,
+            suspended: engines[path].suspended,
+            exit: engines[path].exit.clone(),
+            variants: engines[path].variants.clone(),
         };
         engines.push(e);
         Ok(())
@@ -45,18 +59,63 @@ pub fn clone_engine(engines: &mut Vec<SymExEngine>, path: usize) -> usize {
 }
 
 
-pub fn new_assert(e: &mut Vec<SymExEngine>, path: usize, assert: String, lisp: String) -> usize {
-    //println!("new assert");
+pub fn new_assert(e: &mut Vec<SymExEngine>, path: usize, cond: SymTerm) -> Result<usize, SolverError> {
     let l = e.len();
     clone_engine(e, path);
-    //println!("asserting {}", assert.clone());
-    //println!("asserting l {}", lisp.clone());
-    
-    e[l].new_assertion(assert.clone(), lisp.clone());
-    let neg_assert = "!".to_owned() + &assert.clone();
-    let neg_lisp = format!("(not {})", lisp.clone());
-    e[path].new_assertion(neg_assert.clone(), neg_lisp);
-    l
+    e[l].new_assertion(&cond)?;
+    e[path].new_assertion(&SymTerm::not(cond))?;
+    Ok(l)
+}
+
+/// Like `new_assert`, for a condition `cond` that is already in terms of the
+/// parameters.
+pub fn new_assert_var0(e: &mut Vec<SymExEngine>, path: usize, cond: SymTerm) -> Result<usize, SolverError> {
+    let l = e.len();
+    clone_engine(e, path);
+    let not = SymTerm::not(cond.clone());
+    e[l].new_assertion_var0(cond.to_string(), cond.to_lisp())?;
+    e[path].new_assertion_var0(not.to_string(), not.to_lisp())?;
+    Ok(l)
+}
+
+///
+/// Merges the path `from` into the path `into` if both are feasible, forked
+/// from the same engine and still running with the same variables. The path
+/// condition becomes the disjunction of the two, and every variable whose
+/// values differ an `ite` on which of them was taken. `from` is left
+/// infeasible so that nothing executes it any further. Returns whether the
+/// two were merged.
+///
+pub fn merge_engines(e: &mut [SymExEngine], into: usize, from: usize) -> bool {
+    let (a, b) = (&e[into], &e[from]);
+    let mergeable = a.pi.shares_solver(&b.pi)
+        && a.pi.satisfiable && b.pi.satisfiable
+        && a.fn_name == b.fn_name
+        && !a.returned && !b.returned
+        && !a.suspended && !b.suspended
+        && a.exit.is_none() && b.exit.is_none()
+        && a.truncated == b.truncated
+        && a.sigma.len() == b.sigma.len()
+        && a.sigma.iter().zip(&b.sigma).all(|(x, y)| x.name == y.name && x.ty == y.ty);
+    if !mergeable {
+        return false;
+    }
+
+    let pi = e[from].pi.copy_solver();
+    let sigma = e[from].sigma.clone();
+    let (reached_symex, depth) = (e[from].reached_symex, e[from].depth);
+    e[from].pi.satisfiable = false;
+
+    let engine = &mut e[into];
+    let guard = engine.pi.merge(&pi);
+    for (var, other) in engine.sigma.iter_mut().zip(sigma) {
+        if var.var0 != other.var0 {
+            var.var0 = SymTerm::ite(guard.clone(), other.var0, var.var0.clone());
+        }
+    }
+    engine.reached_symex |= reached_symex;
+    engine.depth = engine.depth.min(depth);
+    true
 }
 
 #[cfg(test)]
@@ -0,0 +1,295 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::symex::SymExEngine;
+
+///
+/// Which of the pending paths takes the next step.
+///
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Strategy {
+    /// The path that became pending last first, so that each path runs to
+    /// the end before the ones forked from it are taken up.
+    #[default]
+    Dfs,
+    /// The path that took the fewest branches first (the one that became
+    /// pending first among those).
+    Bfs,
+    /// A random path first.
+    RandomPath,
+    /// The path whose last branch was taken the fewest times first.
+    Coverage,
+}
+
+impl Strategy {
+    /// Reads a strategy name as given to `--strategy`.
+    pub fn from_name(name: &str) -> Option<Strategy> {
+        match name.to_ascii_lowercase().as_str() {
+            "dfs" => Some(Strategy::Dfs),
+            "bfs" => Some(Strategy::Bfs),
+            "random" | "random-path" => Some(Strategy::RandomPath),
+            "coverage" | "coverage-guided" => Some(Strategy::Coverage),
+            _ => None,
+        }
+    }
+}
+
+///
+/// The limits on a whole run, any of which stops the exploration once
+/// reached.
+///
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Budget {
+    /// How many paths may be forked.
+    pub max_paths: Option<usize>,
+    /// How long the solvers may take in total.
+    pub max_solver_time: Option<Duration>,
+    /// How long the run may take.
+    pub max_time: Option<Duration>,
+}
+
+impl Budget {
+    pub fn new() -> Self {
+        Budget::default()
+    }
+
+    pub fn with_max_paths(mut self, max_paths: Option<usize>) -> Self {
+        self.max_paths = max_paths;
+        self
+    }
+
+    pub fn with_max_solver_time(mut self, max_solver_time: Option<Duration>) -> Self {
+        self.max_solver_time = max_solver_time;
+        self
+    }
+
+    pub fn with_max_time(mut self, max_time: Option<Duration>) -> Self {
+        self.max_time = max_time;
+        self
+    }
+}
+
+/// Which limit of the `Budget` ran out.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BudgetKind {
+    Paths,
+    SolverTime,
+    Time,
+}
+
+impl Display for BudgetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BudgetKind::Paths => "the path limit was reached",
+            BudgetKind::SolverTime => "the solver time limit was reached",
+            BudgetKind::Time => "the time limit was reached",
+        })
+    }
+}
+
+///
+/// Decides which of the pending paths steps next and stops the exploration
+/// once the `Budget` runs out. Paths that are pending then are suspended:
+/// they are not executed any further and make up the frontier of the run.
+///
+/// The pending paths are kept in a worklist of `Pending` states, in the
+/// order they became pending, which `pick` takes the next one from.
+///
+pub struct Scheduler {
+    pub strategy: Strategy,
+    pub budget: Budget,
+    started: Instant,
+    /// How many paths have been forked.
+    paths: usize,
+    /// How many paths were dropped as infeasible when they forked.
+    pruned: usize,
+    /// The time taken by each solver started so far.
+    clocks: Vec<Rc<Cell<Duration>>>,
+    /// How many times each arm of each branch (by position) was taken.
+    visits: HashMap<(usize, usize), usize>,
+    rng: u64,
+    /// The budget that ran out, if one has.
+    pub exhausted: Option<BudgetKind>,
+}
+
+/// The scheduler of a run, shared by everything executed in it.
+pub type SchedulerRef = Rc<RefCell<Scheduler>>;
+
+/// A path waiting in a worklist, with the index of the statement it executes
+/// next.
+pub type Pending = (usize, usize);
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler::new(Strategy::default(), Budget::default())
+    }
+}
+
+impl Scheduler {
+    pub fn new(strategy: Strategy, budget: Budget) -> Self {
+        Scheduler {
+            strategy,
+            budget,
+            started: Instant::now(),
+            paths: 0,
+            pruned: 0,
+            clocks: Vec::new(),
+            visits: HashMap::new(),
+            rng: 0x2545_f491_4f6c_dd1d,
+            exhausted: None,
+        }
+    }
+
+    /// Seeds the random order of `Strategy::RandomPath`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        // xorshift never leaves zero
+        self.rng = seed.max(1);
+        self
+    }
+
+    /// Counts the time taken by a new solver towards the budget.
+    pub fn track(&mut self, clock: Rc<Cell<Duration>>) {
+        self.clocks.push(clock);
+    }
+
+    /// Records that `path` was forked from another path at the branch at
+    /// `pos`, taking its arm `arm`. Only feasible paths count towards the
+    /// budget.
+    pub fn forked(&mut self, engine: &mut [SymExEngine], path: usize, pos: usize, arm: usize) {
+        if engine[path].pi.satisfiable {
+            self.paths += 1;
+        }
+        self.took(engine, path, pos, arm);
+    }
+
+    /// Records that `path` took the arm `arm` of the branch at `pos`, which
+    /// prunes it if the arm cannot be taken.
+    pub fn took(&mut self, engine: &mut [SymExEngine], path: usize, pos: usize, arm: usize) {
+        if !engine[path].pi.satisfiable {
+            self.pruned += 1;
+        }
+        engine[path].depth += 1;
+        engine[path].branch = Some((pos, arm));
+        *self.visits.entry((pos, arm)).or_insert(0) += 1;
+    }
+
+    /// How many paths were dropped as infeasible at the branch they took.
+    pub fn pruned(&self) -> usize {
+        self.pruned
+    }
+
+    /// Returns the budget that has run out, if any.
+    pub fn check_budget(&mut self) -> Option<BudgetKind> {
+        if self.exhausted.is_none() {
+            let solver_time: Duration = self.clocks.iter().map(|clock| clock.get()).sum();
+            self.exhausted = if self.budget.max_paths.is_some_and(|max| self.paths >= max) {
+                Some(BudgetKind::Paths)
+            } else if self.budget.max_solver_time.is_some_and(|max| solver_time >= max) {
+                Some(BudgetKind::SolverTime)
+            } else if self.budget.max_time.is_some_and(|max| self.started.elapsed() >= max) {
+                Some(BudgetKind::Time)
+            } else {
+                None
+            };
+        }
+        self.exhausted
+    }
+
+    ///
+    /// Takes the state that steps next off `worklist`. Paths that can no
+    /// longer step (infeasible, returned, suspended or leaving a loop body by
+    /// `break` or `continue`) are dropped from it first. If the budget has
+    /// run out, every path left is suspended instead and `None` returned.
+    ///
+    pub fn pick(&mut self, engine: &mut [SymExEngine], worklist: &mut Vec<Pending>) -> Option<Pending> {
+        worklist.retain(|(id, _)| engine[*id].pi.satisfiable && !engine[*id].returned && !engine[*id].suspended && engine[*id].exit.is_none());
+        if worklist.is_empty() {
+            return None;
+        }
+
+        if self.check_budget().is_some() {
+            for (id, _) in worklist.drain(..) {
+                engine[id].suspended = true;
+            }
+            return None;
+        }
+
+        // ties go to the state that became pending first
+        let first_min = |key: &dyn Fn(usize) -> usize| {
+            (0..worklist.len()).min_by_key(|i| (key(worklist[*i].0), *i)).unwrap_or(0)
+        };
+        let i = match self.strategy {
+            Strategy::Dfs => worklist.len() - 1,
+            Strategy::Bfs => first_min(&|id| engine[id].depth as usize),
+            Strategy::RandomPath => (self.next_random() % worklist.len() as u64) as usize,
+            Strategy::Coverage => first_min(&|id| engine[id].branch.and_then(|branch| self.visits.get(&branch).copied()).unwrap_or(0)),
+        };
+        Some(worklist.remove(i))
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symex::{self, Budget, BudgetKind, Scheduler, SolverConfig, Strategy, SymExEngine};
+
+    fn engines(n: usize) -> Vec<SymExEngine> {
+        let mut engine = Vec::new();
+        let config = SolverConfig::new();
+        for _ in 0..n {
+            symex::new_engine(&mut engine, "f", &config).unwrap();
+        }
+        engine
+    }
+
+    /// The order `s` picks the paths 0 to 3 in, pending in that order.
+    fn order(s: &mut Scheduler, engine: &mut [SymExEngine]) -> Vec<usize> {
+        let mut worklist = vec![(0, 0), (1, 0), (2, 0), (3, 0)];
+        let mut order = Vec::new();
+        while let Some((id, _)) = s.pick(engine, &mut worklist) {
+            order.push(id);
+        }
+        order
+    }
+
+    #[test]
+    fn test_schedule() {
+        let mut engine = engines(4);
+
+        let mut s = Scheduler::new(Strategy::Dfs, Budget::new());
+        assert_eq!(order(&mut s, &mut engine), vec![3, 2, 1, 0]);
+
+        // 1 and 3 took branches taken once, 0 and 2 one taken twice
+        let mut s = Scheduler::new(Strategy::Coverage, Budget::new());
+        s.took(&mut engine, 0, 10, 0);
+        s.took(&mut engine, 2, 10, 0);
+        s.took(&mut engine, 1, 10, 1);
+        s.took(&mut engine, 3, 20, 0);
+        assert_eq!(order(&mut s, &mut engine), vec![1, 3, 0, 2]);
+
+        let mut s = Scheduler::new(Strategy::Bfs, Budget::new());
+        s.took(&mut engine, 1, 30, 0);
+        assert_eq!(order(&mut s, &mut engine), vec![0, 2, 3, 1]);
+
+        let mut s = Scheduler::new(Strategy::RandomPath, Budget::new()).with_seed(7);
+        let mut random = order(&mut s, &mut engine);
+        random.sort();
+        assert_eq!(random, vec![0, 1, 2, 3]);
+
+        // once the budget runs out every pending path is suspended
+        let mut s = Scheduler::new(Strategy::Dfs, Budget::new().with_max_paths(Some(1)));
+        s.forked(&mut engine, 1, 40, 0);
+        assert!(order(&mut s, &mut engine).is_empty());
+        assert_eq!(s.exhausted, Some(BudgetKind::Paths));
+        assert!(engine.iter().all(|e| e.suspended));
+    }
+}
@@ -0,0 +1,115 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::parser::Span;
+use crate::parser::parser::{PPos, PanicKind};
+use crate::symex::{substitute_lisp, IntTy, PathCondition, SymTerm};
+
+///
+/// The summaries computed so far, keyed by function name. A function maps to
+/// `None` while its summary is being computed (or could not be), in which case
+/// calls to it are inlined instead.
+///
+pub type SummaryCache = Rc<RefCell<HashMap<String, Option<Rc<FnSummary>>>>>;
+
+///
+/// What a function does in terms of its parameters, computed once by
+/// executing it on symbolic parameters and then instantiated at every call
+/// instead of executing the body again.
+///
+#[derive(Debug, Clone)]
+pub struct FnSummary {
+    /// The parameters of the function, which the terms below refer to.
+    pub params: Vec<(String, IntTy)>,
+    /// Every feasible path through the function that returns.
+    pub paths: Vec<SummaryPath>,
+    /// Every panic found in the function.
+    pub panics: Vec<SummaryPanic>,
+    /// Whether the budget of the run ran out while summarising, leaving
+    /// some of its paths out.
+    pub suspended: bool,
+}
+
+///
+/// A path through a summarised function: the solver assertions that take it
+/// and the value it returns.
+///
+#[derive(Debug, Clone)]
+pub struct SummaryPath {
+    pub asserts: Vec<String>,
+    /// The unconstrained ints (such as the values of unknown calls) the
+    /// assertions use besides the parameters, as (name, sort).
+    pub decls: Vec<(String, String)>,
+    pub ret: Option<SymTerm>,
+    /// Whether the path was cut off by the loop iteration limit.
+    pub truncated: bool,
+}
+
+///
+/// A panic in a summarised function and the solver assertions under which it
+/// happens.
+///
+#[derive(Debug, Clone)]
+pub struct SummaryPanic {
+    pub span: Span<PPos>,
+    pub kind: PanicKind,
+    pub asserts: Vec<String>,
+    pub decls: Vec<(String, String)>,
+}
+
+/// The constants declared in `pi` that are not one of `params`.
+pub fn local_decls(pi: &PathCondition, params: &[(String, IntTy)]) -> Vec<(String, String)> {
+    pi.decls().into_iter()
+        .filter(|(name, _)| !params.iter().any(|(p, _)| p == name))
+        .collect()
+}
+
+impl FnSummary {
+    /// Reads the summary of a path off the solver `pi` it ended with.
+    pub fn path(&self, pi: &PathCondition, ret: Option<SymTerm>, truncated: bool) -> SummaryPath {
+        SummaryPath { asserts: pi.assertions(), decls: local_decls(pi, &self.params), ret, truncated }
+    }
+
+    ///
+    /// Rewrites `asserts` and `ret` (in terms of the parameters and `decls`)
+    /// into terms of the call site by replacing every parameter with its
+    /// argument in `args` and declaring a fresh int in `pi` for every decl.
+    /// Returns the conjunction of the assertions and the rewritten `ret`.
+    ///
+    fn instantiate(&self, asserts: &[String], decls: &[(String, String)], ret: Option<&SymTerm>, args: &[SymTerm], pi: &mut PathCondition) -> (String, Option<SymTerm>) {
+        let mut names: HashMap<&str, SymTerm> = HashMap::new();
+        for ((param, _), arg) in self.params.iter().zip(args) {
+            names.insert(param, arg.clone());
+        }
+        for (name, sort) in decls {
+            let hint = name.trim_matches('|').split('!').next().unwrap_or("v");
+            names.insert(name, SymTerm::Var(pi.fresh_const(hint, sort)));
+        }
+
+        let rename = |term: &str| substitute_lisp(term, |symbol| names.get(symbol).map(SymTerm::to_lisp));
+        let cond = match asserts.len() {
+            0 => "true".to_string(),
+            1 => rename(&asserts[0]),
+            _ => format!("(and {})", asserts.iter().map(|a| rename(a)).collect::<Vec<_>>().join(" ")),
+        };
+        (cond, ret.map(|r| r.substitute(&|name| names.get(name).cloned())))
+    }
+}
+
+impl SummaryPath {
+    ///
+    /// Returns the condition under which a call to `summary` with the
+    /// arguments `args` (terms of the call site) takes this path, along with
+    /// what it returns.
+    ///
+    pub fn instantiate(&self, summary: &FnSummary, args: &[SymTerm], pi: &mut PathCondition) -> (String, Option<SymTerm>) {
+        summary.instantiate(&self.asserts, &self.decls, self.ret.as_ref(), args, pi)
+    }
+}
+
+impl SummaryPanic {
+    /// Returns the condition under which a call to `summary` with the
+    /// arguments `args` (terms of the call site) hits this panic.
+    pub fn instantiate(&self, summary: &FnSummary, args: &[SymTerm], pi: &mut PathCondition) -> String {
+        summary.instantiate(&self.asserts, &self.decls, None, args, pi).0
+    }
+}
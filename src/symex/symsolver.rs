@@ -1,122 +1,422 @@
 #![allow(unused)]
-static PATH_TO_SOLVER:&str = "z3\\bin\\z3.exe";
+use std::{cell::{Cell, RefCell}, rc::Rc};
+use std::time::{Duration, Instant};
+
 use rsmt2::*;
+use crate::symex::{IntTy, SolverConfig, SolverError, SymTerm};
+
+///
+/// An incremental SMT-LIB solver over bit-vectors: either a solver process
+/// driven through `rsmt2` or the built-in `BvSolver`.
+///
+pub trait SymSolver {
+    fn declare_const(&mut self, name: &str, sort: &str) -> SmtRes<()>;
+    fn push(&mut self, n: u8) -> SmtRes<()>;
+    fn pop(&mut self, n: u8) -> SmtRes<()>;
+    fn assert(&mut self, term: &str) -> SmtRes<()>;
+    /// Checks the assertions, returning `None` if the solver could not tell
+    /// whether they are satisfiable.
+    fn check_sat_or_unk(&mut self) -> SmtRes<Option<bool>>;
+    /// Returns the value of each of `terms` in the model found by the last
+    /// satisfiable check.
+    fn get_values(&mut self, terms: &[String]) -> SmtRes<Vec<(String, String)>>;
+}
+
+impl SymSolver for Solver<()> {
+    fn declare_const(&mut self, name: &str, sort: &str) -> SmtRes<()> {
+        Solver::declare_const(self, name, sort)
+    }
+
+    fn push(&mut self, n: u8) -> SmtRes<()> {
+        Solver::push(self, n)
+    }
+
+    fn pop(&mut self, n: u8) -> SmtRes<()> {
+        Solver::pop(self, n)
+    }
+
+    fn assert(&mut self, term: &str) -> SmtRes<()> {
+        Solver::assert(self, term)
+    }
+
+    fn check_sat_or_unk(&mut self) -> SmtRes<Option<bool>> {
+        Solver::check_sat_or_unk(self)
+    }
+
+    fn get_values(&mut self, terms: &[String]) -> SmtRes<Vec<(String, String)>> {
+        Solver::get_values(self, terms)
+    }
+}
+
+///
+/// One solver shared by every path forked from the same engine.
+/// Each assertion of the path last checked sits in a scope of its own, so
+/// switching to another path only pops the assertions the two do not share
+/// and pushes the other's. Constants are declared in the scope that is open
+/// and declared again below it when it is popped, so every path sees them.
+///
+pub struct SolverSession {
+    s: Box<dyn SymSolver>,
+    /// The assertions currently on the stack, one per scope.
+    loaded: Vec<String>,
+    /// Every constant declared so far as (name, sort, how many scopes deep it
+    /// is declared, or `None` until it is sent to the solver), in order.
+    /// Their number keeps fresh names unique.
+    declared: Vec<(String, String, Option<usize>)>,
+    /// How long the checks have taken so far.
+    clock: Rc<Cell<Duration>>,
+}
+
+impl SolverSession {
+    fn new(config: &SolverConfig) -> Result<Self, SolverError> {
+        Ok(SolverSession { s: config.spawn()?, loaded: Vec::new(), declared: Vec::new(), clock: Rc::default() })
+    }
+
+    /// Checks the assertions on the stack, counting the time it takes.
+    fn check(&mut self) -> SmtRes<Option<bool>> {
+        let start = Instant::now();
+        let res = self.s.check_sat_or_unk();
+        self.clock.set(self.clock.get() + start.elapsed());
+        res
+    }
+
+    /// Declares the constant `name` for every path. It is sent to the solver
+    /// by the next `load`, in the scope that is open then, and `unload`
+    /// declares it again if that scope is popped.
+    fn declare(&mut self, name: &str, sort: &str) {
+        self.declared.push((name.to_string(), sort.to_string(), None));
+    }
 
-pub struct SymSolver {
-    pub s: Solver<()>,
+    /// Pops scopes until only the first `len` assertions are left, declaring
+    /// the constants of the popped scopes again in the one left open.
+    fn unload(&mut self, len: usize) -> SmtRes<()> {
+        let mut n = self.loaded.len().saturating_sub(len);
+        while n > 0 {
+            let k = n.min(u8::MAX as usize);
+            self.s.pop(k as u8)?;
+            self.loaded.truncate(self.loaded.len() - k);
+            n -= k;
+        }
+
+        for (name, sort, depth) in self.declared.iter_mut().filter(|(.., depth)| depth.is_some_and(|depth| depth > len)) {
+            self.s.declare_const(name, sort)?;
+            *depth = Some(len);
+        }
+        Ok(())
+    }
+
+    /// Makes `asserts` the assertions on the stack, keeping the scopes of the
+    /// longest prefix already there, after sending the constants declared
+    /// since the last load.
+    fn load(&mut self, asserts: &[String]) -> SmtRes<()> {
+        let shared = self.loaded.iter().zip(asserts).take_while(|(a, b)| a == b).count();
+        self.unload(shared)?;
+        for (name, sort, depth) in self.declared.iter_mut().filter(|(.., depth)| depth.is_none()) {
+            self.s.declare_const(name, sort)?;
+            *depth = Some(self.loaded.len());
+        }
+        for assert in &asserts[shared..] {
+            self.s.push(1)?;
+            self.loaded.push(assert.clone());
+            self.s.assert(assert)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Whether some assertions can hold, with what the solver found if they can.
+/// `Unknown` when it could not tell (e.g. it timed out).
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckResult<T> {
+    Sat(T),
+    Unsat,
+    Unknown,
+}
+
+impl<T> CheckResult<T> {
+    /// What was found if the assertions can hold for certain.
+    pub fn sat(self) -> Option<T> {
+        match self {
+            CheckResult::Sat(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> CheckResult<U> {
+        match self {
+            CheckResult::Sat(value) => CheckResult::Sat(f(value)),
+            CheckResult::Unsat => CheckResult::Unsat,
+            CheckResult::Unknown => CheckResult::Unknown,
+        }
+    }
+}
+
+pub struct PathCondition {
+    session: Rc<RefCell<SolverSession>>,
     pub pi_str: String,
-    int_str: String,
-    assert_str: String,
+    decls: Vec<(String, String)>,
+    asserts: Vec<String>,
     pub satisfiable: bool,
+    /// Whether the solver failed on an assertion of this path, so that it
+    /// cannot tell whether the path is feasible.
+    pub unknown: bool,
 }
 
 
-impl SymSolver {
-    pub fn new() -> Self {
-        
-        SymSolver {
-            s: SmtConf::z3(PATH_TO_SOLVER).spawn(()).unwrap(),
+impl PathCondition {
+    /// Starts the solver described by `config` for a new engine.
+    pub fn new(config: &SolverConfig) -> Result<Self, SolverError> {
+        Ok(PathCondition {
+            session: Rc::new(RefCell::new(SolverSession::new(config)?)),
             pi_str: "true".to_string(),
-            int_str: "".to_string(),
-            assert_str: "".to_string(),
+            decls: Vec::new(),
+            asserts: Vec::new(),
             satisfiable: true,
+            unknown: false,
+        })
+    }
+
+    /// Returns a solver for a path forked from this one. It shares this
+    /// solver's process, so forking costs nothing on the solver's side.
+    pub fn copy_solver(&self) -> PathCondition {
+        PathCondition {
+            session: self.session.clone(),
+            pi_str: self.pi_str.clone(),
+            decls: self.decls.clone(),
+            asserts: self.asserts.clone(),
+            satisfiable: self.satisfiable,
+            unknown: self.unknown,
         }
     }
 
-    pub fn copy_solver(&self) -> SymSolver {
-        let mut s = SmtConf::z3(PATH_TO_SOLVER).spawn(()).unwrap();
-        let mut ints = self.int_str.split("#").collect::<Vec<&str>>();
-        if ints.len() > 0 {
-            ints.drain(0..1);
-            let mut i = 0;
-            while i < ints.len() {
-                s.declare_const(ints[i].clone(), "Int");
-                i = i + 1;
-            }
+    ///
+    /// Asserts `assert` for the rest of this path and checks whether the path
+    /// is still feasible. If the solver fails, the path is kept but marked as
+    /// unknown and the error is returned.
+    ///
+    pub fn add_assertion_to_solver(&mut self, assert: &str) -> Result<(), SolverError> {
+        self.asserts.push(assert.to_string());
+        if !self.satisfiable || self.unknown {
+            return Ok(());
         }
 
-        let mut asserts = self.assert_str.split("#").collect::<Vec<&str>>();
-        if asserts.len() > 0 {
-            asserts.drain(0..1);
-            let mut j = 0;
-            while j < asserts.len() {
-                s.assert(asserts[j].clone().to_string());
-                j = j + 1;
-            }
+        let mut session = self.session.borrow_mut();
+        // a query that timed out may still be satisfiable
+        match session.load(&self.asserts).and_then(|_| session.check()) {
+            Ok(Some(false)) => self.satisfiable = false,
+            Ok(_) => {},
+            Err(err) => {
+                self.unknown = true;
+                return Err(SolverError::Assert { assert: assert.to_string(), reason: err.to_string() });
+            },
         }
-        SymSolver {
-            s: s,
-            pi_str: self.pi_str.clone(),
-            int_str: self.int_str.clone(),
-            assert_str: self.assert_str.clone(),
-            satisfiable: self.satisfiable.clone(),
+        Ok(())
+    }
+
+    /// Checks whether `assert` can hold on top of the current assertions
+    /// without keeping it. Returns the value of each of the ints `names` if it
+    /// can, and `Unknown` if the solver could not tell or failed.
+    pub fn check_with(&mut self, assert: &str, names: &[String]) -> CheckResult<Vec<(String, String)>> {
+        if !self.satisfiable {
+            return CheckResult::Unsat;
+        }
+        if self.unknown {
+            return CheckResult::Unknown;
+        }
+
+        // `assert` gets a scope of its own like those of the path, which the
+        // next load pops
+        let mut session = self.session.borrow_mut();
+        if session.load(&self.asserts).and_then(|_| session.s.push(1)).is_err() {
+            self.unknown = true;
+            return CheckResult::Unknown;
+        }
+        session.loaded.push(assert.to_string());
+
+        match session.s.assert(assert).and_then(|_| session.check()) {
+            Ok(Some(true)) if names.is_empty() => CheckResult::Sat(Vec::new()),
+            Ok(Some(true)) => session.s.get_values(names).map_or(CheckResult::Unknown, CheckResult::Sat),
+            Ok(Some(false)) => CheckResult::Unsat,
+            Ok(None) | Err(_) => CheckResult::Unknown,
         }
     }
-    pub fn add_assertion_to_solver(&mut self, assert: &String) {
-        //println!("asserting {}", assert.clone());
-        self.s.assert(assert.clone()).unwrap();
-        self.assert_str = format!("{}#{}", self.assert_str, assert.clone());
-        let is_sat = self.s.check_sat();
-        match is_sat {
-            Ok(b, ..) => {
-                if !b{
-                    self.satisfiable = false;
-                }
-            }, 
-            Err(_) => {
-                println!("Error when attempting to assert {} with Z3", assert);
-                self.satisfiable = false;
-            },
-        }   
+
+    /// Whether `other` runs on the same solver as this path, as paths forked
+    /// from one engine do.
+    pub fn shares_solver(&self, other: &PathCondition) -> bool {
+        Rc::ptr_eq(&self.session, &other.session)
     }
 
-    pub fn load_solver(&self) -> Solver<()> {
-        let mut s = SmtConf::z3(PATH_TO_SOLVER).spawn(()).unwrap();
-        let ints = self.int_str.split("#");
-        for _ in ints {
-            //println!("{}", i);
-            //s.declare_const(i.clone(), "Int");
-        }
+    ///
+    /// Merges the path condition of `other`, which shares this solver, into
+    /// this one: the assertions both made before they were forked are kept
+    /// and the ones made since become the disjunction of the two. Returns the
+    /// condition under which the merged path is `other` rather than this one,
+    /// as a `Bool` constant declared equal to it: the assertions `other` made
+    /// since, or if it made none, those this one did not.
+    ///
+    pub fn merge(&mut self, other: &PathCondition) -> SymTerm {
+        let shared = self.asserts.iter().zip(&other.asserts).take_while(|(a, b)| a == b).count();
+        let guard = conjunction(&self.asserts[shared..]);
+        let other_guard = conjunction(&other.asserts[shared..]);
 
-        let asserts = self.assert_str.split("#");
-        for a in asserts {
-           // println!("{}", a);
-            let _ = s.assert(a.clone().to_string());
+        self.asserts.truncate(shared);
+        if guard != "true" && other_guard != "true" {
+            self.asserts.push(format!("(or {} {})", guard, other_guard));
+        }
+        for decl in &other.decls {
+            if !self.decls.contains(decl) {
+                self.decls.push(decl.clone());
+            }
         }
-        return s;
+        self.pi_str = format!("({}) || ({})", self.pi_str, other.pi_str);
+        self.satisfiable |= other.satisfiable;
+        self.unknown |= other.unknown;
+        let other_cond = match (guard.as_str(), other_guard.as_str()) {
+            ("true", "true") => return SymTerm::Bool(false),
+            (guard, "true") => format!("(not {})", guard),
+            (_, other_guard) => other_guard.to_string(),
+        };
+        let name = self.fresh_const("merge", "Bool");
+        self.asserts.push(format!("(= {} {})", name, other_cond));
+        SymTerm::Var(name)
     }
 
     pub fn to_string(&self) -> String {
         format!("{}", &self.pi_str)
     }
 
-    pub fn add_int(&mut self, v: String) {
-        let _ = self.s.declare_const(v.clone(), "Int");
-        self.int_str = format!("{}#{}", self.int_str, v.clone());
+    /// The time the checks of every path sharing this solver have taken.
+    pub fn clock(&self) -> Rc<Cell<Duration>> {
+        self.session.borrow().clock.clone()
+    }
+
+    /// Declares `v` as a bit-vector constant with the width of `ty`, or a
+    /// `Bool` constant if `ty` is `bool`.
+    pub fn add_int(&mut self, v: String, ty: IntTy) {
+        self.session.borrow_mut().declare(&v, &ty.sort());
+        self.decls.push((v, ty.sort()));
+    }
+
+    /// Declares a new int of type `ty` that is unconstrained, named after
+    /// `hint`, and returns its name.
+    pub fn fresh_int(&mut self, hint: &str, ty: IntTy) -> String {
+        self.fresh_const(hint, &ty.sort())
+    }
+
+    /// Declares a new unconstrained constant of the SMT-LIB sort `sort`,
+    /// named after `hint`, and returns its name.
+    pub fn fresh_const(&mut self, hint: &str, sort: &str) -> String {
+        let mut session = self.session.borrow_mut();
+        let name = format!("|{}!{}|", hint, session.declared.len());
+        session.declare(&name, sort);
+        self.decls.push((name.clone(), sort.to_string()));
+        name
     }
 
-    pub fn add_assertion_to_pi_str(&mut self, assert: &String) {
+    /// Returns every constant declared on this path as (name, sort).
+    pub fn decls(&self) -> Vec<(String, String)> {
+        self.decls.clone()
+    }
+
+    /// Returns every assertion made on this path, in order.
+    pub fn assertions(&self) -> Vec<String> {
+        self.asserts.clone()
+    }
+
+    pub fn add_assertion_to_pi_str(&mut self, assert: &str) {
         self.pi_str = self.pi_str.clone() + assert;
     }
 }
 
 
 
+/// The conjunction of `asserts` as one SMT-LIB term.
+fn conjunction(asserts: &[String]) -> String {
+    match asserts {
+        [] => "true".to_string(),
+        [assert] => assert.clone(),
+        _ => format!("(and {})", asserts.join(" ")),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::symex::SymSolver;
+    use std::rc::Rc;
+
+    use crate::symex::{PathCondition, IntTy, SolverConfig, CheckResult};
 
     #[test]
     pub fn test_lisp() -> Result<(), Box<dyn std::error::Error>> {
-        let mut s = SymSolver::new();
+        let mut s = PathCondition::new(&SolverConfig::new()).unwrap();
         //add any variables used in expressions here with the method detailed below
-        s.add_int("x".to_string());
-        s.add_int("y".to_string());
+        s.add_int("x".to_string(), IntTy::I32);
+        s.add_int("y".to_string(), IntTy::I32);
 
-        s.add_assertion_to_solver(&"y == x + 12".to_string());
+        s.add_assertion_to_solver("(= y (bvadd x (_ bv12 32)))")?;
 
         //let is_sat = s.check_sat()?;
         //assert!(is_sat);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_copy_solver_shares_session() {
+        let mut a = PathCondition::new(&SolverConfig::new()).unwrap();
+        a.add_int("x".to_string(), IntTy::I32);
+        a.add_assertion_to_solver("(bvsgt x (_ bv10 32))").unwrap();
+
+        // each path keeps its own assertions on the one process
+        let mut b = a.copy_solver();
+        b.add_assertion_to_solver("(bvslt x (_ bv5 32))").unwrap();
+        a.add_assertion_to_solver("(bvslt x (_ bv20 32))").unwrap();
+        assert!(!b.satisfiable);
+        assert!(a.satisfiable);
+
+        // a constant declared on one path is usable from the other
+        // declaring it keeps the scopes of the path last checked
+        let y = b.fresh_int("y", IntTy::I32);
+        assert_eq!(a.session.borrow().loaded, a.assertions());
+        assert!(a.check_with(&format!("(= {} x)", y), &["x".to_string()]).sat().is_some());
+
+        // and it outlives the scope it was declared in
+        assert_eq!(b.check_with(&format!("(= {} x)", y), &["x".to_string()]), CheckResult::Unsat);
+        assert!(a.check_with(&format!("(= {} x)", y), &["x".to_string()]).sat().is_some());
+        assert_eq!(a.assertions().len(), 2);
+        assert!(Rc::ptr_eq(&a.session, &b.session));
+    }
+
+    #[test]
+    pub fn test_solver_error_keeps_path() {
+        let mut s = PathCondition::new(&SolverConfig::new()).unwrap();
+        s.add_int("x".to_string(), IntTy::I32);
+        assert!(s.add_assertion_to_solver("(bvsgt x").is_err());
+        assert!(s.satisfiable);
+        assert!(s.unknown);
+        assert_eq!(s.check_with("(= x (_ bv1 32))", &[]), CheckResult::Unknown);
+
+        // so does a scope the solver cannot pop
+        let mut s = PathCondition::new(&SolverConfig::new()).unwrap();
+        s.add_int("x".to_string(), IntTy::I32);
+        s.session.borrow_mut().loaded.push("true".to_string());
+        assert!(s.add_assertion_to_solver("(bvsgt x (_ bv1 32))").is_err());
+        assert!(s.satisfiable);
+        assert!(s.unknown);
+    }
+
+    #[test]
+    pub fn test_merge_guard() {
+        let mut a = PathCondition::new(&SolverConfig::new()).unwrap();
+        a.add_int("x".to_string(), IntTy::I32);
+
+        // a path that asserted nothing since the fork only takes the inputs
+        // the other one does not
+        let mut b = a.copy_solver();
+        b.add_assertion_to_solver("(bvsgt x (_ bv10 32))").unwrap();
+        let guard = a.merge(&b).to_lisp();
+        assert_eq!(a.check_with(&format!("(and (bvsgt x (_ bv10 32)) (not {}))", guard), &[]), CheckResult::Unsat);
+        assert_eq!(a.check_with(&format!("(and (bvslt x (_ bv0 32)) {})", guard), &[]), CheckResult::Unsat);
+    }
+}
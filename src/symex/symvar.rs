@@ -1,67 +1,211 @@
+use crate::symex::SymTerm;
+
+/// 
+/// A fixed-width Rust integer type (`i32`, `u8`, `usize`, etc.) and how it is
+/// encoded as an SMT-LIB bit-vector.
+/// 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IntTy {
+    /// The width of the type in bits.
+    pub bits: u32,
+    /// Whether the type is signed (two's complement) or unsigned.
+    pub signed: bool,
+}
+
+impl IntTy {
+    /// The type Rust falls back to for unsuffixed integer literals.
+    pub const I32: IntTy = IntTy { bits: 32, signed: true };
+
+    /// The type of indexes and lengths.
+    pub const USIZE: IntTy = IntTy { bits: 64, signed: false };
+
+    /// `bool`, which is carried like a one-bit unsigned int but is a
+    /// `Bool` to the solver.
+    pub const BOOL: IntTy = IntTy { bits: 1, signed: false };
+
+    /// The type of the tag of an enum value, the index of its variant.
+    pub const TAG: IntTy = IntTy { bits: 32, signed: false };
+
+    /// Resolves a Rust integer type name (`i8` to `i128`, `u8` to `u128`,
+    /// `isize` and `usize`) to its bit-vector encoding.
+    pub fn from_type_str(t: &str) -> Option<IntTy> {
+        let t = t.trim();
+        let (signed, bits) = match t.split_at_checked(1) {
+            Some(("i", bits)) => (true, bits),
+            Some(("u", bits)) => (false, bits),
+            _ => return None,
+        };
+
+        let bits = match bits {
+            "size" => 64,
+            "8" | "16" | "32" | "64" | "128" => bits.parse().ok()?,
+            _ => return None,
+        };
+
+        Some(IntTy { bits, signed })
+    }
+
+    /// Like `from_type_str`, but also resolves `bool` to `IntTy::BOOL`.
+    pub fn from_scalar_str(t: &str) -> Option<IntTy> {
+        match t.trim() {
+            "bool" => Some(IntTy::BOOL),
+            t => IntTy::from_type_str(t),
+        }
+    }
+
+    /// Whether this is `bool` rather than an int.
+    pub fn is_bool(&self) -> bool {
+        *self == IntTy::BOOL
+    }
+
+    /// The SMT-LIB sort of this type, i.e. `(_ BitVec N)`, or `Bool`.
+    pub fn sort(&self) -> String {
+        if self.is_bool() {
+            return "Bool".to_string();
+        }
+        format!("(_ BitVec {})", self.bits)
+    }
+
+    /// The smallest value of this type.
+    pub fn min(&self) -> i128 {
+        if self.signed {
+            i128::MIN >> (128 - self.bits)
+        } else {
+            0
+        }
+    }
+
+    /// The largest value of this type.
+    pub fn max(&self) -> u128 {
+        if self.signed {
+            u128::MAX >> (129 - self.bits)
+        } else {
+            u128::MAX >> (128 - self.bits)
+        }
+    }
+
+    /// Whether `value` is a value of this type, i.e. is not wrapped by `lit`.
+    pub fn contains(&self, value: i128) -> bool {
+        value >= self.min() && (value < 0 || value as u128 <= self.max())
+    }
+
+    /// Returns the bit-vector literal for `value`, wrapped to the width of
+    /// this type. A `bool` is `true` unless `value` is 0.
+    pub fn lit(&self, value: i128) -> String {
+        if self.is_bool() {
+            return (value != 0).to_string();
+        }
+        format!("(_ bv{} {})", (value as u128) & self.max_unsigned(), self.bits)
+    }
+
+    /// Reads a bit-vector value printed by the solver (`#x..`, `#b..` or
+    /// `(_ bvN W)`) as a decimal Rust literal of this type, or a boolean
+    /// value as `true` or `false`.
+    pub fn parse_value(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        if self.is_bool() {
+            return matches!(value, "true" | "false").then(|| value.to_string());
+        }
+        let bits = if let Some(hex) = value.strip_prefix("#x") {
+            u128::from_str_radix(hex, 16).ok()?
+        } else if let Some(bin) = value.strip_prefix("#b") {
+            u128::from_str_radix(bin, 2).ok()?
+        } else {
+            value.strip_prefix("(_ bv")?.split_whitespace().next()?.parse().ok()?
+        };
+
+        if self.signed {
+            let shift = 128 - self.bits;
+            Some((((bits << shift) as i128) >> shift).to_string())
+        } else {
+            Some((bits & self.max_unsigned()).to_string())
+        }
+    }
+
+    fn max_unsigned(&self) -> u128 {
+        u128::MAX >> (128 - self.bits)
+    }
+}
+
+impl std::fmt::Display for IntTy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_bool() {
+            return f.write_str("bool");
+        }
+        write!(f, "{}{}", if self.signed { "i" } else { "u" }, self.bits)
+    }
+}
+
 #[derive(Clone)]
 pub struct SymVar {
     pub name: String,
-    pub var0: String,
-    pub lisp_var0: String,
-    pub min: i128,
-    pub max: u128,
-    pub prev: String,
+    /// The value of the variable in terms of the parameters.
+    pub var0: SymTerm,
+    pub ty: IntTy,
+    pub prev: SymTerm,
 }
 
 
 impl SymVar {
     pub fn new(s: String, t: String) -> Self {
+        let ty = Self::set_ty(t);
         SymVar {
             name: s.clone(),
-            var0: s.clone(),
-            lisp_var0: s.clone(),
-            min: Self::set_min(t.clone()),
-            max: Self::set_max(t.clone()),
-            prev: s.clone()
+            var0: SymTerm::var(s.clone()),
+            ty,
+            prev: SymTerm::var(s)
         }
     }
 
-    pub fn new_assign(s: String, t: String, assign: String, l_assign: String) -> Self {
+    pub fn new_assign(s: String, t: String, assign: SymTerm) -> Self {
+        let ty = Self::set_ty(t);
         SymVar {
-            name: s.clone(),
+            name: s,
             var0: assign.clone(),
-            lisp_var0: l_assign.clone(),
-            min: Self::set_min(t.clone()),
-            max: Self::set_max(t.clone()),
-            prev: assign.clone()
+            ty,
+            prev: assign
         }
     }
 
     pub fn to_string(&self) -> String {
         format!("{} in var0 = {};", &self.name, &self.var0)
-        //format!("{} in var0 = {}; range {}..{}", &self.name, &self.var0, &self.min, &self.max)
     }
 
-    fn set_min(t: String) -> i128 {
-        if !t.contains("i") {
-            return 0;
-        }
-
-        else {
-            let s = t.replace("i", "");
-            let exp: u32 = s.parse().expect("could not resolve to an int");
-            let base: i128 = 2;
-            return base.pow(exp - 1) * -1;
-        }
+    fn set_ty(t: String) -> IntTy {
+        IntTy::from_scalar_str(&t).expect("could not resolve to an int or bool")
     }
+}
 
-    fn set_max(t: String) -> u128 {
-        let base: u128 = 2;
-        if !t.contains("i") {
-            let s = t.replace("u", "");
-            let exp: u32 = s.parse().expect("could not resolve to an int");
-            return base.pow(exp) - 1;
-        }
+#[cfg(test)]
+mod tests {
+    use crate::symex::IntTy;
 
-        else {
-            let s = t.replace("i", "");
-            let exp: u32 = s.parse().expect("could not resolve to an int");
-            return base.pow(exp - 1) - 1
-        }
+    #[test]
+    fn test_int_ty() {
+        let i8_ty = IntTy::from_type_str("i8").unwrap();
+        assert_eq!(i8_ty.min(), -128);
+        assert_eq!(i8_ty.max(), 127);
+        assert_eq!(i8_ty.sort(), "(_ BitVec 8)");
+        assert_eq!(i8_ty.lit(-1), "(_ bv255 8)");
+
+        let usize_ty = IntTy::from_type_str("usize").unwrap();
+        assert_eq!(usize_ty, IntTy { bits: 64, signed: false });
+        assert_eq!(usize_ty.max(), u64::MAX as u128);
+        assert_eq!(IntTy::from_type_str("u128").unwrap().max(), u128::MAX);
+        assert_eq!(IntTy::from_type_str("bool"), None);
+
+        assert_eq!(i8_ty.parse_value("#xff").unwrap(), "-1");
+        assert_eq!(i8_ty.parse_value("#b00000101").unwrap(), "5");
+        assert_eq!(usize_ty.parse_value("(_ bv18446744073709551615 64)").unwrap(), "18446744073709551615");
+        assert_eq!(i8_ty.parse_value("x"), None);
+
+        let bool_ty = IntTy::from_scalar_str("bool").unwrap();
+        assert!(bool_ty.is_bool());
+        assert_eq!(bool_ty.sort(), "Bool");
+        assert_eq!(bool_ty.lit(1), "true");
+        assert_eq!(bool_ty.parse_value("false").unwrap(), "false");
+        assert_eq!(bool_ty.parse_value("#b1"), None);
+        assert_eq!(bool_ty.to_string(), "bool");
+        assert_eq!(IntTy::from_scalar_str("u16"), IntTy::from_type_str("u16"));
     }
-}
\ No newline at end of file
+}
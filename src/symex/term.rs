@@ -0,0 +1,230 @@
+use std::fmt::Display;
+
+use crate::parser::parser::BinOp;
+use crate::symex::IntTy;
+
+///
+/// A symbolic value: a typed tree of bit-vector and boolean operations over
+/// named variables. It prints as SMT-LIB with `to_lisp` and as Rust with
+/// `Display`, and variables are substituted by identifier, never by text.
+///
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SymTerm {
+    /// A variable (or an unknown value, such as the result of a call that
+    /// was not executed) by its SMT-LIB symbol.
+    Var(String),
+    /// An integer literal of type `ty`, wrapped to its width when printed.
+    Int(i128, IntTy),
+    Bool(bool),
+    /// `-term` on a bit-vector.
+    Neg(Box<SymTerm>),
+    /// The bitwise `!term` on a bit-vector.
+    BvNot(Box<SymTerm>),
+    /// The logical `!term` on a condition.
+    Not(Box<SymTerm>),
+    /// The logical `left ^ right` on conditions.
+    Xor(Box<SymTerm>, Box<SymTerm>),
+    /// `left op right` on operands of the given signedness.
+    BinOp { op: BinOp, signed: bool, left: Box<SymTerm>, right: Box<SymTerm> },
+    /// `term as to` where `term` has type `from`.
+    Cast { from: IntTy, to: IntTy, term: Box<SymTerm> },
+    /// `then` if `cond` holds and `els` otherwise.
+    Ite { cond: Box<SymTerm>, then: Box<SymTerm>, els: Box<SymTerm> },
+}
+
+impl SymTerm {
+    pub fn var(name: impl Into<String>) -> SymTerm {
+        SymTerm::Var(name.into())
+    }
+
+    pub fn not(term: SymTerm) -> SymTerm {
+        SymTerm::Not(Box::new(term))
+    }
+
+    pub fn bin(op: BinOp, signed: bool, left: SymTerm, right: SymTerm) -> SymTerm {
+        SymTerm::BinOp { op, signed, left: Box::new(left), right: Box::new(right) }
+    }
+
+    pub fn eq(left: SymTerm, right: SymTerm) -> SymTerm {
+        SymTerm::bin(BinOp::EqEq, false, left, right)
+    }
+
+    pub fn and(left: SymTerm, right: SymTerm) -> SymTerm {
+        SymTerm::bin(BinOp::And, false, left, right)
+    }
+
+    pub fn xor(left: SymTerm, right: SymTerm) -> SymTerm {
+        SymTerm::Xor(Box::new(left), Box::new(right))
+    }
+
+    pub fn ite(cond: SymTerm, then: SymTerm, els: SymTerm) -> SymTerm {
+        SymTerm::Ite { cond: Box::new(cond), then: Box::new(then), els: Box::new(els) }
+    }
+
+    /// Converts this term of type `from` into a term of type `to`, sign or
+    /// zero extending it when `to` is wider and truncating it when narrower.
+    /// A `bool` is left as it is, as only `as` turns it into an int.
+    pub fn cast(self, from: IntTy, to: IntTy) -> SymTerm {
+        if from.bits == to.bits || from.is_bool() || to.is_bool() {
+            self
+        } else {
+            SymTerm::Cast { from, to, term: Box::new(self) }
+        }
+    }
+
+    /// Returns a condition that holds when `left op right` (`+`, `-` or `*`
+    /// on terms of type `ty`) overflows. The operation is redone at double
+    /// width, where it cannot overflow, and compared with the result.
+    pub fn overflow(op: BinOp, ty: IntTy, left: SymTerm, right: SymTerm) -> SymTerm {
+        let wide = IntTy { bits: ty.bits * 2, signed: ty.signed };
+        let narrow = SymTerm::bin(op.clone(), ty.signed, left.clone(), right.clone());
+        SymTerm::not(SymTerm::eq(
+            narrow.cast(ty, wide),
+            SymTerm::bin(op, ty.signed, left.cast(ty, wide), right.cast(ty, wide)),
+        ))
+    }
+
+    /// Returns this term with every variable for which `value` returns a
+    /// replacement replaced.
+    pub fn substitute(&self, value: &impl Fn(&str) -> Option<SymTerm>) -> SymTerm {
+        use SymTerm::*;
+        match self {
+            Var(name) => value(name).unwrap_or_else(|| self.clone()),
+            Int(..) | Bool(_) => self.clone(),
+            Neg(term) => Neg(Box::new(term.substitute(value))),
+            BvNot(term) => BvNot(Box::new(term.substitute(value))),
+            Not(term) => Not(Box::new(term.substitute(value))),
+            Xor(left, right) => SymTerm::xor(left.substitute(value), right.substitute(value)),
+            BinOp { op, signed, left, right } => {
+                SymTerm::bin(op.clone(), *signed, left.substitute(value), right.substitute(value))
+            },
+            Cast { from, to, term } => Cast { from: *from, to: *to, term: Box::new(term.substitute(value)) },
+            Ite { cond, then, els } => SymTerm::ite(cond.substitute(value), then.substitute(value), els.substitute(value)),
+        }
+    }
+
+    /// Whether every variable in this term is one for which `known` holds.
+    pub fn only_vars(&self, known: &impl Fn(&str) -> bool) -> bool {
+        use SymTerm::*;
+        match self {
+            Var(name) => known(name),
+            Int(..) | Bool(_) => true,
+            Neg(term) | BvNot(term) | Not(term) | Cast { term, .. } => term.only_vars(known),
+            Xor(left, right) | BinOp { left, right, .. } => left.only_vars(known) && right.only_vars(known),
+            Ite { cond, then, els } => cond.only_vars(known) && then.only_vars(known) && els.only_vars(known),
+        }
+    }
+
+    /// Returns this term in SMT-LIB.
+    pub fn to_lisp(&self) -> String {
+        use SymTerm::*;
+        match self {
+            Var(name) => name.clone(),
+            Int(value, ty) => ty.lit(*value),
+            Bool(b) => b.to_string(),
+            Neg(term) => format!("(bvneg {})", term.to_lisp()),
+            BvNot(term) => format!("(bvnot {})", term.to_lisp()),
+            Not(term) => format!("(not {})", term.to_lisp()),
+            Xor(left, right) => format!("(xor {} {})", left.to_lisp(), right.to_lisp()),
+            BinOp { op: self::BinOp::NotEq, left, right, .. } => {
+                format!("(not (= {} {}))", left.to_lisp(), right.to_lisp())
+            },
+            BinOp { op, signed, left, right } => {
+                format!("({} {} {})", op.bv_op(*signed), left.to_lisp(), right.to_lisp())
+            },
+            Cast { from, to, term } if to.bits > from.bits => {
+                let ext = if from.signed { "sign_extend" } else { "zero_extend" };
+                format!("((_ {} {}) {})", ext, to.bits - from.bits, term.to_lisp())
+            },
+            Cast { to, term, .. } => format!("((_ extract {} 0) {})", to.bits - 1, term.to_lisp()),
+            Ite { cond, then, els } => format!("(ite {} {} {})", cond.to_lisp(), then.to_lisp(), els.to_lisp()),
+        }
+    }
+
+    /// Writes `term`, in parentheses if it is an operator application.
+    fn fmt_operand(term: &SymTerm, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match term {
+            SymTerm::BinOp { .. } | SymTerm::Xor(..) | SymTerm::Cast { .. } | SymTerm::Ite { .. } => write!(f, "({})", term),
+            _ => Display::fmt(term, f),
+        }
+    }
+}
+
+impl Display for SymTerm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use SymTerm::*;
+        match self {
+            Var(name) => f.write_str(name.trim_matches('|')),
+            Int(value, ty) => {
+                let lit = ty.lit(*value);
+                f.write_str(&ty.parse_value(&lit).unwrap_or(lit))
+            },
+            Bool(b) => Display::fmt(b, f),
+            Neg(term) => {
+                f.write_str("-")?;
+                SymTerm::fmt_operand(term, f)
+            },
+            BvNot(term) | Not(term) => {
+                f.write_str("!")?;
+                SymTerm::fmt_operand(term, f)
+            },
+            BinOp { op, left, right, .. } => {
+                let op = match op {
+                    self::BinOp::EqEq => "==",
+                    self::BinOp::NotEq => "!=",
+                    op => op.as_ref(),
+                };
+                SymTerm::fmt_operand(left, f)?;
+                write!(f, " {} ", op)?;
+                SymTerm::fmt_operand(right, f)
+            },
+            Xor(left, right) => {
+                SymTerm::fmt_operand(left, f)?;
+                f.write_str(" ^ ")?;
+                SymTerm::fmt_operand(right, f)
+            },
+            Cast { to, term, .. } => {
+                SymTerm::fmt_operand(term, f)?;
+                write!(f, " as {}", to)
+            },
+            Ite { cond, then, els } => write!(f, "if {} {{ {} }} else {{ {} }}", cond, then, els),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parser::BinOp;
+    use crate::symex::{IntTy, SymTerm};
+
+    #[test]
+    fn test_term() {
+        let i8_ty = IntTy::from_type_str("i8").unwrap();
+        let x = SymTerm::var("x");
+        let y = SymTerm::var("y");
+
+        assert_eq!(x.clone().cast(i8_ty, IntTy::I32).to_lisp(), "((_ sign_extend 24) x)");
+        assert_eq!(x.clone().cast(IntTy::I32, i8_ty).to_lisp(), "((_ extract 7 0) x)");
+        assert_eq!(
+            SymTerm::overflow(BinOp::Add, i8_ty, x.clone(), y.clone()).to_lisp(),
+            "(not (= ((_ sign_extend 8) (bvadd x y)) (bvadd ((_ sign_extend 8) x) ((_ sign_extend 8) y))))"
+        );
+
+        // `xy` is a variable of its own, not `x` followed by `y`
+        let xy = SymTerm::bin(BinOp::Mul, true, SymTerm::var("xy"), SymTerm::bin(BinOp::Add, true, x.clone(), SymTerm::Int(-1, i8_ty)));
+        let value = |name: &str| (name == "x").then_some(SymTerm::Int(3, i8_ty));
+        let xy = xy.substitute(&value);
+        assert_eq!(xy.to_lisp(), "(bvmul xy (bvadd (_ bv3 8) (_ bv255 8)))");
+        assert_eq!(xy.to_string(), "xy * (3 + -1)");
+
+        let cond = SymTerm::not(SymTerm::bin(BinOp::NotEq, true, SymTerm::var("|f(x)|"), x));
+        assert_eq!(cond.to_lisp(), "(not (not (= |f(x)| x)))");
+        assert_eq!(cond.to_string(), "!(f(x) != x)");
+
+        let (a, b) = (SymTerm::var("a"), SymTerm::var("b"));
+        let flags = SymTerm::bin(BinOp::Or, false, SymTerm::xor(a.clone(), b.clone()), SymTerm::not(a.clone()));
+        assert_eq!(flags.to_lisp(), "(or (xor a b) (not a))");
+        assert_eq!(flags.to_string(), "(a ^ b) || !a");
+        assert_eq!(a.clone().cast(IntTy::BOOL, IntTy::I32), a);
+    }
+}
@@ -0,0 +1,277 @@
+use std::rc::Rc;
+use std::fmt::Write;
+
+use crate::parser::parser::{enum_variants, RCrate, RItem, RFn, RStruct, RType, Execute, ExecuteArgs, SymexRes};
+use crate::symex::{IntTy, SymModel};
+
+/// 
+/// Symbolically executes every function in `krate` and returns a
+/// `#[cfg(test)]` module per function with one `#[test]` for each distinct
+/// path found: paths that finish call the function with the path's inputs
+/// and check the value it returns, and paths that panic are marked
+/// `#[should_panic]`. `main` is skipped, and so are functions whose
+/// parameters cannot be written out as literals (see `literal`), with a
+/// comment saying so.
+/// 
+pub fn gen_tests(krate: &RCrate, args: ExecuteArgs<str>) -> String {
+    let mut out = String::new();
+    let text = args.store;
+    let args = args.with_fns(Rc::new(krate.fn_map(text))).with_structs(Rc::new(krate.struct_map(text))).with_enums(Rc::new(krate.enum_map(text)));
+
+    for item in krate.items.iter() {
+        let RItem::Fn { val, .. } = item else {
+            continue;
+        };
+        let fn_name = val.id.into_string(text);
+        if fn_name == "main" {
+            continue;
+        }
+
+        // only ints and bools, and arrays, tuples, structs and enums of
+        // them, can be written back out as arguments
+        if !val.args.iter().all(|arg| arg.ident().is_some() && literal(&arg.ty, "", &SymModel::default(), &args).is_some()) {
+            let _ = writeln!(out, "// no tests for `{}`: its parameters cannot all be written out as literals\n", fn_name);
+            continue;
+        }
+
+        let mut engine = Vec::new();
+        let res = match item.execute(&mut engine, args.clone()) {
+            Ok(res) => res.res,
+            Err(_) => continue,
+        };
+
+        out.push_str(&gen_fn_tests(val, &fn_name, &args, &res));
+    }
+
+    out
+}
+
+/// Returns the test module for the function `val` from its execution results.
+fn gen_fn_tests(val: &RFn, fn_name: &str, args: &ExecuteArgs<str>, res: &[SymexRes]) -> String {
+    // (attribute, body) of each test, without duplicates since different
+    // paths often share inputs
+    let mut tests: Vec<(String, String)> = Vec::new();
+    // the calls whose return value is not known, which would check nothing
+    let mut unchecked: Vec<String> = Vec::new();
+    let returns = val.ret_type.is_some();
+
+    for r in res {
+        let test = match r {
+            // truncated paths would assert what the function does after
+            // being cut off in a loop, not what it really does
+            SymexRes::Path { truncated: true, .. } => continue,
+            SymexRes::Path { model, ret: Some(ret), .. } => {
+                let call = gen_call(val, fn_name, args, model);
+                let body = match ret.as_str() {
+                    "true" => format!("assert!({});", call),
+                    "false" => format!("assert!(!{});", call),
+                    _ => format!("assert_eq!({}, {});", call, ret),
+                };
+                (String::new(), body)
+            },
+            SymexRes::Path { model, .. } if returns => {
+                let call = gen_call(val, fn_name, args, model);
+                if !unchecked.contains(&call) {
+                    unchecked.push(call);
+                }
+                continue;
+            },
+            SymexRes::Path { model, .. } => {
+                (String::new(), format!("{};", gen_call(val, fn_name, args, model)))
+            },
+            SymexRes::Panic { kind, model, .. } => {
+                (format!("    #[should_panic(expected = \"{}\")]\n", kind), format!("{};", gen_call(val, fn_name, args, model)))
+            },
+            _ => continue,
+        };
+
+        if !tests.contains(&test) {
+            tests.push(test);
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "#[cfg(test)]\nmod {}_symex_tests {{\n    use super::*;", fn_name);
+    for (i, (attr, body)) in tests.iter().enumerate() {
+        let _ = write!(out, "\n    #[test]\n{}    fn path_{}() {{\n        {}\n    }}\n", attr, i, body);
+    }
+    for call in unchecked {
+        let _ = write!(out, "\n    // no test for {}: the value it returns is not known\n", call);
+    }
+    out.push_str("}\n\n");
+    out
+}
+
+/// Returns a call to `val` with the values of its parameters in `model`.
+fn gen_call(val: &RFn, fn_name: &str, args: &ExecuteArgs<str>, model: &SymModel) -> String {
+    let values: Vec<String> = val.args.iter()
+        .filter_map(|arg| literal(&arg.ty, &arg.ident()?.into_string(args.store), model, args))
+        .collect();
+    format!("{}({})", fn_name, values.join(", "))
+}
+
+/// 
+/// Returns a literal of type `ty` for the parameter, or part of one, named
+/// `path` in `model`: an int or bool, or an array, tuple, struct or enum
+/// built from the values of its parts, named as `RType::declare` names
+/// them. A part the model leaves out, as nothing constrains it, is `0` or
+/// `false`, and an enum without a variant takes its first. `None` for
+/// types that cannot be written out, like floats and references.
+/// 
+fn literal(ty: &RType, path: &str, model: &SymModel, args: &ExecuteArgs<str>) -> Option<String> {
+    let text = args.store;
+    let part = |sep: &str, name: &str, ty: &RType| literal(ty, &format!("{}{}{}", path, sep, name), model, args);
+
+    if let Some(int_ty) = IntTy::from_scalar_str(&ty.into_string(text)) {
+        let default = if int_ty.is_bool() { "false" } else { "0" };
+        return Some(model.get(path).unwrap_or(default).to_string());
+    }
+    if let RType::Template { name, args: ty_args, .. } = ty {
+        let name = name.into_string(text);
+        if let Some(variants) = enum_variants(text, &args.enums, &name, ty_args) {
+            let (variant, fields) = variants.iter().find(|(variant, _)| model.get(path) == Some(variant.as_str())).or(variants.first())?;
+            let fields = fields.iter()
+                .map(|(field, ty)| Some((field.clone(), part(&format!(".{}.", variant), field, (*ty)?)?)))
+                .collect::<Option<Vec<_>>>()?;
+            // the variants of `Option` and `Result` are in the prelude, and
+            // have their fields by position
+            let declared = args.enums.get(&name).and_then(|declared| declared.variants.iter().find(|v| v.ident().into_string(text) == *variant));
+            return Some(match declared {
+                Some(declared) => fields_literal(&format!("{}::{}", name, variant), fields, matches!(declared, RStruct::Struct { .. })),
+                None => fields_literal(variant, fields, false),
+            });
+        }
+        if let Some(declared) = args.structs.get(&name) {
+            let fields = declared.fields(text).into_iter()
+                .map(|(field, ty)| Some((field.clone(), part(".", &field, ty)?)))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(fields_literal(&name, fields, matches!(declared, RStruct::Struct { .. })));
+        }
+        return None;
+    }
+    match ty {
+        RType::Tuple { types, .. } => {
+            let items = types.iter().enumerate().map(|(i, ty)| part(".", &i.to_string(), ty)).collect::<Option<Vec<_>>>()?;
+            Some(match items.len() {
+                1 => format!("({},)", items[0]),
+                _ => format!("({})", items.join(", ")),
+            })
+        },
+        RType::Array { item_type, .. } => {
+            let (_, len) = ty.array_of(text)?;
+            let items = (0..len).map(|i| part("", &format!("[{}]", i), item_type)).collect::<Option<Vec<_>>>()?;
+            Some(format!("[{}]", items.join(", ")))
+        },
+        RType::Template { .. } => None,
+    }
+}
+
+/// Returns `name` with the `fields` of a struct or enum variant, by name in
+/// braces if `braced` and by position in parentheses otherwise.
+fn fields_literal(name: &str, fields: Vec<(String, String)>, braced: bool) -> String {
+    if fields.is_empty() {
+        return name.to_string();
+    }
+    let fields: Vec<String> = match braced {
+        true => fields.into_iter().map(|(field, value)| format!("{}: {}", field, value)).collect(),
+        false => fields.into_iter().map(|(_, value)| value).collect(),
+    };
+    match braced {
+        true => format!("{} {{ {} }}", name, fields.join(", ")),
+        false => format!("{}({})", name, fields.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parser::{parse_file, PPos, PanicKind, BinOp, RItem, SymexRes, ExecuteArgs};
+    use crate::parser::{ParseResult, Span};
+    use crate::symex::SymModel;
+
+    use super::{gen_tests, gen_fn_tests};
+
+    #[test]
+    fn test_gen_fn_tests() {
+        let s = "fn f(x: i32, y: u8) -> i32 { x + 1 }";
+        let krate = match parse_file(s) {
+            ParseResult::Okay(value, _) => value,
+            _ => panic!("could not parse"),
+        };
+        let RItem::Fn { val, .. } = &krate.items[0] else { panic!("Expected a fn") };
+
+        let model = SymModel { values: vec![("x".to_string(), "2147483647".to_string()), ("y".to_string(), "0".to_string())] };
+        let res = vec![
+            SymexRes::Panic { span: Span::new(PPos::new(), PPos::new()), kind: PanicKind::Overflow(BinOp::Add), model: model.clone() },
+            SymexRes::Path { path: 0, model: SymModel { values: vec![("x".to_string(), "-1".to_string())] }, ret: None, truncated: false },
+            SymexRes::Path { path: 1, model: SymModel { values: vec![("x".to_string(), "-1".to_string())] }, ret: None, truncated: false },
+            SymexRes::Path { path: 2, model: SymModel { values: vec![("x".to_string(), "4".to_string())] }, ret: Some("5".to_string()), truncated: false },
+            SymexRes::Path { path: 3, model: SymModel { values: vec![("x".to_string(), "101".to_string())] }, ret: None, truncated: true },
+        ];
+
+        assert_eq!(gen_fn_tests(val, "f", &ExecuteArgs::new(s), &res), "\
+#[cfg(test)]
+mod f_symex_tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = \"attempt to add with overflow\")]
+    fn path_0() {
+        f(2147483647, 0);
+    }
+
+    #[test]
+    fn path_1() {
+        assert_eq!(f(4, 0), 5);
+    }
+
+    // no test for f(-1, 0): the value it returns is not known
+}
+
+");
+    }
+
+    #[test]
+    fn test_gen_tests_skips_non_int_params() {
+        let s = "fn f(x: i32) { } fn g(b: bool, x: i32) { } fn h(y: f64) { }";
+        let krate = match parse_file(s) {
+            ParseResult::Okay(value, _) => value,
+            _ => panic!("could not parse"),
+        };
+
+        let out = gen_tests(&krate, ExecuteArgs::new(s));
+        assert!(out.contains("mod f_symex_tests"));
+        assert!(out.contains("mod g_symex_tests"));
+        assert!(!out.contains("mod h_symex_tests"));
+        assert!(out.contains("// no tests for `h`"));
+        // nothing constrains b, and a bool is written as one
+        assert!(out.contains("g(false, 0);"), "{}", out);
+    }
+
+    #[test]
+    fn test_gen_tests_compound_params() {
+        let s = "
+struct Point { x: i32, y: i32 }
+enum Shape { Dot, Circle(i32), Rect { w: u8, h: u8 } }
+fn fa(a: [i32; 3], i: usize) -> i32 { 10 / a[i] }
+fn ft(t: (i32, bool)) -> i32 { if t.1 { 100 / t.0 } else { 0 } }
+fn fs(p: Point) -> i32 { 100 / (p.x - p.y) }
+fn fo(o: Option<i32>) -> i32 { match o { Some(v) => 100 / v, None => 1 } }
+fn fe(s: Shape) -> u8 { match s { Shape::Dot => 0, Shape::Circle(_) => 1, Shape::Rect { w, h } => w / h } }
+";
+        let krate = match parse_file(s) {
+            ParseResult::Okay(value, _) => value,
+            _ => panic!("could not parse"),
+        };
+
+        // the values of the parts of each parameter make up its literal
+        let out = gen_tests(&krate, ExecuteArgs::new(s));
+        assert!(out.contains("fa([0, 0, 0], 0);"), "{}", out);
+        assert!(out.contains("fa([0, 0, 0], 3);"), "{}", out);
+        assert!(out.contains("ft((0, true));"), "{}", out);
+        assert!(out.contains("fs(Point { x: 0, y: 0 });"), "{}", out);
+        assert!(out.contains("fo(Some(0));"), "{}", out);
+        assert!(out.contains("assert_eq!(fo(None), 1);"), "{}", out);
+        assert!(out.contains("assert_eq!(fe(Shape::Dot), 0);"), "{}", out);
+        assert!(out.contains("fe(Shape::Rect { w: 0, h: 0 });"), "{}", out);
+    }
+}